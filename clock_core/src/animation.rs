@@ -0,0 +1,581 @@
+//! Animating values over time, modelled on the Windows Animation Manager
+//! (`IUIAnimationManager`) but in plain Rust, so it runs and can be tested
+//! on any platform.
+//!
+//! A [`Manager`] owns the schedule of the [`Variable`]s it creates. Every
+//! frame the application calls [`Manager::update`] with the current time in
+//! seconds and then reads the variables' values.
+
+mod storyboard;
+mod transition;
+
+use std::{cell::Cell, fmt, rc::Rc};
+
+pub use storyboard::{
+    Keyframe, Repeat, RepeatMode, Storyboard, StoryboardError, TransitionId,
+};
+pub use transition::{InvalidTransition, Sample, Transition};
+
+/// A value animated by a [`Manager`], like `IUIAnimationVariable`.
+///
+/// Clones refer to the same variable, so the application can keep one to
+/// read while the manager updates another.
+#[derive(Clone, Debug)]
+pub struct Variable {
+    sample: Rc<Cell<Sample>>,
+}
+
+impl Variable {
+    pub fn value(&self) -> f64 {
+        self.sample.get().value
+    }
+
+    /// How fast the value is changing, in units per second.
+    pub fn velocity(&self) -> f64 {
+        self.sample.get().velocity
+    }
+
+    pub fn sample(&self) -> Sample {
+        self.sample.get()
+    }
+
+    fn is(&self, other: &Variable) -> bool {
+        Rc::ptr_eq(&self.sample, &other.sample)
+    }
+}
+
+/// Whether [`Manager::update`] changed any variable, like
+/// `UI_ANIMATION_UPDATE_RESULT`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpdateResult {
+    NoChange,
+    VariablesChanged,
+}
+
+/// What a new storyboard may do to a scheduled one that animates the same
+/// variables, like `UI_ANIMATION_PRIORITY_EFFECT`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriorityEffect {
+    /// Drop the scheduled storyboard if it hasn't started yet.
+    Cancel,
+    /// Stop the scheduled storyboard animating the variables the new one
+    /// animates, from when the new one is scheduled.
+    Trim,
+    /// Let a storyboard that repeats forever finish its current repetition,
+    /// then start the new one.
+    Conclude,
+}
+
+/// The new storyboard would have to wait for one that repeats forever.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InsufficientPriority;
+
+impl fmt::Display for InsufficientPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a storyboard that never ends animates the same variables")
+    }
+}
+
+impl std::error::Error for InsufficientPriority {}
+
+type PriorityComparison = dyn Fn(u32, u32, PriorityEffect) -> bool;
+
+/// Schedules storyboards of variables and moves them along as time passes.
+#[derive(Default)]
+pub struct Manager {
+    // In the order they were scheduled in.
+    scheduled: Vec<Scheduled>,
+    time: f64,
+    comparison: Option<Box<PriorityComparison>>,
+}
+
+#[derive(Debug)]
+struct Scheduled {
+    storyboard: Storyboard,
+    start: f64,
+    end: f64,
+    // Where the variables were when the storyboard started.
+    initial: Option<Vec<Sample>>,
+    // When every variable was handed over to a later storyboard.
+    trimmed: Vec<f64>,
+}
+
+impl Scheduled {
+    fn shares_variables(&self, storyboard: &Storyboard) -> bool {
+        self.storyboard
+            .variables
+            .iter()
+            .any(|variable| storyboard.variables.iter().any(|v| v.is(variable)))
+    }
+}
+
+impl fmt::Debug for Manager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Manager")
+            .field("scheduled", &self.scheduled)
+            .field("time", &self.time)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Manager {
+    pub fn new() -> Self {
+        Manager::default()
+    }
+
+    /// A variable at rest at `initial_value`.
+    pub fn create_variable(&self, initial_value: f64) -> Variable {
+        Variable {
+            sample: Rc::new(Cell::new(Sample::at_rest(initial_value))),
+        }
+    }
+
+    /// Decides how a new storyboard resolves conflicts with scheduled
+    /// ones, like the `Set*PriorityComparison` methods of
+    /// `IUIAnimationManager` rolled into one.
+    ///
+    /// The comparison is called with the tags of the scheduled storyboard
+    /// and the new one and returns whether the new one may have the effect.
+    pub fn set_priority_comparison(
+        &mut self,
+        comparison: impl Fn(u32, u32, PriorityEffect) -> bool + 'static,
+    ) {
+        self.comparison = Some(Box::new(comparison));
+    }
+
+    /// Starts `transition` of `variable` at `time`, like
+    /// `IUIAnimationManager::ScheduleTransition`.
+    pub fn schedule_transition(
+        &mut self,
+        variable: &Variable,
+        transition: Transition,
+        time: f64,
+    ) -> Result<(), InsufficientPriority> {
+        let mut storyboard = Storyboard::new();
+        storyboard.add_transition(variable, transition);
+        self.schedule(storyboard, time)
+    }
+
+    /// Starts `storyboard` at `time`, like `IUIAnimationStoryboard::Schedule`.
+    ///
+    /// Scheduled storyboards that animate the same variables are cancelled,
+    /// trimmed or concluded if the priority comparison allows it, in that
+    /// order. Otherwise, as the Animation Manager does when it hasn't been
+    /// told how to resolve conflicts, they finish first and `storyboard`
+    /// starts after them.
+    pub fn schedule(
+        &mut self,
+        storyboard: Storyboard,
+        time: f64,
+    ) -> Result<(), InsufficientPriority> {
+        let comparison = self.comparison.as_deref();
+        let new = storyboard.tag;
+        let has_priority = |scheduled: &Scheduled, effect| {
+            comparison.is_some_and(|has_priority| {
+                has_priority(scheduled.storyboard.tag, new, effect)
+            })
+        };
+
+        let mut start = time;
+        let mut cancelled = Vec::new();
+        for (index, scheduled) in self.scheduled.iter_mut().enumerate() {
+            if !scheduled.shares_variables(&storyboard) {
+                continue;
+            }
+
+            if scheduled.initial.is_none()
+                && scheduled.start > time
+                && has_priority(scheduled, PriorityEffect::Cancel)
+            {
+                cancelled.push(index);
+            } else if has_priority(scheduled, PriorityEffect::Trim) {
+                for (variable, trimmed) in scheduled
+                    .storyboard
+                    .variables
+                    .iter()
+                    .zip(&mut scheduled.trimmed)
+                {
+                    if storyboard.variables.iter().any(|v| v.is(variable)) {
+                        *trimmed = trimmed.min(time);
+                    }
+                }
+            } else {
+                if scheduled.end.is_infinite()
+                    && has_priority(scheduled, PriorityEffect::Conclude)
+                {
+                    let elapsed = (time - scheduled.start).max(0.0);
+                    scheduled.storyboard.conclude(elapsed);
+                    scheduled.end = scheduled.start
+                        + scheduled
+                            .storyboard
+                            .duration()
+                            .unwrap_or(f64::INFINITY);
+                }
+                start = start.max(scheduled.end);
+            }
+        }
+        if start.is_infinite() {
+            return Err(InsufficientPriority);
+        }
+        for index in cancelled.into_iter().rev() {
+            self.scheduled.remove(index);
+        }
+
+        let end = storyboard
+            .duration()
+            .map_or(f64::INFINITY, |duration| start + duration);
+        let trimmed = vec![f64::INFINITY; storyboard.variables.len()];
+        self.scheduled.push(Scheduled {
+            storyboard,
+            start,
+            end,
+            initial: None,
+            trimmed,
+        });
+        Ok(())
+    }
+
+    /// Moves every variable to where it is at `time`, in seconds on the
+    /// same clock the storyboards were scheduled with. Time doesn't go
+    /// backwards, so earlier times than the last update are ignored.
+    pub fn update(&mut self, time: f64) -> UpdateResult {
+        if time < self.time {
+            return UpdateResult::NoChange;
+        }
+        self.time = time;
+
+        // Storyboards start from wherever the variables are at their start,
+        // so everything else is brought up to it first.
+        let mut result = UpdateResult::NoChange;
+        loop {
+            let next = self
+                .scheduled
+                .iter()
+                .filter(|scheduled| scheduled.initial.is_none())
+                .map(|scheduled| scheduled.start)
+                .fold(f64::INFINITY, f64::min);
+            if next > time {
+                break;
+            }
+
+            self.advance(next, &mut result);
+            for scheduled in &mut self.scheduled {
+                if scheduled.initial.is_none() && scheduled.start == next {
+                    scheduled.initial = Some(
+                        scheduled
+                            .storyboard
+                            .variables
+                            .iter()
+                            .map(Variable::sample)
+                            .collect(),
+                    );
+                }
+            }
+        }
+        self.advance(time, &mut result);
+
+        result
+    }
+
+    fn advance(&mut self, time: f64, result: &mut UpdateResult) {
+        self.scheduled.retain(|scheduled| {
+            let initial = match &scheduled.initial {
+                Some(initial) => initial,
+                None => return true,
+            };
+
+            let elapsed = time.min(scheduled.end) - scheduled.start;
+            let samples = scheduled.storyboard.sample(initial, elapsed);
+            for ((variable, sample), &trimmed) in scheduled
+                .storyboard
+                .variables
+                .iter()
+                .zip(samples)
+                .zip(&scheduled.trimmed)
+            {
+                if time <= trimmed && sample != variable.sample() {
+                    variable.sample.set(sample);
+                    *result = UpdateResult::VariablesChanged;
+                }
+            }
+
+            let last =
+                scheduled.trimmed.iter().fold(0.0, |a: f64, &b| a.max(b));
+            time < scheduled.end && time < last
+        });
+    }
+
+    /// Whether any storyboard is running or scheduled.
+    pub fn is_busy(&self) -> bool {
+        !self.scheduled.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variables_move_only_on_update() {
+        let mut manager = Manager::new();
+        let variable = manager.create_variable(0.0);
+        manager
+            .schedule_transition(
+                &variable,
+                Transition::linear(2.0, 10.0).unwrap(),
+                1.0,
+            )
+            .unwrap();
+
+        assert!(manager.is_busy());
+        assert_eq!(variable.value(), 0.0);
+        assert_eq!(manager.update(1.0), UpdateResult::NoChange);
+
+        assert_eq!(manager.update(2.0), UpdateResult::VariablesChanged);
+        assert_eq!(variable.value(), 5.0);
+        assert_eq!(variable.velocity(), 5.0);
+
+        assert_eq!(manager.update(3.5), UpdateResult::VariablesChanged);
+        assert_eq!(variable.value(), 10.0);
+        assert!(!manager.is_busy());
+        assert_eq!(manager.update(4.0), UpdateResult::NoChange);
+    }
+
+    #[test]
+    fn the_bonus_clock_swing() {
+        let mut manager = Manager::new();
+        let swing = manager.create_variable(0.0);
+        manager
+            .schedule_transition(
+                &swing,
+                Transition::accelerate_decelerate(5.0, 1.0, 0.2, 0.8).unwrap(),
+                100.0,
+            )
+            .unwrap();
+
+        let values: Vec<_> = [100.0, 101.0, 103.0, 105.0, 106.0]
+            .iter()
+            .map(|&time| {
+                manager.update(time);
+                (swing.value() * 1000.0).round() / 1000.0
+            })
+            .collect();
+        assert_eq!(values, [0.0, 0.2, 0.8, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn transitions_of_a_busy_variable_are_queued() {
+        let mut manager = Manager::new();
+        let variable = manager.create_variable(0.0);
+        let there = Transition::linear(1.0, 1.0).unwrap();
+        let back = Transition::linear(1.0, 0.0).unwrap();
+        manager.schedule_transition(&variable, there, 0.0).unwrap();
+        manager.schedule_transition(&variable, back, 0.5).unwrap();
+
+        manager.update(0.5);
+        assert_eq!(variable.value(), 0.5);
+        manager.update(1.5);
+        assert_eq!(variable.value(), 0.5);
+        assert_eq!(variable.velocity(), -1.0);
+        manager.update(2.0);
+        assert_eq!(variable.value(), 0.0);
+    }
+
+    #[test]
+    fn skipped_transitions_still_hand_over_their_final_value() {
+        let mut manager = Manager::new();
+        let variable = manager.create_variable(0.0);
+        manager
+            .schedule_transition(
+                &variable,
+                Transition::linear(1.0, 4.0).unwrap(),
+                0.0,
+            )
+            .unwrap();
+        manager
+            .schedule_transition(
+                &variable,
+                Transition::cubic(1.0, 0.0, 0.0).unwrap(),
+                0.0,
+            )
+            .unwrap();
+
+        // Straight into the middle of the second transition, which starts
+        // at 4 moving at 4 per second.
+        manager.update(1.5);
+        let cubic = Transition::cubic(1.0, 0.0, 0.0).unwrap();
+        let expected = cubic.sample(
+            Sample {
+                value: 4.0,
+                velocity: 4.0,
+            },
+            0.5,
+        );
+        assert_eq!(variable.sample(), expected);
+    }
+
+    #[test]
+    fn other_variables_are_independent() {
+        let mut manager = Manager::new();
+        let a = manager.create_variable(0.0);
+        let b = manager.create_variable(10.0);
+        manager
+            .schedule_transition(&a, Transition::linear(1.0, 1.0).unwrap(), 0.0)
+            .unwrap();
+        manager
+            .schedule_transition(&b, Transition::linear(1.0, 0.0).unwrap(), 0.0)
+            .unwrap();
+
+        manager.update(0.5);
+        assert_eq!((a.value(), b.value()), (0.5, 5.0));
+    }
+
+    #[test]
+    fn time_does_not_go_backwards() {
+        let mut manager = Manager::new();
+        let variable = manager.create_variable(0.0);
+        manager
+            .schedule_transition(
+                &variable,
+                Transition::linear(1.0, 1.0).unwrap(),
+                0.0,
+            )
+            .unwrap();
+
+        manager.update(0.75);
+        assert_eq!(manager.update(0.25), UpdateResult::NoChange);
+        assert_eq!(variable.value(), 0.75);
+    }
+
+    fn linear(duration: f64, final_value: f64) -> Transition {
+        Transition::linear(duration, final_value).unwrap()
+    }
+
+    #[test]
+    fn storyboards_animate_several_variables() {
+        let mut manager = Manager::new();
+        let (hand, dial) =
+            (manager.create_variable(0.0), manager.create_variable(0.5));
+        let mut storyboard = Storyboard::new();
+        storyboard.add_delay(&hand, 1.0);
+        storyboard.add_transition(&hand, linear(1.0, 1.0));
+        storyboard.add_transition(&dial, linear(2.0, 1.0));
+        manager.schedule(storyboard, 10.0).unwrap();
+
+        manager.update(11.0);
+        assert_eq!((hand.value(), dial.value()), (0.0, 0.75));
+        manager.update(11.5);
+        assert_eq!((hand.value(), dial.value()), (0.5, 0.875));
+        manager.update(12.0);
+        assert_eq!((hand.value(), dial.value()), (1.0, 1.0));
+        assert!(!manager.is_busy());
+    }
+
+    #[test]
+    fn without_priority_storyboards_wait() {
+        let mut manager = Manager::new();
+        let variable = manager.create_variable(0.0);
+        let mut forever = Storyboard::new();
+        let id = forever.add_transition(&variable, linear(1.0, 1.0));
+        let end = forever.add_keyframe_after_transition(id);
+        forever
+            .repeat_between_keyframes(
+                Storyboard::START,
+                end,
+                Repeat::Forever,
+                RepeatMode::Alternate,
+            )
+            .unwrap();
+        manager.schedule(forever, 0.0).unwrap();
+
+        assert_eq!(
+            manager.schedule_transition(&variable, linear(1.0, 5.0), 0.5),
+            Err(InsufficientPriority)
+        );
+        manager.update(100.5);
+        assert_eq!(variable.value(), 0.5);
+    }
+
+    #[test]
+    fn pending_storyboards_can_be_cancelled() {
+        let mut manager = Manager::new();
+        manager.set_priority_comparison(|_, _, effect| {
+            effect == PriorityEffect::Cancel
+        });
+        let variable = manager.create_variable(0.0);
+        manager
+            .schedule_transition(&variable, linear(1.0, 1.0), 0.0)
+            .unwrap();
+        manager
+            .schedule_transition(&variable, linear(1.0, 2.0), 0.5)
+            .unwrap();
+        manager
+            .schedule_transition(&variable, linear(1.0, 3.0), 0.5)
+            .unwrap();
+
+        // The second transition hadn't started, so the third replaced it
+        // but still waited for the first.
+        manager.update(1.5);
+        assert_eq!(variable.value(), 2.0);
+        manager.update(2.0);
+        assert_eq!(variable.value(), 3.0);
+    }
+
+    #[test]
+    fn running_storyboards_can_be_trimmed() {
+        let mut manager = Manager::new();
+        manager.set_priority_comparison(|scheduled, new, effect| {
+            effect == PriorityEffect::Trim && new > scheduled
+        });
+        let (a, b) =
+            (manager.create_variable(0.0), manager.create_variable(0.0));
+        let mut both = Storyboard::new();
+        both.add_transition(&a, linear(2.0, 2.0));
+        both.add_transition(&b, linear(2.0, 2.0));
+        manager.schedule(both, 0.0).unwrap();
+
+        let mut takeover = Storyboard::new();
+        takeover.set_tag(1);
+        takeover.add_transition(&a, linear(1.0, 0.0));
+        manager.schedule(takeover, 1.0).unwrap();
+
+        // `a` turns around at 1 where the new storyboard took over, and `b`
+        // carries on.
+        manager.update(1.5);
+        assert_eq!((a.value(), b.value()), (0.5, 1.5));
+        manager.update(2.0);
+        assert_eq!((a.value(), b.value()), (0.0, 2.0));
+        assert!(!manager.is_busy());
+    }
+
+    #[test]
+    fn repeating_storyboards_can_be_concluded() {
+        let mut manager = Manager::new();
+        manager.set_priority_comparison(|_, _, effect| {
+            effect == PriorityEffect::Conclude
+        });
+        let variable = manager.create_variable(0.0);
+        let mut forever = Storyboard::new();
+        let id = forever.add_transition(&variable, linear(1.0, 1.0));
+        let end = forever.add_keyframe_after_transition(id);
+        forever
+            .repeat_between_keyframes(
+                Storyboard::START,
+                end,
+                Repeat::Forever,
+                RepeatMode::Alternate,
+            )
+            .unwrap();
+        manager.schedule(forever, 0.0).unwrap();
+        manager
+            .schedule_transition(&variable, linear(1.0, 5.0), 2.5)
+            .unwrap();
+
+        // The third play runs forward, to 1, and the transition starts
+        // there.
+        manager.update(3.0);
+        assert_eq!(variable.value(), 1.0);
+        manager.update(3.5);
+        assert_eq!(variable.value(), 3.0);
+    }
+}
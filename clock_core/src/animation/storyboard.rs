@@ -0,0 +1,526 @@
+use std::fmt;
+
+use super::{Sample, Transition, Variable};
+
+/// A point in time within a [`Storyboard`], like `UI_ANIMATION_KEYFRAME`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Keyframe(usize);
+
+/// A transition added to a [`Storyboard`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransitionId(usize);
+
+/// How often the part of a storyboard between two keyframes plays again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Repeat {
+    /// Plays the part `n` more times, so `n + 1` times in all.
+    Times(u32),
+    /// Until the storyboard is concluded, see [`super::PriorityEffect`].
+    Forever,
+}
+
+/// Which way repetitions play, like `UI_ANIMATION_REPEAT_MODE`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RepeatMode {
+    /// Every repetition plays forward, jumping back to the start.
+    #[default]
+    Normal,
+    /// Every other repetition plays backwards, so the variables move back
+    /// and forth.
+    Alternate,
+}
+
+/// A storyboard couldn't be built as asked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StoryboardError {
+    /// The transition would start before the variable's previous one ends.
+    Overlap,
+    /// The keyframe comes before the storyboard starts.
+    NegativeOffset,
+    /// The end keyframe of a repetition isn't after its start keyframe.
+    EmptyRepetition,
+    /// A storyboard repeats only one part.
+    AlreadyRepeating,
+}
+
+impl fmt::Display for StoryboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            StoryboardError::Overlap => {
+                "transitions of a variable can't overlap"
+            }
+            StoryboardError::NegativeOffset => {
+                "keyframes can't come before the start of the storyboard"
+            }
+            StoryboardError::EmptyRepetition => {
+                "the end keyframe must come after the start keyframe"
+            }
+            StoryboardError::AlreadyRepeating => {
+                "the storyboard already repeats between other keyframes"
+            }
+        })
+    }
+}
+
+impl std::error::Error for StoryboardError {}
+
+/// Transitions of one or more variables that play together, like
+/// `IUIAnimationStoryboard`.
+///
+/// Every variable's transitions play one after the other, from the start
+/// of the storyboard or from a keyframe, and can be spaced out by delays.
+/// Keyframes are at offsets from other keyframes or at the end of
+/// transitions, and one part of the storyboard between two keyframes can
+/// be repeated.
+#[derive(Clone, Debug, Default)]
+pub struct Storyboard {
+    pub(super) variables: Vec<Variable>,
+    // Where every variable's last transition or delay ends.
+    cursors: Vec<f64>,
+    pub(super) segments: Vec<Segment>,
+    // In seconds from the start of the storyboard.
+    keyframes: Vec<f64>,
+    pub(super) repetition: Option<Repetition>,
+    pub(super) tag: u32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Segment {
+    pub(super) variable: usize,
+    pub(super) start: f64,
+    pub(super) transition: Transition,
+}
+
+impl Segment {
+    fn end(&self) -> f64 {
+        self.start + self.transition.duration()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Repetition {
+    pub(super) start: f64,
+    pub(super) end: f64,
+    pub(super) repeat: Repeat,
+    pub(super) mode: RepeatMode,
+}
+
+impl Storyboard {
+    /// The start of every storyboard.
+    pub const START: Keyframe = Keyframe(usize::MAX);
+
+    pub fn new() -> Self {
+        Storyboard::default()
+    }
+
+    /// Identifies the storyboard to the priority comparison, see
+    /// [`super::Manager::set_priority_comparison`].
+    pub fn set_tag(&mut self, tag: u32) {
+        self.tag = tag;
+    }
+
+    pub fn tag(&self) -> u32 {
+        self.tag
+    }
+
+    /// Adds `transition` of `variable` after the variable's previous
+    /// transition or delay in this storyboard, or at its start.
+    pub fn add_transition(
+        &mut self,
+        variable: &Variable,
+        transition: Transition,
+    ) -> TransitionId {
+        let index = self.variable_index(variable);
+        let start = self.cursors[index];
+        self.push(index, start, transition)
+    }
+
+    /// Adds `transition` of `variable` starting at `keyframe`.
+    pub fn add_transition_at_keyframe(
+        &mut self,
+        variable: &Variable,
+        transition: Transition,
+        keyframe: Keyframe,
+    ) -> Result<TransitionId, StoryboardError> {
+        let index = self.variable_index(variable);
+        let start = self.offset(keyframe);
+        if start < self.cursors[index] {
+            return Err(StoryboardError::Overlap);
+        }
+        Ok(self.push(index, start, transition))
+    }
+
+    /// Holds `variable` where it is for `seconds` before its next
+    /// transition.
+    pub fn add_delay(&mut self, variable: &Variable, seconds: f64) {
+        let index = self.variable_index(variable);
+        self.cursors[index] += seconds.max(0.0);
+    }
+
+    pub fn add_keyframe_at_offset(
+        &mut self,
+        keyframe: Keyframe,
+        offset: f64,
+    ) -> Result<Keyframe, StoryboardError> {
+        let offset = self.offset(keyframe) + offset;
+        if !(offset >= 0.0 && offset.is_finite()) {
+            return Err(StoryboardError::NegativeOffset);
+        }
+        self.keyframes.push(offset);
+        Ok(Keyframe(self.keyframes.len() - 1))
+    }
+
+    pub fn add_keyframe_after_transition(
+        &mut self,
+        transition: TransitionId,
+    ) -> Keyframe {
+        self.keyframes.push(self.segments[transition.0].end());
+        Keyframe(self.keyframes.len() - 1)
+    }
+
+    /// Plays the part of the storyboard between `start` and `end` again,
+    /// like `IUIAnimationStoryboard2::RepeatBetweenKeyframes`.
+    ///
+    /// A normal repetition jumps back to the variables' values at `start`,
+    /// so it's smooth only if they end where they started. Alternating
+    /// repetitions repeated an odd number of times end with the variables
+    /// at their values at `start` and hold them there for the rest of the
+    /// storyboard.
+    pub fn repeat_between_keyframes(
+        &mut self,
+        start: Keyframe,
+        end: Keyframe,
+        repeat: Repeat,
+        mode: RepeatMode,
+    ) -> Result<(), StoryboardError> {
+        if self.repetition.is_some() {
+            return Err(StoryboardError::AlreadyRepeating);
+        }
+        let (start, end) = (self.offset(start), self.offset(end));
+        if end <= start {
+            return Err(StoryboardError::EmptyRepetition);
+        }
+
+        self.repetition = Some(Repetition {
+            start,
+            end,
+            repeat,
+            mode,
+        });
+        Ok(())
+    }
+
+    /// How long the storyboard plays once scheduled, with all its
+    /// repetitions, or `None` if it repeats forever.
+    pub fn duration(&self) -> Option<f64> {
+        let once = self
+            .cursors
+            .iter()
+            .chain(&self.keyframes)
+            .fold(0.0, |a: f64, &b| a.max(b));
+        match self.repetition {
+            None => Some(once),
+            Some(Repetition {
+                repeat: Repeat::Forever,
+                ..
+            }) => None,
+            Some(Repetition {
+                start,
+                end,
+                repeat: Repeat::Times(times),
+                ..
+            }) => Some(once + (end - start) * times as f64),
+        }
+    }
+
+    fn offset(&self, keyframe: Keyframe) -> f64 {
+        if keyframe == Storyboard::START {
+            0.0
+        } else {
+            self.keyframes[keyframe.0]
+        }
+    }
+
+    fn variable_index(&mut self, variable: &Variable) -> usize {
+        match self.variables.iter().position(|other| other.is(variable)) {
+            Some(index) => index,
+            None => {
+                self.variables.push(variable.clone());
+                self.cursors.push(0.0);
+                self.variables.len() - 1
+            }
+        }
+    }
+
+    fn push(
+        &mut self,
+        variable: usize,
+        start: f64,
+        transition: Transition,
+    ) -> TransitionId {
+        self.segments.push(Segment {
+            variable,
+            start,
+            transition,
+        });
+        self.cursors[variable] = start + transition.duration();
+        TransitionId(self.segments.len() - 1)
+    }
+
+    /// Where in the storyboard's own timeline, played once, it is `elapsed`
+    /// seconds after it started, and whether that part plays backwards.
+    pub(super) fn position(&self, elapsed: f64) -> (f64, bool) {
+        let Repetition {
+            start,
+            end,
+            repeat,
+            mode,
+        } = match self.repetition {
+            Some(repetition) if elapsed > repetition.start => repetition,
+            _ => return (elapsed, false),
+        };
+
+        let length = end - start;
+        let plays = match repeat {
+            Repeat::Times(times) => times as f64 + 1.0,
+            Repeat::Forever => f64::INFINITY,
+        };
+        let into = elapsed - start;
+        if into >= length * plays {
+            // Past the repetitions, where the last one left off.
+            let reversed = mode == RepeatMode::Alternate && plays % 2.0 == 0.0;
+            return if reversed {
+                (start, false)
+            } else {
+                (start + into - length * (plays - 1.0), false)
+            };
+        }
+
+        // The end of a play belongs to it, not to the next one.
+        let play = (into / length).ceil().max(1.0) - 1.0;
+        let within = into - play * length;
+        if mode == RepeatMode::Alternate && play % 2.0 == 1.0 {
+            (end - within, true)
+        } else {
+            (start + within, false)
+        }
+    }
+
+    /// Samples every variable of the storyboard `elapsed` seconds after it
+    /// started from `initial`, the values the variables had then.
+    pub(super) fn sample(
+        &self,
+        initial: &[Sample],
+        elapsed: f64,
+    ) -> Vec<Sample> {
+        let (position, reversed) = self.position(elapsed);
+
+        (0..self.variables.len())
+            .map(|variable| {
+                let mut sample =
+                    self.sample_variable(variable, initial[variable], position);
+                if reversed {
+                    sample.velocity = -sample.velocity;
+                }
+                sample
+            })
+            .collect()
+    }
+
+    fn sample_variable(
+        &self,
+        variable: usize,
+        initial: Sample,
+        position: f64,
+    ) -> Sample {
+        let mut current = initial;
+        let mut end = 0.0;
+        for segment in self
+            .segments
+            .iter()
+            .filter(|segment| segment.variable == variable)
+        {
+            if segment.start > position {
+                break;
+            }
+            // Held in place by a delay, the variable comes to rest.
+            let from = if segment.start > end {
+                Sample::at_rest(current.value)
+            } else {
+                current
+            };
+            current = segment.transition.sample(from, position - segment.start);
+            end = segment.end();
+        }
+
+        if position > end {
+            Sample::at_rest(current.value)
+        } else {
+            current
+        }
+    }
+
+    /// Stops repeating forever after the play that is running `elapsed`
+    /// seconds after the storyboard started.
+    pub(super) fn conclude(&mut self, elapsed: f64) {
+        if let Some(repetition) = &mut self.repetition {
+            if repetition.repeat == Repeat::Forever {
+                let length = repetition.end - repetition.start;
+                let into = (elapsed - repetition.start).max(0.0);
+                let plays = (into / length).ceil().max(1.0);
+                repetition.repeat = Repeat::Times(plays as u32 - 1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::Manager;
+
+    fn linear(duration: f64, final_value: f64) -> Transition {
+        Transition::linear(duration, final_value).unwrap()
+    }
+
+    #[test]
+    fn transitions_of_a_variable_follow_each_other() {
+        let manager = Manager::new();
+        let variable = manager.create_variable(0.0);
+        let mut storyboard = Storyboard::new();
+        storyboard.add_transition(&variable, linear(1.0, 2.0));
+        storyboard.add_delay(&variable, 0.5);
+        storyboard.add_transition(&variable, linear(1.0, 0.0));
+
+        assert_eq!(storyboard.duration(), Some(2.5));
+        let initial = [Sample::at_rest(0.0)];
+        let value = |elapsed| storyboard.sample(&initial, elapsed)[0].value;
+        assert_eq!(value(0.5), 1.0);
+        assert_eq!(value(1.25), 2.0);
+        assert_eq!(storyboard.sample(&initial, 1.25)[0].velocity, 0.0);
+        assert_eq!(value(2.0), 1.0);
+        assert_eq!(value(3.0), 0.0);
+    }
+
+    #[test]
+    fn keyframes() {
+        let manager = Manager::new();
+        let (a, b) =
+            (manager.create_variable(0.0), manager.create_variable(0.0));
+        let mut storyboard = Storyboard::new();
+        let first = storyboard.add_transition(&a, linear(2.0, 1.0));
+        let after = storyboard.add_keyframe_after_transition(first);
+        let later = storyboard.add_keyframe_at_offset(after, 1.0).unwrap();
+        storyboard
+            .add_transition_at_keyframe(&b, linear(1.0, 1.0), later)
+            .unwrap();
+
+        assert_eq!(storyboard.duration(), Some(4.0));
+        let initial = [Sample::at_rest(0.0); 2];
+        assert_eq!(storyboard.sample(&initial, 3.0)[1].value, 0.0);
+        assert_eq!(storyboard.sample(&initial, 3.5)[1].value, 0.5);
+
+        assert_eq!(
+            storyboard.add_transition_at_keyframe(
+                &a,
+                linear(1.0, 0.0),
+                Storyboard::START
+            ),
+            Err(StoryboardError::Overlap)
+        );
+        assert_eq!(
+            storyboard.add_keyframe_at_offset(Storyboard::START, -1.0),
+            Err(StoryboardError::NegativeOffset)
+        );
+    }
+
+    #[test]
+    fn normal_repetitions_jump_back() {
+        let manager = Manager::new();
+        let variable = manager.create_variable(0.0);
+        let mut storyboard = Storyboard::new();
+        let id = storyboard.add_transition(&variable, linear(1.0, 1.0));
+        let end = storyboard.add_keyframe_after_transition(id);
+        storyboard
+            .repeat_between_keyframes(
+                Storyboard::START,
+                end,
+                Repeat::Times(2),
+                RepeatMode::Normal,
+            )
+            .unwrap();
+
+        assert_eq!(storyboard.duration(), Some(3.0));
+        let initial = [Sample::at_rest(0.0)];
+        let values: Vec<_> = [0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 4.0]
+            .iter()
+            .map(|&elapsed| storyboard.sample(&initial, elapsed)[0].value)
+            .collect();
+        assert_eq!(values, [0.5, 1.0, 0.5, 1.0, 0.5, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn alternate_repetitions_play_backwards() {
+        let manager = Manager::new();
+        let variable = manager.create_variable(0.0);
+        let mut storyboard = Storyboard::new();
+        let id = storyboard.add_transition(&variable, linear(1.0, 1.0));
+        let end = storyboard.add_keyframe_after_transition(id);
+        storyboard
+            .repeat_between_keyframes(
+                Storyboard::START,
+                end,
+                Repeat::Times(1),
+                RepeatMode::Alternate,
+            )
+            .unwrap();
+
+        let initial = [Sample::at_rest(0.0)];
+        let back = storyboard.sample(&initial, 1.25)[0];
+        assert_eq!(back.value, 0.75);
+        assert_eq!(back.velocity, -1.0);
+        // An odd number of repetitions ends back at the start.
+        assert_eq!(storyboard.sample(&initial, 2.0)[0].value, 0.0);
+        assert_eq!(storyboard.sample(&initial, 5.0)[0].value, 0.0);
+    }
+
+    #[test]
+    fn repeating_forever_has_no_duration() {
+        let manager = Manager::new();
+        let variable = manager.create_variable(0.0);
+        let mut storyboard = Storyboard::new();
+        storyboard.add_transition(&variable, linear(1.0, 1.0));
+        let half = storyboard
+            .add_keyframe_at_offset(Storyboard::START, 0.5)
+            .unwrap();
+        let end = storyboard
+            .add_keyframe_at_offset(Storyboard::START, 1.0)
+            .unwrap();
+        storyboard
+            .repeat_between_keyframes(
+                half,
+                end,
+                Repeat::Forever,
+                RepeatMode::Alternate,
+            )
+            .unwrap();
+
+        assert_eq!(storyboard.duration(), None);
+        let initial = [Sample::at_rest(0.0)];
+        assert_eq!(storyboard.sample(&initial, 100.25)[0].value, 0.75);
+        assert_eq!(storyboard.sample(&initial, 100.75)[0].value, 0.75);
+        storyboard.conclude(100.6);
+        assert_eq!(storyboard.duration(), Some(101.0));
+        assert_eq!(storyboard.sample(&initial, 102.0)[0].value, 1.0);
+
+        assert_eq!(
+            storyboard.repeat_between_keyframes(
+                half,
+                end,
+                Repeat::Times(1),
+                RepeatMode::Normal
+            ),
+            Err(StoryboardError::AlreadyRepeating)
+        );
+    }
+}
@@ -0,0 +1,512 @@
+use std::fmt;
+
+/// The value and velocity of an animation variable at some point in time.
+/// Velocity is in units per second.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Sample {
+    pub value: f64,
+    pub velocity: f64,
+}
+
+impl Sample {
+    pub const fn at_rest(value: f64) -> Self {
+        Sample {
+            value,
+            velocity: 0.0,
+        }
+    }
+}
+
+/// How an animation variable moves to a final value over a given duration, in
+/// seconds, like the transitions of the Windows Animation Manager
+/// (`IUIAnimationTransitionLibrary`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transition {
+    duration: f64,
+    final_value: f64,
+    curve: Curve,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Curve {
+    Linear,
+    AccelerateDecelerate {
+        acceleration_ratio: f64,
+        deceleration_ratio: f64,
+    },
+    Cubic {
+        final_velocity: f64,
+    },
+    Spring {
+        damping_ratio: f64,
+    },
+    Bounce {
+        bounces: u32,
+    },
+}
+
+/// The arguments passed to a [`Transition`] constructor were out of range,
+/// where the Animation Manager would fail with `E_INVALIDARG`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidTransition(&'static str);
+
+impl fmt::Display for InvalidTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid transition: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidTransition {}
+
+// The spring is considered at rest once it swings less than this fraction of
+// where it started from, which it does at the end of its duration.
+const SPRING_SETTLED: f64 = 0.001;
+
+// How much of its speed the bouncing variable keeps at every bounce.
+const RESTITUTION: f64 = 0.5;
+
+impl Transition {
+    /// Moves at a constant velocity, ignoring the initial velocity.
+    pub fn linear(
+        duration: f64,
+        final_value: f64,
+    ) -> Result<Self, InvalidTransition> {
+        Transition::new(duration, final_value, Curve::Linear)
+    }
+
+    /// Starts at rest, speeds up at a constant rate for the first
+    /// `acceleration_ratio` of the duration, slows down at a constant rate
+    /// for the last `deceleration_ratio` of it, and moves at a constant
+    /// velocity in between. The ratios can't add up to more than 1.
+    ///
+    /// Like `CreateAccelerateDecelerateTransition`.
+    pub fn accelerate_decelerate(
+        duration: f64,
+        final_value: f64,
+        acceleration_ratio: f64,
+        deceleration_ratio: f64,
+    ) -> Result<Self, InvalidTransition> {
+        if !(0.0..=1.0).contains(&acceleration_ratio)
+            || !(0.0..=1.0).contains(&deceleration_ratio)
+            || acceleration_ratio + deceleration_ratio > 1.0
+        {
+            return Err(InvalidTransition(
+                "the acceleration and deceleration ratios must be at least 0 \
+                 and add up to at most 1",
+            ));
+        }
+
+        Transition::new(
+            duration,
+            final_value,
+            Curve::AccelerateDecelerate {
+                acceleration_ratio,
+                deceleration_ratio,
+            },
+        )
+    }
+
+    /// Follows a cubic curve from the initial value and velocity to the
+    /// final value and velocity, like `CreateCubicTransition`.
+    pub fn cubic(
+        duration: f64,
+        final_value: f64,
+        final_velocity: f64,
+    ) -> Result<Self, InvalidTransition> {
+        if !final_velocity.is_finite() {
+            return Err(InvalidTransition("the final velocity must be finite"));
+        }
+        Transition::new(duration, final_value, Curve::Cubic { final_velocity })
+    }
+
+    /// Swings around the final value like a damped spring, keeping the
+    /// initial velocity. The closer `damping_ratio` is to 0 the more it
+    /// swings, and it must be less than 1. The spring is stiff enough to
+    /// settle within the duration, after which it rests at the final value.
+    pub fn spring(
+        duration: f64,
+        final_value: f64,
+        damping_ratio: f64,
+    ) -> Result<Self, InvalidTransition> {
+        if !(damping_ratio > 0.0 && damping_ratio < 1.0) {
+            return Err(InvalidTransition(
+                "the damping ratio must be between 0 and 1",
+            ));
+        }
+        Transition::new(duration, final_value, Curve::Spring { damping_ratio })
+    }
+
+    /// Falls to the final value like a dropped ball, ignoring the initial
+    /// velocity, and bounces off it `bounces` times, every bounce half as
+    /// fast as the one before.
+    pub fn bounce(
+        duration: f64,
+        final_value: f64,
+        bounces: u32,
+    ) -> Result<Self, InvalidTransition> {
+        Transition::new(duration, final_value, Curve::Bounce { bounces })
+    }
+
+    fn new(
+        duration: f64,
+        final_value: f64,
+        curve: Curve,
+    ) -> Result<Self, InvalidTransition> {
+        if !(duration >= 0.0 && duration.is_finite()) {
+            return Err(InvalidTransition(
+                "the duration must be a finite number of seconds, at least 0",
+            ));
+        }
+        if !final_value.is_finite() {
+            return Err(InvalidTransition("the final value must be finite"));
+        }
+
+        Ok(Transition {
+            duration,
+            final_value,
+            curve,
+        })
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+
+    pub fn final_value(&self) -> f64 {
+        self.final_value
+    }
+
+    /// Where a variable that started this transition at `initial` is after
+    /// `elapsed` seconds. Before the start it is at `initial`, from the end
+    /// on at the final value.
+    pub fn sample(&self, initial: Sample, elapsed: f64) -> Sample {
+        if elapsed <= 0.0 && self.duration > 0.0 {
+            return initial;
+        }
+        if elapsed >= self.duration {
+            return Sample {
+                value: self.final_value,
+                velocity: self.final_velocity(initial),
+            };
+        }
+
+        let duration = self.duration;
+        let distance = self.final_value - initial.value;
+        // How far along the transition is, from 0 to 1.
+        let u = elapsed / duration;
+
+        match self.curve {
+            Curve::Linear => Sample {
+                value: initial.value + distance * u,
+                velocity: distance / duration,
+            },
+            Curve::AccelerateDecelerate {
+                acceleration_ratio: a,
+                deceleration_ratio: d,
+            } => {
+                // The velocity rises linearly to its peak, stays there and
+                // falls linearly back to 0, covering the distance on the way.
+                let peak = 1.0 / (1.0 - (a + d) / 2.0);
+                let (progress, speed) = if u < a {
+                    (peak * u * u / (2.0 * a), peak * u / a)
+                } else if u <= 1.0 - d {
+                    (peak * (u - a / 2.0), peak)
+                } else {
+                    let left = 1.0 - u;
+                    (1.0 - peak * left * left / (2.0 * d), peak * left / d)
+                };
+                Sample {
+                    value: initial.value + distance * progress,
+                    velocity: distance * speed / duration,
+                }
+            }
+            Curve::Cubic { final_velocity } => {
+                // Cubic Hermite interpolation, with the velocities scaled to
+                // the unit interval.
+                let (v0, v1) =
+                    (initial.velocity * duration, final_velocity * duration);
+                let (u2, u3) = (u * u, u * u * u);
+                let value = (2.0 * u3 - 3.0 * u2 + 1.0) * initial.value
+                    + (u3 - 2.0 * u2 + u) * v0
+                    + (-2.0 * u3 + 3.0 * u2) * self.final_value
+                    + (u3 - u2) * v1;
+                let slope = (6.0 * u2 - 6.0 * u) * initial.value
+                    + (3.0 * u2 - 4.0 * u + 1.0) * v0
+                    + (-6.0 * u2 + 6.0 * u) * self.final_value
+                    + (3.0 * u2 - 2.0 * u) * v1;
+                Sample {
+                    value,
+                    velocity: slope / duration,
+                }
+            }
+            Curve::Spring { damping_ratio } => {
+                // An underdamped oscillator around the final value, stiff
+                // enough to decay to SPRING_SETTLED within the duration.
+                let natural = -SPRING_SETTLED.ln() / (damping_ratio * duration);
+                let decay = damping_ratio * natural;
+                let frequency =
+                    natural * (1.0 - damping_ratio * damping_ratio).sqrt();
+
+                let a = -distance;
+                let b = (initial.velocity + decay * a) / frequency;
+                let envelope = (-decay * elapsed).exp();
+                let (sin, cos) = (frequency * elapsed).sin_cos();
+                Sample {
+                    value: self.final_value + envelope * (a * cos + b * sin),
+                    velocity: envelope
+                        * ((frequency * b - decay * a) * cos
+                            - (decay * b + frequency * a) * sin),
+                }
+            }
+            Curve::Bounce { bounces } => {
+                let (height, speed) = bounce(u, bounces);
+                Sample {
+                    value: initial.value + distance * (1.0 - height),
+                    velocity: -distance * speed / duration,
+                }
+            }
+        }
+    }
+
+    // The velocity at the end of the transition.
+    fn final_velocity(&self, initial: Sample) -> f64 {
+        match self.curve {
+            Curve::Linear if self.duration > 0.0 => {
+                (self.final_value - initial.value) / self.duration
+            }
+            Curve::Cubic { final_velocity } => final_velocity,
+            _ => 0.0,
+        }
+    }
+}
+
+// The height, from 1 down to 0, and vertical velocity of a ball dropped at
+// `u` = 0 that bounces `bounces` times and comes to rest at `u` = 1.
+fn bounce(u: f64, bounces: u32) -> (f64, f64) {
+    // The fall takes `fall`, the k-th bounce 2 * RESTITUTION^k * `fall`.
+    let airtime: f64 = (1..=bounces)
+        .map(|k| 2.0 * RESTITUTION.powi(k as i32))
+        .sum();
+    let fall = 1.0 / (1.0 + airtime);
+    let gravity = 2.0 / (fall * fall);
+
+    if u < fall {
+        return (1.0 - gravity * u * u / 2.0, -gravity * u);
+    }
+
+    let mut start = fall;
+    for k in 1..=bounces {
+        let launch = RESTITUTION.powi(k as i32) * gravity * fall;
+        let end = start + 2.0 * launch / gravity;
+        if u < end || k == bounces {
+            let t = (u - start).min(end - start);
+            return (launch * t - gravity * t * t / 2.0, launch - gravity * t);
+        }
+        start = end;
+    }
+    (0.0, 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    // The velocity matches the slope of the value.
+    fn assert_consistent(transition: Transition, initial: Sample) {
+        let h = 1e-6;
+        // An odd number of steps, to stay clear of where a ball bounces.
+        let steps = 47;
+        for step in 1..steps {
+            let t = transition.duration() * step as f64 / steps as f64;
+            let before = transition.sample(initial, t - h).value;
+            let after = transition.sample(initial, t + h).value;
+            let velocity = transition.sample(initial, t).velocity;
+            assert!(
+                ((after - before) / (2.0 * h) - velocity).abs()
+                    < 1e-3 * velocity.abs().max(1.0),
+                "at {}: {} vs {}",
+                t,
+                (after - before) / (2.0 * h),
+                velocity
+            );
+        }
+    }
+
+    #[test]
+    fn linear_moves_at_a_constant_velocity() {
+        let linear = Transition::linear(2.0, 10.0).unwrap();
+        let initial = Sample {
+            value: 4.0,
+            velocity: 100.0,
+        };
+
+        assert_eq!(linear.sample(initial, 0.0), initial);
+        let half = linear.sample(initial, 1.0);
+        assert_close(half.value, 7.0);
+        assert_close(half.velocity, 3.0);
+        assert_eq!(
+            linear.sample(initial, 5.0),
+            Sample {
+                value: 10.0,
+                velocity: 3.0
+            }
+        );
+    }
+
+    #[test]
+    fn accelerate_decelerate_like_the_bonus_clock() {
+        // The swing of module_3_bonus: accelerating for the first second and
+        // decelerating for the remaining four, so the peak velocity is twice
+        // the average.
+        let swing =
+            Transition::accelerate_decelerate(5.0, 1.0, 0.2, 0.8).unwrap();
+        let start = Sample::at_rest(0.0);
+
+        let peak = swing.sample(start, 1.0);
+        assert_close(peak.value, 0.2);
+        assert_close(peak.velocity, 0.4);
+
+        // Quadratic on the way up and on the way down.
+        assert_close(swing.sample(start, 0.5).value, 0.05);
+        assert_close(swing.sample(start, 0.5).velocity, 0.2);
+        assert_close(swing.sample(start, 3.0).value, 1.0 - 0.1 * 4.0 / 2.0);
+        assert_close(swing.sample(start, 3.0).velocity, 0.2);
+
+        assert_eq!(swing.sample(start, 5.0), Sample::at_rest(1.0));
+        assert_consistent(swing, start);
+    }
+
+    #[test]
+    fn accelerate_decelerate_ignores_the_initial_velocity() {
+        let transition =
+            Transition::accelerate_decelerate(1.0, 2.0, 0.25, 0.25).unwrap();
+        let moving = Sample {
+            value: 0.0,
+            velocity: 50.0,
+        };
+
+        assert_eq!(transition.sample(moving, 0.5), {
+            transition.sample(Sample::at_rest(0.0), 0.5)
+        });
+        // The constant part in the middle, at 4/3 the average velocity.
+        let middle = transition.sample(moving, 0.5);
+        assert_close(middle.value, 1.0);
+        assert_close(middle.velocity, 8.0 / 3.0);
+        assert_consistent(transition, moving);
+    }
+
+    #[test]
+    fn accelerate_decelerate_rejects_ratios_above_one() {
+        assert!(Transition::accelerate_decelerate(1.0, 1.0, 0.6, 0.5).is_err());
+        assert!(Transition::accelerate_decelerate(1.0, 1.0, -0.1, 0.5).is_err());
+        assert!(Transition::accelerate_decelerate(1.0, 1.0, 0.5, 0.5).is_ok());
+        assert!(Transition::accelerate_decelerate(1.0, 1.0, 0.0, 0.0).is_ok());
+    }
+
+    #[test]
+    fn cubic_matches_both_velocities() {
+        let cubic = Transition::cubic(2.0, 10.0, -1.0).unwrap();
+        let initial = Sample {
+            value: 0.0,
+            velocity: 3.0,
+        };
+
+        assert_close(cubic.sample(initial, 1e-12).velocity, 3.0);
+        assert_close(cubic.sample(initial, 2.0 - 1e-12).velocity, -1.0);
+        assert_eq!(
+            cubic.sample(initial, 2.0),
+            Sample {
+                value: 10.0,
+                velocity: -1.0
+            }
+        );
+        assert_consistent(cubic, initial);
+    }
+
+    #[test]
+    fn spring_overshoots_and_settles() {
+        let spring = Transition::spring(1.0, 1.0, 0.3).unwrap();
+        let start = Sample::at_rest(0.0);
+
+        let highest = (1..100)
+            .map(|step| spring.sample(start, step as f64 / 100.0).value)
+            .fold(f64::MIN, f64::max);
+        assert!(highest > 1.2, "{}", highest);
+
+        let end = spring.sample(start, 1.0 - 1e-9);
+        assert!((end.value - 1.0).abs() <= SPRING_SETTLED, "{:?}", end);
+        assert_eq!(spring.sample(start, 1.0), Sample::at_rest(1.0));
+        assert_consistent(spring, start);
+    }
+
+    #[test]
+    fn spring_keeps_the_initial_velocity() {
+        let spring = Transition::spring(1.0, 0.0, 0.5).unwrap();
+        let initial = Sample {
+            value: 0.0,
+            velocity: 5.0,
+        };
+
+        assert_close(spring.sample(initial, 1e-12).velocity, 5.0);
+        assert!(spring.sample(initial, 0.05).value > 0.0);
+        assert_consistent(spring, initial);
+        assert!(Transition::spring(1.0, 0.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn bounce_touches_the_final_value_at_every_bounce() {
+        let bounce = Transition::bounce(1.0, 10.0, 2).unwrap();
+        let start = Sample::at_rest(0.0);
+
+        // The fall takes 1 / (1 + 2 * 0.5 + 2 * 0.25) of the duration.
+        let fall = 1.0 / 2.5;
+        assert_close(bounce.sample(start, fall).value, 10.0);
+        // The first bounce is back up a quarter of the way, half as fast.
+        let top = bounce.sample(start, fall * 1.5);
+        assert_close(top.value, 10.0 - 2.5);
+        assert_close(top.velocity, 0.0);
+        assert_close(bounce.sample(start, fall * 2.0).value, 10.0);
+
+        for step in 0..=100 {
+            let value = bounce.sample(start, step as f64 / 100.0).value;
+            assert!((0.0..=10.0 + 1e-9).contains(&value), "{}", value);
+        }
+        assert_eq!(bounce.sample(start, 1.0), Sample::at_rest(10.0));
+        assert_consistent(bounce, start);
+    }
+
+    #[test]
+    fn bounce_without_bounces_is_a_fall() {
+        let fall = Transition::bounce(2.0, 1.0, 0).unwrap();
+        let start = Sample::at_rest(0.0);
+        assert_close(fall.sample(start, 1.0).value, 0.25);
+        assert_close(fall.sample(start, 2.0 - 1e-12).value, 1.0);
+    }
+
+    #[test]
+    fn zero_duration_jumps_to_the_final_value() {
+        let jump = Transition::linear(0.0, 3.0).unwrap();
+        assert_eq!(
+            jump.sample(Sample::at_rest(1.0), 0.0),
+            Sample::at_rest(3.0)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_durations_and_values() {
+        assert!(Transition::linear(-1.0, 0.0).is_err());
+        assert!(Transition::linear(f64::INFINITY, 0.0).is_err());
+        assert!(Transition::linear(f64::NAN, 0.0).is_err());
+        assert!(Transition::linear(1.0, f64::NAN).is_err());
+        assert!(Transition::cubic(1.0, 0.0, f64::INFINITY).is_err());
+    }
+}
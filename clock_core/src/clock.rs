@@ -0,0 +1,627 @@
+use std::{fmt, str::FromStr};
+
+/// Milliseconds in a day without leap seconds.
+pub const MILLISECONDS_PER_DAY: u32 = 24 * 60 * 60 * 1000;
+
+/// A time of day as shown by the clock.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ClockTime {
+    hour: u8,
+    minute: u8,
+    second: u8,
+    millisecond: u16,
+}
+
+impl ClockTime {
+    /// Creates a time of day, clamping every field to its valid range.
+    ///
+    /// `SYSTEMTIME` reports a leap second as second `60`. Rather than
+    /// wrapping to the next minute early, a leap second holds the clock at
+    /// the very end of the minute (`59.999`).
+    pub fn new(hour: u8, minute: u8, second: u8, millisecond: u16) -> Self {
+        let (second, millisecond) = if second >= 60 {
+            (59, 999)
+        } else {
+            (second, millisecond.min(999))
+        };
+
+        ClockTime {
+            hour: hour.min(23),
+            minute: minute.min(59),
+            second,
+            millisecond,
+        }
+    }
+
+    pub fn hms(hour: u8, minute: u8, second: u8) -> Self {
+        ClockTime::new(hour, minute, second, 0)
+    }
+
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    pub fn millisecond(&self) -> u16 {
+        self.millisecond
+    }
+
+    /// Milliseconds since midnight, in `0..MILLISECONDS_PER_DAY`.
+    pub fn millisecond_of_day(&self) -> u32 {
+        ((self.hour as u32 * 60 + self.minute as u32) * 60 + self.second as u32)
+            * 1000
+            + self.millisecond as u32
+    }
+
+    /// The time `millisecond` milliseconds after midnight, wrapping around
+    /// at the end of the day.
+    pub fn from_millisecond_of_day(millisecond: u64) -> Self {
+        let millisecond = (millisecond % MILLISECONDS_PER_DAY as u64) as u32;
+        let second = millisecond / 1000;
+        let minute = second / 60;
+
+        ClockTime {
+            hour: (minute / 60) as u8,
+            minute: (minute % 60) as u8,
+            second: (second % 60) as u8,
+            millisecond: (millisecond % 1000) as u16,
+        }
+    }
+
+    /// Moves the time forward, or backward for negative `milliseconds`,
+    /// wrapping around midnight.
+    pub fn wrapping_add_milliseconds(&self, milliseconds: i64) -> Self {
+        let day = MILLISECONDS_PER_DAY as i64;
+        let millisecond = (self.millisecond_of_day() as i64
+            + milliseconds.rem_euclid(day))
+            % day;
+        ClockTime::from_millisecond_of_day(millisecond as u64)
+    }
+}
+
+impl fmt::Display for ClockTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second)?;
+        if self.millisecond != 0 {
+            write!(f, ".{:03}", self.millisecond)?;
+        }
+        Ok(())
+    }
+}
+
+/// The error returned when parsing a [`ClockTime`] fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseClockTimeError(String);
+
+impl fmt::Display for ParseClockTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid time {:?}, expected HH:MM[:SS[.mmm]]", self.0)
+    }
+}
+
+impl std::error::Error for ParseClockTimeError {}
+
+impl FromStr for ClockTime {
+    type Err = ParseClockTimeError;
+
+    /// Parses `HH:MM`, `HH:MM:SS` or `HH:MM:SS.mmm`. Unlike
+    /// [`ClockTime::new`] out of range fields are rejected, except for the
+    /// leap second `60`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || ParseClockTimeError(text.to_owned());
+
+        let (clock, millisecond) = match text.split_once('.') {
+            Some((clock, fraction)) => {
+                if fraction.is_empty()
+                    || fraction.len() > 3
+                    || !fraction.bytes().all(|byte| byte.is_ascii_digit())
+                {
+                    return Err(error());
+                }
+                // ".5" is half a second, not 5 milliseconds.
+                let digits = format!("{:0<3}", fraction);
+                (clock, digits.parse::<u16>().map_err(|_| error())?)
+            }
+            None => (text, 0),
+        };
+
+        let mut fields = clock.split(':').map(|field| {
+            if field.len() == 2 && field.bytes().all(|b| b.is_ascii_digit()) {
+                field.parse::<u8>().ok()
+            } else {
+                None
+            }
+        });
+        let hour = fields.next().flatten().ok_or_else(error)?;
+        let minute = fields.next().flatten().ok_or_else(error)?;
+        let second = match fields.next() {
+            Some(second) => second.ok_or_else(error)?,
+            None if millisecond == 0 && !text.contains('.') => 0,
+            None => return Err(error()),
+        };
+        if fields.next().is_some() || hour > 23 || minute > 59 || second > 60 {
+            return Err(error());
+        }
+
+        Ok(ClockTime::new(hour, minute, second, millisecond))
+    }
+}
+
+/// The angles of the clock hands in degrees, clockwise from twelve o'clock,
+/// each in `0.0..360.0`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HandAngles {
+    pub hour: f32,
+    pub minute: f32,
+    pub second: f32,
+}
+
+impl HandAngles {
+    /// Wraps every angle into `0.0..360.0`. Close to a full turn `f32` can
+    /// round up to exactly `360.0`, which becomes `0.0`.
+    pub fn new(hour: f32, minute: f32, second: f32) -> Self {
+        HandAngles {
+            hour: wrap_degrees(hour),
+            minute: wrap_degrees(minute),
+            second: wrap_degrees(second),
+        }
+    }
+
+    /// The angles `progress` of the way from these to `to`, every hand
+    /// turning as `sweep` says. `0.0` gives these angles and `1.0` gives
+    /// `to`.
+    pub fn interpolate(
+        self,
+        to: HandAngles,
+        progress: f32,
+        sweep: Sweep,
+    ) -> Self {
+        HandAngles {
+            hour: interpolate_angle(self.hour, to.hour, progress, sweep),
+            minute: interpolate_angle(self.minute, to.minute, progress, sweep),
+            second: interpolate_angle(self.second, to.second, progress, sweep),
+        }
+    }
+}
+
+/// Which way a hand turns when it is animated from one angle to another.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sweep {
+    /// The shorter way around, clockwise when both ways are as long.
+    #[default]
+    ShortestArc,
+    /// Always clockwise, like a clock being wound forward, even when that
+    /// is almost a full turn.
+    Clockwise,
+}
+
+/// The angle in degrees `progress` of the way from `from` to `to`, turning
+/// as `sweep` says and wrapped into `0.0..360.0`.
+pub fn interpolate_angle(
+    from: f32,
+    to: f32,
+    progress: f32,
+    sweep: Sweep,
+) -> f32 {
+    let turn = (to - from).rem_euclid(360.0);
+    let turn = match sweep {
+        Sweep::ShortestArc if turn > 180.0 => turn - 360.0,
+        _ => turn,
+    };
+    // Finish exactly on `to` rather than wherever rounding lands.
+    if progress == 1.0 {
+        wrap_degrees(to)
+    } else {
+        wrap_degrees(from + turn * progress)
+    }
+}
+
+fn wrap_degrees(angle: f32) -> f32 {
+    let angle = angle.rem_euclid(360.0);
+    if angle >= 360.0 {
+        0.0
+    } else {
+        angle
+    }
+}
+
+/// How the hands move over time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClockMode {
+    /// Every hand sweeps continuously: milliseconds move the second hand,
+    /// seconds move the minute hand and minutes move the hour hand.
+    #[default]
+    Smooth,
+    /// The second and minute hands jump once per second and minute, like a
+    /// quartz clock. The hour hand still advances with the minutes so it
+    /// sits between the numbers at half past.
+    Tick,
+    /// Like [`ClockMode::Smooth`], but the hour hand goes around the dial
+    /// once per day instead of twice, with midnight at the top.
+    Dial24,
+}
+
+impl ClockMode {
+    pub fn angles(self, time: ClockTime) -> HandAngles {
+        let hour = time.hour as f32;
+        let minute = time.minute as f32;
+        let second = time.second as f32;
+        let millisecond = time.millisecond as f32;
+
+        match self {
+            ClockMode::Smooth | ClockMode::Dial24 => {
+                // 60 seconds = 360 degrees, 1 second = 6 degrees.
+                let second = (second + millisecond / 1000.0) * 6.0;
+                // 1 minute = 6 degrees, plus 1/60th of the second hand.
+                let minute = minute * 6.0 + second / 60.0;
+                let hour = if self == ClockMode::Dial24 {
+                    // 24 hours = 360 degrees, 1 hour = 15 degrees.
+                    hour * 15.0 + minute / 24.0
+                } else {
+                    // 12 hours = 360 degrees, 1 hour = 30 degrees.
+                    (hour % 12.0) * 30.0 + minute / 12.0
+                };
+
+                HandAngles::new(hour, minute, second)
+            }
+            ClockMode::Tick => HandAngles::new(
+                // 60 minutes = 30 degrees, 1 minute = 0.5 degree.
+                (hour % 12.0) * 30.0 + minute * 0.5,
+                minute * 6.0,
+                second * 6.0,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-3;
+
+    fn assert_angles(actual: HandAngles, hour: f32, minute: f32, second: f32) {
+        assert!(
+            (actual.hour - hour).abs() < EPSILON
+                && (actual.minute - minute).abs() < EPSILON
+                && (actual.second - second).abs() < EPSILON,
+            "expected ({}, {}, {}), got {:?}",
+            hour,
+            minute,
+            second,
+            actual
+        );
+    }
+
+    const MODES: [ClockMode; 3] =
+        [ClockMode::Smooth, ClockMode::Tick, ClockMode::Dial24];
+
+    #[test]
+    fn midnight_is_straight_up_in_every_mode() {
+        for mode in MODES {
+            assert_angles(mode.angles(ClockTime::hms(0, 0, 0)), 0.0, 0.0, 0.0);
+        }
+    }
+
+    #[test]
+    fn noon_wraps_the_twelve_hour_dial() {
+        let noon = ClockTime::hms(12, 0, 0);
+        assert_angles(ClockMode::Smooth.angles(noon), 0.0, 0.0, 0.0);
+        assert_angles(ClockMode::Tick.angles(noon), 0.0, 0.0, 0.0);
+        assert_angles(ClockMode::Dial24.angles(noon), 180.0, 0.0, 0.0);
+    }
+
+    #[test]
+    fn afternoon_matches_morning_on_twelve_hour_dial() {
+        for mode in [ClockMode::Smooth, ClockMode::Tick] {
+            for hour in 0..12 {
+                let morning = mode.angles(ClockTime::new(hour, 17, 42, 250));
+                let evening =
+                    mode.angles(ClockTime::new(hour + 12, 17, 42, 250));
+                assert_eq!(morning, evening);
+            }
+        }
+    }
+
+    #[test]
+    fn quarter_hours() {
+        let time = ClockTime::hms(3, 15, 0);
+        assert_angles(ClockMode::Smooth.angles(time), 97.5, 90.0, 0.0);
+        assert_angles(ClockMode::Tick.angles(time), 97.5, 90.0, 0.0);
+        assert_angles(ClockMode::Dial24.angles(time), 48.75, 90.0, 0.0);
+
+        let time = ClockTime::hms(18, 45, 0);
+        assert_angles(ClockMode::Smooth.angles(time), 202.5, 270.0, 0.0);
+        assert_angles(ClockMode::Dial24.angles(time), 281.25, 270.0, 0.0);
+    }
+
+    #[test]
+    fn smooth_sweeps_and_tick_jumps() {
+        let time = ClockTime::new(10, 8, 30, 500);
+        // 30.5 s; 8 min + 30.5 s; 10 h + 8.508 min
+        assert_angles(ClockMode::Smooth.angles(time), 304.254, 51.05, 183.0);
+        assert_angles(ClockMode::Tick.angles(time), 304.0, 48.0, 180.0);
+    }
+
+    #[test]
+    fn last_millisecond_of_the_day_is_just_before_a_full_turn() {
+        let time = ClockTime::new(23, 59, 59, 999);
+        // How far each hand is from completing its turn.
+        let short_of_twelve = |angle: f32| (360.0 - angle) % 360.0;
+        for mode in [ClockMode::Smooth, ClockMode::Dial24] {
+            let angles = mode.angles(time);
+            assert!(short_of_twelve(angles.hour) < 0.01);
+            assert!(short_of_twelve(angles.minute) < 0.01);
+            assert!(short_of_twelve(angles.second) < 0.01);
+        }
+
+        assert_angles(ClockMode::Tick.angles(time), 359.5, 354.0, 354.0);
+    }
+
+    #[test]
+    fn leap_second_is_clamped_to_the_end_of_the_minute() {
+        let leap = ClockTime::new(23, 59, 60, 500);
+        assert_eq!(leap, ClockTime::new(23, 59, 59, 999));
+        assert_eq!((leap.second(), leap.millisecond()), (59, 999));
+
+        for mode in MODES {
+            let angles = mode.angles(leap);
+            assert!(angles.second < 360.0);
+            assert!(angles.minute < 360.0);
+        }
+    }
+
+    #[test]
+    fn out_of_range_fields_are_clamped() {
+        let time = ClockTime::new(99, 99, 99, 9999);
+        assert_eq!(time, ClockTime::new(23, 59, 59, 999));
+        assert_eq!(ClockTime::new(1, 2, 3, 1000).millisecond(), 999);
+    }
+
+    fn assert_turn(from: f32, to: f32, sweep: Sweep, halfway: f32) {
+        let actual = interpolate_angle(from, to, 0.5, sweep);
+        assert!(
+            (actual - halfway).abs() < EPSILON,
+            "{} to {} {:?}: expected {} halfway, got {}",
+            from,
+            to,
+            sweep,
+            halfway,
+            actual
+        );
+    }
+
+    #[test]
+    fn shortest_arc_turns_the_short_way_around() {
+        // Clockwise and counterclockwise without wrapping.
+        assert_turn(30.0, 90.0, Sweep::ShortestArc, 60.0);
+        assert_turn(90.0, 30.0, Sweep::ShortestArc, 60.0);
+        // Clockwise and counterclockwise through twelve o'clock.
+        assert_turn(350.0, 10.0, Sweep::ShortestArc, 0.0);
+        assert_turn(10.0, 350.0, Sweep::ShortestArc, 0.0);
+        assert_turn(340.0, 0.0, Sweep::ShortestArc, 350.0);
+        assert_turn(0.0, 340.0, Sweep::ShortestArc, 350.0);
+        // Half a turn either way goes clockwise.
+        assert_turn(0.0, 180.0, Sweep::ShortestArc, 90.0);
+        assert_turn(270.0, 90.0, Sweep::ShortestArc, 0.0);
+        assert_turn(90.0, 270.0, Sweep::ShortestArc, 180.0);
+    }
+
+    #[test]
+    fn clockwise_never_turns_back() {
+        assert_turn(30.0, 90.0, Sweep::Clockwise, 60.0);
+        assert_turn(350.0, 10.0, Sweep::Clockwise, 0.0);
+        // Almost a full turn rather than a little way back.
+        assert_turn(90.0, 30.0, Sweep::Clockwise, 240.0);
+        assert_turn(10.0, 350.0, Sweep::Clockwise, 180.0);
+        assert_turn(0.0, 359.0, Sweep::Clockwise, 179.5);
+        // From twelve o'clock, like the bonus clock's intro.
+        assert_turn(0.0, 304.0, Sweep::Clockwise, 152.0);
+    }
+
+    #[test]
+    fn hands_that_are_already_there_stay_put() {
+        for sweep in [Sweep::ShortestArc, Sweep::Clockwise] {
+            for angle in [0.0, 123.0, 359.5] {
+                for progress in [0.0, 0.5, 1.0] {
+                    assert_eq!(
+                        interpolate_angle(angle, angle, progress, sweep),
+                        angle
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn interpolation_starts_and_ends_on_the_angles() {
+        for sweep in [Sweep::ShortestArc, Sweep::Clockwise] {
+            for (from, to) in [(350.0, 10.0), (10.0, 350.0), (0.0, 359.999)] {
+                assert_eq!(interpolate_angle(from, to, 0.0, sweep), from);
+                assert_eq!(interpolate_angle(from, to, 1.0, sweep), to);
+            }
+            // Unwrapped angles come out wrapped.
+            assert_eq!(interpolate_angle(-10.0, 720.0, 1.0, sweep), 0.0);
+            assert_eq!(interpolate_angle(-10.0, 10.0, 0.0, sweep), 350.0);
+        }
+    }
+
+    #[test]
+    fn springs_overshoot_past_the_target() {
+        // Progress beyond 1 keeps turning and wraps through twelve.
+        let angle = interpolate_angle(300.0, 350.0, 1.5, Sweep::Clockwise);
+        assert!((angle - 15.0).abs() < EPSILON);
+        let angle = interpolate_angle(10.0, 350.0, -0.5, Sweep::ShortestArc);
+        assert!((angle - 20.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn every_hand_interpolates_between_two_times() {
+        // Clocks going back an hour for the end of daylight saving time.
+        let from = ClockMode::Smooth.angles(ClockTime::hms(2, 59, 30));
+        let to = ClockMode::Smooth.angles(ClockTime::hms(1, 59, 45));
+
+        let shortest = from.interpolate(to, 0.5, Sweep::ShortestArc);
+        assert_angles(shortest, 74.8125, 357.75, 225.0);
+        let forward = from.interpolate(to, 0.5, Sweep::Clockwise);
+        assert_angles(forward, 254.8125, 357.75, 225.0);
+        assert_eq!(from.interpolate(to, 1.0, Sweep::Clockwise), to);
+    }
+
+    #[test]
+    fn millisecond_of_day_round_trips() {
+        for time in [
+            ClockTime::hms(0, 0, 0),
+            ClockTime::new(10, 8, 30, 500),
+            ClockTime::new(23, 59, 59, 999),
+        ] {
+            assert_eq!(
+                ClockTime::from_millisecond_of_day(
+                    time.millisecond_of_day() as u64
+                ),
+                time
+            );
+        }
+        assert_eq!(
+            ClockTime::new(23, 59, 59, 999).millisecond_of_day(),
+            MILLISECONDS_PER_DAY - 1
+        );
+        assert_eq!(
+            ClockTime::from_millisecond_of_day(MILLISECONDS_PER_DAY as u64),
+            ClockTime::hms(0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn adding_milliseconds_wraps_around_midnight() {
+        let time = ClockTime::new(23, 59, 59, 500);
+        assert_eq!(
+            time.wrapping_add_milliseconds(1000),
+            ClockTime::new(0, 0, 0, 500)
+        );
+        assert_eq!(
+            ClockTime::hms(0, 0, 0).wrapping_add_milliseconds(-1),
+            ClockTime::new(23, 59, 59, 999)
+        );
+        assert_eq!(
+            time.wrapping_add_milliseconds(
+                3 * MILLISECONDS_PER_DAY as i64 + 500
+            ),
+            ClockTime::hms(0, 0, 0)
+        );
+        assert!(time.wrapping_add_milliseconds(i64::MIN).hour() < 24);
+    }
+
+    #[test]
+    fn parses_and_displays_times() {
+        let parse = |text: &str| text.parse::<ClockTime>();
+
+        assert_eq!(parse("10:08"), Ok(ClockTime::hms(10, 8, 0)));
+        assert_eq!(parse("10:08:30"), Ok(ClockTime::hms(10, 8, 30)));
+        assert_eq!(parse("10:08:30.5"), Ok(ClockTime::new(10, 8, 30, 500)));
+        assert_eq!(parse("10:08:30.025"), Ok(ClockTime::new(10, 8, 30, 25)));
+        assert_eq!(parse("23:59:60"), Ok(ClockTime::new(23, 59, 59, 999)));
+
+        for invalid in [
+            "",
+            "10",
+            "1:08",
+            "10:8",
+            "24:00",
+            "10:60",
+            "10:08:61",
+            "10:08:30.",
+            "10:08:30.1234",
+            "10:08.5",
+            "10:08:30:00",
+            "aa:bb",
+        ] {
+            assert!(parse(invalid).is_err(), "{:?}", invalid);
+        }
+
+        assert_eq!(ClockTime::hms(1, 2, 3).to_string(), "01:02:03");
+        assert_eq!(ClockTime::new(1, 2, 3, 40).to_string(), "01:02:03.040");
+        let time = ClockTime::new(13, 14, 15, 160);
+        assert_eq!(parse(&time.to_string()), Ok(time));
+    }
+
+    #[test]
+    fn every_second_of_the_day_stays_on_the_dial() {
+        for mode in MODES {
+            for hour in 0..24 {
+                for minute in 0..60 {
+                    for second in 0..60 {
+                        let angles = mode
+                            .angles(ClockTime::new(hour, minute, second, 999));
+                        for angle in [angles.hour, angles.minute, angles.second]
+                        {
+                            assert!(
+                                (0.0..360.0).contains(&angle),
+                                "{:?} at {}:{}:{}",
+                                mode,
+                                hour,
+                                minute,
+                                second
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_hands_never_move_backwards_within_their_period() {
+        let mut previous = ClockMode::Smooth.angles(ClockTime::hms(0, 0, 0));
+        for minute_of_day in 1..(24 * 60) {
+            let time = ClockTime::hms(
+                (minute_of_day / 60) as u8,
+                (minute_of_day % 60) as u8,
+                0,
+            );
+            let angles = ClockMode::Smooth.angles(time);
+
+            if minute_of_day % 60 != 0 {
+                assert!(angles.minute > previous.minute);
+            } else {
+                // The minute hand wraps back to twelve on the hour.
+                assert_eq!(angles.minute, 0.0);
+            }
+            if minute_of_day != 12 * 60 {
+                assert!(angles.hour > previous.hour);
+            } else {
+                assert_eq!(angles.hour, 0.0);
+            }
+            previous = angles;
+        }
+    }
+
+    #[test]
+    fn hands_agree_with_each_other() {
+        // In smooth mode the minute hand moves 1/60th as fast as the second
+        // hand and the hour hand 1/12th as fast as the minute hand.
+        for (hour, minute, second, millisecond) in [
+            (0, 0, 1, 0),
+            (4, 20, 0, 0),
+            (7, 59, 30, 250),
+            (11, 11, 11, 111),
+        ] {
+            let time = ClockTime::new(hour, minute, second, millisecond);
+            let angles = ClockMode::Smooth.angles(time);
+            let seconds = second as f32 + millisecond as f32 / 1000.0;
+            let minutes = minute as f32 + seconds / 60.0;
+            let hours = hour as f32 + minutes / 60.0;
+
+            assert!((angles.second - seconds * 6.0).abs() < EPSILON);
+            assert!((angles.minute - minutes * 6.0).abs() < EPSILON);
+            assert!((angles.hour - hours * 30.0).abs() < EPSILON);
+        }
+    }
+}
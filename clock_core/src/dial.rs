@@ -0,0 +1,200 @@
+use crate::geometry::{Matrix, Point};
+
+/// How a kind of tick mark looks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TickStyle {
+    /// Length as a fraction of the dial radius, measured inwards from the rim.
+    pub length: f32,
+    /// Stroke width in device independent pixels.
+    pub width: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TickKind {
+    Hour,
+    Minute,
+}
+
+/// A tick mark as a line segment from the rim of the dial inwards.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tick {
+    pub kind: TickKind,
+    /// The end on the rim.
+    pub outer: Point,
+    pub inner: Point,
+    pub width: f32,
+}
+
+/// The tick marks around a dial: one every five minutes for the hours and,
+/// optionally, one for every other minute.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TickLayout {
+    pub hour: TickStyle,
+    pub minute: Option<TickStyle>,
+}
+
+impl Default for TickLayout {
+    fn default() -> Self {
+        TickLayout {
+            hour: TickStyle {
+                length: 0.1,
+                width: 2.0,
+            },
+            minute: Some(TickStyle {
+                length: 0.04,
+                width: 1.0,
+            }),
+        }
+    }
+}
+
+impl TickLayout {
+    /// Twelve hour ticks and no minute ticks.
+    pub fn hours_only(hour: TickStyle) -> Self {
+        TickLayout { hour, minute: None }
+    }
+
+    /// The tick marks of a dial with the given center and radius, clockwise
+    /// starting at twelve o'clock.
+    pub fn ticks(&self, center: Point, radius: f32) -> Vec<Tick> {
+        (0..60)
+            .filter_map(|minute| {
+                let (kind, style) = if minute % 5 == 0 {
+                    (TickKind::Hour, self.hour)
+                } else {
+                    (TickKind::Minute, self.minute?)
+                };
+
+                // Lay the tick out at twelve o'clock and turn it into place.
+                let rotation = Matrix::rotation(minute as f32 * 6.0, center);
+                let outer = Point::new(center.x, center.y - radius);
+                let inner = Point::new(
+                    center.x,
+                    center.y - radius * (1.0 - style.length),
+                );
+
+                Some(Tick {
+                    kind,
+                    outer: rotation.transform_point(outer),
+                    inner: rotation.transform_point(inner),
+                    width: style.width,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-3;
+    const CENTER: Point = Point::new(256.0, 200.0);
+    const RADIUS: f32 = 180.0;
+
+    fn angle_of(point: Point) -> f32 {
+        // Clockwise from twelve o'clock, in degrees.
+        let angle = (point.x - CENTER.x).atan2(CENTER.y - point.y).to_degrees();
+        angle.rem_euclid(360.0)
+    }
+
+    #[test]
+    fn default_layout_has_sixty_ticks() {
+        let ticks = TickLayout::default().ticks(CENTER, RADIUS);
+        assert_eq!(ticks.len(), 60);
+
+        let hours = ticks
+            .iter()
+            .filter(|tick| tick.kind == TickKind::Hour)
+            .count();
+        assert_eq!(hours, 12);
+    }
+
+    #[test]
+    fn every_tick_lies_on_the_dial() {
+        let layout = TickLayout::default();
+        for tick in layout.ticks(CENTER, RADIUS) {
+            let style = match tick.kind {
+                TickKind::Hour => layout.hour,
+                TickKind::Minute => layout.minute.unwrap(),
+            };
+
+            assert!((tick.outer.distance(CENTER) - RADIUS).abs() < EPSILON);
+            assert!(
+                (tick.inner.distance(CENTER) - RADIUS * (1.0 - style.length))
+                    .abs()
+                    < EPSILON
+            );
+            assert!(
+                (tick.outer.distance(tick.inner) - RADIUS * style.length).abs()
+                    < EPSILON
+            );
+            assert_eq!(tick.width, style.width);
+        }
+    }
+
+    #[test]
+    fn ticks_point_at_the_center_and_are_evenly_spaced() {
+        let ticks = TickLayout::default().ticks(CENTER, RADIUS);
+        for (minute, tick) in ticks.iter().enumerate() {
+            let expected = minute as f32 * 6.0;
+            let outer = angle_of(tick.outer);
+            let inner = angle_of(tick.inner);
+
+            // Both ends share the angle, so the segment is radial.
+            for angle in [outer, inner] {
+                let error = (angle - expected).abs();
+                assert!(
+                    error < EPSILON || (360.0 - error) < EPSILON,
+                    "tick {} at {} degrees",
+                    minute,
+                    angle
+                );
+            }
+            assert_eq!(
+                tick.kind == TickKind::Hour,
+                minute % 5 == 0,
+                "tick {}",
+                minute
+            );
+        }
+    }
+
+    #[test]
+    fn quarter_hours_are_axis_aligned() {
+        let ticks = TickLayout::default().ticks(CENTER, RADIUS);
+        let rim = |minute: usize| ticks[minute].outer;
+
+        let expected = [
+            (0, Point::new(CENTER.x, CENTER.y - RADIUS)),
+            (15, Point::new(CENTER.x + RADIUS, CENTER.y)),
+            (30, Point::new(CENTER.x, CENTER.y + RADIUS)),
+            (45, Point::new(CENTER.x - RADIUS, CENTER.y)),
+        ];
+        for (minute, point) in expected {
+            assert!(rim(minute).distance(point) < EPSILON, "tick {}", minute);
+        }
+    }
+
+    #[test]
+    fn hours_only_layout_has_twelve_ticks() {
+        let layout = TickLayout::hours_only(TickStyle {
+            length: 0.2,
+            width: 3.0,
+        });
+        let ticks = layout.ticks(CENTER, RADIUS);
+
+        assert_eq!(ticks.len(), 12);
+        assert!(ticks.iter().all(|tick| tick.kind == TickKind::Hour));
+        let three = Point::new(CENTER.x + RADIUS * 0.8, CENTER.y);
+        assert!(ticks[3].inner.distance(three) < EPSILON);
+    }
+
+    #[test]
+    fn empty_dial_collapses_to_the_center() {
+        for tick in TickLayout::default().ticks(CENTER, 0.0) {
+            assert!(tick.outer.distance(CENTER) < EPSILON);
+            assert!(tick.inner.distance(CENTER) < EPSILON);
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use std::ops::Mul;
+
+/// A point in device independent pixels, with `y` pointing down.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub const fn new(x: f32, y: f32) -> Self {
+        Point { x, y }
+    }
+
+    pub fn distance(self, other: Point) -> f32 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+/// A 2D affine transform with the same layout and conventions as Direct2D's
+/// `Matrix3x2`: points are row vectors, so `a * b` applies `a` first and `b`
+/// second.
+///
+/// ```text
+/// [x' y' 1] = [x y 1] * | m11 m12 0 |
+///                       | m21 m22 0 |
+///                       | m31 m32 1 |
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix {
+    pub m11: f32,
+    pub m12: f32,
+    pub m21: f32,
+    pub m22: f32,
+    pub m31: f32,
+    pub m32: f32,
+}
+
+impl Matrix {
+    pub const fn identity() -> Self {
+        Matrix {
+            m11: 1.0,
+            m12: 0.0,
+            m21: 0.0,
+            m22: 1.0,
+            m31: 0.0,
+            m32: 0.0,
+        }
+    }
+
+    pub const fn translation(x: f32, y: f32) -> Self {
+        Matrix {
+            m31: x,
+            m32: y,
+            ..Matrix::identity()
+        }
+    }
+
+    pub const fn scale(x: f32, y: f32) -> Self {
+        Matrix {
+            m11: x,
+            m22: y,
+            ..Matrix::identity()
+        }
+    }
+
+    /// A rotation by `angle` degrees around `center`. Since `y` points down,
+    /// positive angles turn clockwise on screen, just like the clock hands.
+    pub fn rotation(angle: f32, center: Point) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Matrix {
+            m11: cos,
+            m12: sin,
+            m21: -sin,
+            m22: cos,
+            m31: center.x - center.x * cos + center.y * sin,
+            m32: center.y - center.x * sin - center.y * cos,
+        }
+    }
+
+    /// The transform undoing this one, `None` if it collapses the plane onto
+    /// a line or a point.
+    pub fn invert(&self) -> Option<Matrix> {
+        let determinant = self.m11 * self.m22 - self.m12 * self.m21;
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+
+        let m11 = self.m22 / determinant;
+        let m12 = -self.m12 / determinant;
+        let m21 = -self.m21 / determinant;
+        let m22 = self.m11 / determinant;
+        Some(Matrix {
+            m11,
+            m12,
+            m21,
+            m22,
+            m31: -(self.m31 * m11 + self.m32 * m21),
+            m32: -(self.m31 * m12 + self.m32 * m22),
+        })
+    }
+
+    pub fn transform_point(&self, point: Point) -> Point {
+        Point {
+            x: point.x * self.m11 + point.y * self.m21 + self.m31,
+            y: point.x * self.m12 + point.y * self.m22 + self.m32,
+        }
+    }
+}
+
+impl Default for Matrix {
+    fn default() -> Self {
+        Matrix::identity()
+    }
+}
+
+impl Mul for Matrix {
+    type Output = Matrix;
+
+    fn mul(self, rhs: Matrix) -> Matrix {
+        Matrix {
+            m11: self.m11 * rhs.m11 + self.m12 * rhs.m21,
+            m12: self.m11 * rhs.m12 + self.m12 * rhs.m22,
+            m21: self.m21 * rhs.m11 + self.m22 * rhs.m21,
+            m22: self.m21 * rhs.m12 + self.m22 * rhs.m22,
+            m31: self.m31 * rhs.m11 + self.m32 * rhs.m21 + rhs.m31,
+            m32: self.m31 * rhs.m12 + self.m32 * rhs.m22 + rhs.m32,
+        }
+    }
+}
+
+#[cfg(windows)]
+mod win32 {
+    use super::{Matrix, Point};
+    use windows::{
+        Foundation::Numerics::Matrix3x2,
+        Win32::Graphics::Direct2D::Common::D2D_POINT_2F,
+    };
+
+    impl From<Point> for D2D_POINT_2F {
+        fn from(point: Point) -> Self {
+            D2D_POINT_2F {
+                x: point.x,
+                y: point.y,
+            }
+        }
+    }
+
+    impl From<D2D_POINT_2F> for Point {
+        fn from(point: D2D_POINT_2F) -> Self {
+            Point::new(point.x, point.y)
+        }
+    }
+
+    impl From<Matrix> for Matrix3x2 {
+        fn from(matrix: Matrix) -> Self {
+            Matrix3x2 {
+                M11: matrix.m11,
+                M12: matrix.m12,
+                M21: matrix.m21,
+                M22: matrix.m22,
+                M31: matrix.m31,
+                M32: matrix.m32,
+            }
+        }
+    }
+
+    impl From<Matrix3x2> for Matrix {
+        fn from(matrix: Matrix3x2) -> Self {
+            Matrix {
+                m11: matrix.M11,
+                m12: matrix.M12,
+                m21: matrix.M21,
+                m22: matrix.M22,
+                m31: matrix.M31,
+                m32: matrix.M32,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_near(actual: Point, expected: Point) {
+        assert!(
+            actual.distance(expected) < EPSILON,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn identity_keeps_points() {
+        let point = Point::new(3.0, -4.5);
+        assert_eq!(Matrix::identity().transform_point(point), point);
+        assert_eq!(Matrix::default(), Matrix::identity());
+    }
+
+    #[test]
+    fn translation_and_scale() {
+        let point = Point::new(2.0, 3.0);
+        assert_near(
+            Matrix::translation(10.0, -1.0).transform_point(point),
+            Point::new(12.0, 2.0),
+        );
+        assert_near(
+            Matrix::scale(2.0, 0.5).transform_point(point),
+            Point::new(4.0, 1.5),
+        );
+    }
+
+    #[test]
+    fn rotation_turns_clockwise_on_screen() {
+        let center = Point::new(100.0, 100.0);
+        let twelve = Point::new(100.0, 50.0);
+
+        assert_near(
+            Matrix::rotation(90.0, center).transform_point(twelve),
+            Point::new(150.0, 100.0),
+        );
+        assert_near(
+            Matrix::rotation(180.0, center).transform_point(twelve),
+            Point::new(100.0, 150.0),
+        );
+        assert_near(
+            Matrix::rotation(-90.0, center).transform_point(twelve),
+            Point::new(50.0, 100.0),
+        );
+        assert_near(
+            Matrix::rotation(33.0, center).transform_point(center),
+            center,
+        );
+    }
+
+    #[test]
+    fn multiplication_applies_left_first() {
+        let point = Point::new(0.0, -1.0);
+        let rotate = Matrix::rotation(90.0, Point::default());
+        let translate = Matrix::translation(5.0, 0.0);
+
+        assert_near(
+            (rotate * translate).transform_point(point),
+            Point::new(6.0, 0.0),
+        );
+        assert_near(
+            (translate * rotate).transform_point(point),
+            Point::new(1.0, 5.0),
+        );
+    }
+
+    #[test]
+    fn inverse_undoes_the_transform() {
+        let transform = Matrix::scale(2.0, 0.5)
+            * Matrix::rotation(30.0, Point::new(4.0, 4.0))
+            * Matrix::translation(-3.0, 8.0);
+        let inverse = transform.invert().unwrap();
+
+        for point in [Point::new(0.0, 0.0), Point::new(-7.0, 2.5)] {
+            assert_near(
+                inverse.transform_point(transform.transform_point(point)),
+                point,
+            );
+            assert_near(
+                transform.transform_point(inverse.transform_point(point)),
+                point,
+            );
+        }
+    }
+
+    #[test]
+    fn degenerate_transforms_have_no_inverse() {
+        assert_eq!(Matrix::scale(0.0, 1.0).invert(), None);
+        assert_eq!(Matrix::scale(f32::INFINITY, 1.0).invert(), None);
+    }
+
+    #[test]
+    fn rotation_around_a_center_is_translate_rotate_translate() {
+        let center = Point::new(7.0, -3.0);
+        let composed = Matrix::translation(-center.x, -center.y)
+            * Matrix::rotation(40.0, Point::default())
+            * Matrix::translation(center.x, center.y);
+        let direct = Matrix::rotation(40.0, center);
+
+        for point in [Point::new(0.0, 0.0), Point::new(12.0, 4.0)] {
+            assert_near(
+                composed.transform_point(point),
+                direct.transform_point(point),
+            );
+        }
+    }
+}
@@ -0,0 +1,25 @@
+//! The platform-independent model of an analog clock: which angle every hand
+//! points at for a given time of day, where that time of day comes from, and
+//! the geometry of the dial, how the hands are animated and when frames are
+//! drawn.
+
+pub mod animation;
+mod clock;
+mod dial;
+mod geometry;
+mod scheduler;
+mod stats;
+mod time_source;
+
+pub use clock::{
+    interpolate_angle, ClockMode, ClockTime, HandAngles, ParseClockTimeError,
+    Sweep, MILLISECONDS_PER_DAY,
+};
+pub use dial::{Tick, TickKind, TickLayout, TickStyle};
+pub use geometry::{Matrix, Point};
+pub use scheduler::{Frame, FrameScheduler, Pacing};
+pub use stats::{FrameSample, FrameStats, Summary};
+pub use time_source::{
+    parse_time_arg, Accelerated, FakeClock, FixedTime, Offset, SystemClock,
+    TimeSource,
+};
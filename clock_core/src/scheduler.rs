@@ -0,0 +1,364 @@
+use crate::clock::{ClockMode, ClockTime};
+
+/// How often a [`FrameScheduler`] has frames drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pacing {
+    /// At the target rate, for hands that sweep or while something is
+    /// animated.
+    #[default]
+    Continuous,
+    /// Once whenever the second of the clock changes, for hands that don't
+    /// move in between.
+    Idle,
+}
+
+impl Pacing {
+    /// Idle for clocks whose hands only jump once a second.
+    pub fn for_mode(mode: ClockMode) -> Self {
+        match mode {
+            ClockMode::Tick => Pacing::Idle,
+            ClockMode::Smooth | ClockMode::Dial24 => Pacing::Continuous,
+        }
+    }
+}
+
+/// A frame to draw, as returned by [`FrameScheduler::begin_frame`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    /// Fixed updates to run before drawing.
+    pub updates: u32,
+    /// Updates that were due but dropped because the frame came too late to
+    /// catch up on all of them.
+    pub skipped_updates: u32,
+    /// Frames that should have been drawn since the last one.
+    pub missed_frames: u32,
+    /// When the last update of this frame is, in seconds on the scheduler's
+    /// clock.
+    pub update_time: f64,
+    /// How far the frame is from the last update towards the next one, in
+    /// `0.0..1.0`, for drawing in between.
+    pub interpolation: f64,
+    step: f64,
+}
+
+impl Frame {
+    /// When every update of the frame is, oldest first.
+    pub fn update_times(&self) -> impl Iterator<Item = f64> {
+        let Frame {
+            updates,
+            update_time,
+            step,
+            ..
+        } = *self;
+        (0..updates).map(move |update| {
+            update_time - (updates - 1 - update) as f64 * step
+        })
+    }
+}
+
+/// Decides when to draw frames and how many fixed updates to run before
+/// each one, replacing a waitable timer that fires at a fixed rate.
+///
+/// Times are seconds on any monotonic clock, like the performance counter,
+/// and the time of day is only used to wake idle clocks on the second, so
+/// the scheduler runs just as well against a fake clock.
+#[derive(Clone, Debug)]
+pub struct FrameScheduler {
+    frame_interval: f64,
+    update_step: f64,
+    max_updates: u32,
+    pacing: Pacing,
+    // When the last frame was drawn and the second of the day it showed,
+    // `None` before the first frame.
+    last_frame: Option<(f64, u32)>,
+    update_time: f64,
+}
+
+impl FrameScheduler {
+    /// Frames and updates `rate` times a second.
+    ///
+    /// # Panics
+    ///
+    /// If `rate` isn't a positive number.
+    pub fn new(rate: f64) -> Self {
+        FrameScheduler {
+            frame_interval: interval(rate),
+            update_step: interval(rate),
+            max_updates: 5,
+            pacing: Pacing::Continuous,
+            last_frame: None,
+            update_time: 0.0,
+        }
+    }
+
+    /// Runs fixed updates `rate` times a second, however often frames are
+    /// drawn.
+    pub fn with_update_rate(mut self, rate: f64) -> Self {
+        self.update_step = interval(rate);
+        self
+    }
+
+    /// Drops updates beyond `max_updates` in a single frame rather than
+    /// falling further and further behind. At least one update runs.
+    pub fn with_max_updates_per_frame(mut self, max_updates: u32) -> Self {
+        self.max_updates = max_updates.max(1);
+        self
+    }
+
+    pub fn with_pacing(mut self, pacing: Pacing) -> Self {
+        self.pacing = pacing;
+        self
+    }
+
+    pub fn set_pacing(&mut self, pacing: Pacing) {
+        self.pacing = pacing;
+    }
+
+    pub fn pacing(&self) -> Pacing {
+        self.pacing
+    }
+
+    /// How many seconds to wait from `now` before the next frame, `0.0`
+    /// when it is due.
+    pub fn time_until_next_frame(
+        &self,
+        now: f64,
+        time_of_day: ClockTime,
+    ) -> f64 {
+        let (last_frame, last_second) = match self.last_frame {
+            Some(last_frame) => last_frame,
+            None => return 0.0,
+        };
+
+        match self.pacing {
+            Pacing::Continuous => {
+                (last_frame + self.frame_interval - now).max(0.0)
+            }
+            Pacing::Idle if second_of_day(time_of_day) != last_second => 0.0,
+            Pacing::Idle => (1000 - time_of_day.millisecond()) as f64 / 1000.0,
+        }
+    }
+
+    /// Starts a frame at `now` showing `time_of_day`.
+    pub fn begin_frame(&mut self, now: f64, time_of_day: ClockTime) -> Frame {
+        let last_frame =
+            self.last_frame.replace((now, second_of_day(time_of_day)));
+        let last_frame = match last_frame {
+            // Idle frames are far apart, so they don't catch up on updates.
+            Some((last_frame, _)) if self.pacing == Pacing::Continuous => {
+                last_frame
+            }
+            _ => {
+                self.update_time = now;
+                return Frame {
+                    updates: 1,
+                    skipped_updates: 0,
+                    missed_frames: 0,
+                    update_time: now,
+                    interpolation: 0.0,
+                    step: self.update_step,
+                };
+            }
+        };
+
+        let missed_frames =
+            whole((now - last_frame) / self.frame_interval - 1.0) as u32;
+        let due = whole((now - self.update_time) / self.update_step);
+        let updates = (due as u32).min(self.max_updates);
+        self.update_time += due * self.update_step;
+
+        Frame {
+            updates,
+            skipped_updates: due as u32 - updates,
+            missed_frames,
+            update_time: self.update_time,
+            interpolation: ((now - self.update_time) / self.update_step)
+                .clamp(0.0, 1.0),
+            step: self.update_step,
+        }
+    }
+}
+
+fn interval(rate: f64) -> f64 {
+    assert!(rate > 0.0 && rate.is_finite(), "invalid rate {}", rate);
+    1.0 / rate
+}
+
+// Rounds down, except that a hair short of a whole number counts as one so
+// rounding errors in the times don't push updates into the next frame.
+fn whole(steps: f64) -> f64 {
+    (steps + 1e-9).floor().max(0.0)
+}
+
+fn second_of_day(time: ClockTime) -> u32 {
+    time.millisecond_of_day() / 1000
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{FakeClock, TimeSource};
+
+    // A fake monotonic clock next to the fake time of day.
+    struct Clocks {
+        now: f64,
+        time_of_day: FakeClock,
+    }
+
+    impl Clocks {
+        fn new() -> Self {
+            Clocks {
+                now: 100.0,
+                time_of_day: FakeClock::new(ClockTime::new(10, 8, 30, 250)),
+            }
+        }
+
+        fn advance(&mut self, seconds: f64) {
+            self.now += seconds;
+            self.time_of_day.advance(Duration::from_secs_f64(seconds));
+        }
+
+        fn wait(&self, scheduler: &FrameScheduler) -> f64 {
+            scheduler.time_until_next_frame(self.now, self.time_of_day.now())
+        }
+
+        fn frame(&self, scheduler: &mut FrameScheduler) -> Frame {
+            scheduler.begin_frame(self.now, self.time_of_day.now())
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn the_first_frame_is_due_at_once() {
+        let mut clocks = Clocks::new();
+        let mut scheduler = FrameScheduler::new(60.0);
+        assert_eq!(clocks.wait(&scheduler), 0.0);
+
+        let frame = clocks.frame(&mut scheduler);
+        assert_eq!((frame.updates, frame.skipped_updates), (1, 0));
+        assert_eq!(frame.update_time, 100.0);
+
+        assert_close(clocks.wait(&scheduler), 1.0 / 60.0);
+        clocks.advance(0.01);
+        assert_close(clocks.wait(&scheduler), 1.0 / 60.0 - 0.01);
+        clocks.advance(0.01);
+        assert_eq!(clocks.wait(&scheduler), 0.0);
+    }
+
+    #[test]
+    fn fixed_updates_are_independent_of_the_frame_rate() {
+        let mut clocks = Clocks::new();
+        let mut scheduler = FrameScheduler::new(30.0).with_update_rate(100.0);
+        clocks.frame(&mut scheduler);
+
+        // 35 ms is three updates with 5 ms left over.
+        clocks.advance(0.035);
+        let frame = clocks.frame(&mut scheduler);
+        assert_eq!(frame.updates, 3);
+        assert_close(frame.update_time, 100.03);
+        assert_close(frame.interpolation, 0.5);
+        let times: Vec<_> = frame.update_times().collect();
+        assert_eq!(times.len(), 3);
+        assert_close(times[0], 100.01);
+        assert_close(times[2], 100.03);
+
+        // The left over time counts towards the next frame.
+        clocks.advance(0.005);
+        assert_eq!(clocks.frame(&mut scheduler).updates, 1);
+    }
+
+    #[test]
+    fn frames_faster_than_updates_interpolate() {
+        let mut clocks = Clocks::new();
+        let mut scheduler = FrameScheduler::new(120.0).with_update_rate(30.0);
+        clocks.frame(&mut scheduler);
+
+        clocks.advance(1.0 / 120.0);
+        let frame = clocks.frame(&mut scheduler);
+        assert_eq!(frame.updates, 0);
+        assert_eq!(frame.update_times().count(), 0);
+        assert_close(frame.interpolation, 0.25);
+    }
+
+    #[test]
+    fn late_frames_skip_updates_and_frames() {
+        let mut clocks = Clocks::new();
+        let mut scheduler =
+            FrameScheduler::new(10.0).with_max_updates_per_frame(4);
+        clocks.frame(&mut scheduler);
+
+        // Stalled for a second, so nine frames were missed and ten updates
+        // are due.
+        clocks.advance(1.0);
+        let frame = clocks.frame(&mut scheduler);
+        assert_eq!(frame.missed_frames, 9);
+        assert_eq!((frame.updates, frame.skipped_updates), (4, 6));
+        assert_close(frame.update_time, 101.0);
+
+        // The next frame is due a frame after this one, not at once.
+        assert_close(clocks.wait(&scheduler), 0.1);
+        clocks.advance(0.1);
+        let frame = clocks.frame(&mut scheduler);
+        assert_eq!((frame.updates, frame.missed_frames), (1, 0));
+    }
+
+    #[test]
+    fn idle_clocks_wake_on_the_second() {
+        let mut clocks = Clocks::new();
+        let mut scheduler = FrameScheduler::new(60.0)
+            .with_pacing(Pacing::for_mode(ClockMode::Tick));
+        clocks.frame(&mut scheduler);
+
+        // 10:08:30.250, so the next second is 750 ms away.
+        assert_close(clocks.wait(&scheduler), 0.75);
+        clocks.advance(0.5);
+        assert_close(clocks.wait(&scheduler), 0.25);
+        clocks.advance(0.25);
+        assert_eq!(clocks.wait(&scheduler), 0.0);
+
+        // Idle frames don't catch up on the updates in between.
+        let frame = clocks.frame(&mut scheduler);
+        assert_eq!((frame.updates, frame.skipped_updates), (1, 0));
+        assert_close(clocks.wait(&scheduler), 1.0);
+    }
+
+    #[test]
+    fn idle_clocks_follow_jumps_of_the_time_of_day() {
+        let clocks = Clocks::new();
+        let mut scheduler = FrameScheduler::new(60.0).with_pacing(Pacing::Idle);
+        clocks.frame(&mut scheduler);
+
+        clocks.time_of_day.set(ClockTime::new(22, 0, 0, 900));
+        assert_eq!(clocks.wait(&scheduler), 0.0);
+    }
+
+    #[test]
+    fn switching_to_continuous_resumes_updates() {
+        let mut clocks = Clocks::new();
+        let mut scheduler = FrameScheduler::new(60.0).with_pacing(Pacing::Idle);
+        clocks.frame(&mut scheduler);
+        clocks.advance(1.0);
+        clocks.frame(&mut scheduler);
+
+        scheduler.set_pacing(Pacing::Continuous);
+        clocks.advance(0.04);
+        let frame = clocks.frame(&mut scheduler);
+        assert_eq!((frame.updates, frame.skipped_updates), (2, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid rate")]
+    fn rates_must_be_positive() {
+        FrameScheduler::new(0.0);
+    }
+}
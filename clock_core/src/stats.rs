@@ -0,0 +1,299 @@
+use std::{collections::VecDeque, fmt, io};
+
+/// What [`FrameStats`] knows about one frame. Times are in seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameSample {
+    /// When the frame started, on the clock passed to [`FrameStats::record`].
+    pub time: f64,
+    /// How long after the previous frame this one started.
+    pub frame_time: f64,
+    /// How long presenting the frame took, from the end of drawing until
+    /// `Present` or `EndDraw` returned.
+    pub present_latency: f64,
+    /// Frames that should have been drawn before this one but weren't.
+    pub dropped: u32,
+}
+
+/// Collects frame times and present latencies over the last frames, for
+/// percentiles, a histogram and a CSV export.
+#[derive(Clone, Debug)]
+pub struct FrameStats {
+    capacity: usize,
+    samples: VecDeque<FrameSample>,
+    last_frame: Option<f64>,
+    // Since the collector was created, not just over the kept samples.
+    frames: u64,
+    dropped: u64,
+}
+
+/// The statistics of the frames kept by a [`FrameStats`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Summary {
+    pub frames: usize,
+    pub mean_frame_time: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max_frame_time: f64,
+    pub mean_present_latency: f64,
+    /// Dropped frames since the collector was created.
+    pub dropped_frames: u64,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |seconds: f64| seconds * 1000.0;
+        write!(
+            f,
+            "{:.1} fps over {} frames, frame time p50 {:.2} ms, p95 {:.2} ms, \
+             p99 {:.2} ms, max {:.2} ms, present latency {:.2} ms, \
+             {} dropped",
+            1.0 / self.mean_frame_time,
+            self.frames,
+            ms(self.p50),
+            ms(self.p95),
+            ms(self.p99),
+            ms(self.max_frame_time),
+            ms(self.mean_present_latency),
+            self.dropped_frames
+        )
+    }
+}
+
+impl FrameStats {
+    /// Keeps the last `capacity` frames, at least one.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        FrameStats {
+            capacity,
+            samples: VecDeque::with_capacity(capacity),
+            last_frame: None,
+            frames: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Records a frame that started at `time`. The first frame only starts
+    /// the clock, since it has no frame time.
+    pub fn record(&mut self, time: f64, present_latency: f64, dropped: u32) {
+        let last_frame = self.last_frame.replace(time);
+        let last_frame = match last_frame {
+            Some(last_frame) => last_frame,
+            None => return,
+        };
+
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(FrameSample {
+            time,
+            frame_time: (time - last_frame).max(0.0),
+            present_latency,
+            dropped,
+        });
+        self.frames += 1;
+        self.dropped += dropped as u64;
+    }
+
+    /// The kept frames, oldest first.
+    pub fn samples(&self) -> impl ExactSizeIterator<Item = &FrameSample> {
+        self.samples.iter()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Frames recorded since the collector was created.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// The frame time that `percentile` percent of the kept frames are at
+    /// or under, by the nearest-rank method. `None` without frames.
+    pub fn percentile(&self, percentile: f64) -> Option<f64> {
+        percentile_of(&self.sorted_frame_times(), percentile)
+    }
+
+    pub fn summary(&self) -> Option<Summary> {
+        let sorted = self.sorted_frame_times();
+        let frames = sorted.len();
+        let mean = |total: f64| total / frames as f64;
+
+        Some(Summary {
+            frames,
+            mean_frame_time: mean(sorted.iter().sum()),
+            p50: percentile_of(&sorted, 50.0)?,
+            p95: percentile_of(&sorted, 95.0)?,
+            p99: percentile_of(&sorted, 99.0)?,
+            max_frame_time: *sorted.last()?,
+            mean_present_latency: mean(
+                self.samples.iter().map(|s| s.present_latency).sum(),
+            ),
+            dropped_frames: self.dropped,
+        })
+    }
+
+    /// Counts the kept frames by frame time in `buckets` buckets `width`
+    /// seconds wide, the last of which also counts everything longer.
+    pub fn histogram(&self, width: f64, buckets: usize) -> Vec<usize> {
+        let mut counts = vec![0; buckets];
+        if let Some(last) = buckets.checked_sub(1) {
+            for sample in &self.samples {
+                let bucket = (sample.frame_time / width) as usize;
+                counts[bucket.min(last)] += 1;
+            }
+        }
+        counts
+    }
+
+    /// Writes the kept frames as CSV with times in milliseconds, one row
+    /// per frame after a header.
+    pub fn write_csv(&self, mut writer: impl io::Write) -> io::Result<()> {
+        writeln!(writer, "time_ms,frame_time_ms,present_latency_ms,dropped")?;
+        for sample in &self.samples {
+            writeln!(
+                writer,
+                "{:.3},{:.3},{:.3},{}",
+                sample.time * 1000.0,
+                sample.frame_time * 1000.0,
+                sample.present_latency * 1000.0,
+                sample.dropped
+            )?;
+        }
+        Ok(())
+    }
+
+    fn sorted_frame_times(&self) -> Vec<f64> {
+        let mut times: Vec<_> =
+            self.samples.iter().map(|s| s.frame_time).collect();
+        times.sort_by(f64::total_cmp);
+        times
+    }
+}
+
+fn percentile_of(sorted: &[f64], percentile: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (percentile.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64)
+        .ceil() as usize;
+    Some(sorted[rank.max(1) - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Frames starting at the given times, in milliseconds.
+    fn stats(times: &[f64]) -> FrameStats {
+        let mut stats = FrameStats::new(100);
+        for &time in times {
+            stats.record(time / 1000.0, 0.001, 0);
+        }
+        stats
+    }
+
+    fn assert_ms(actual: Option<f64>, expected: f64) {
+        let actual = actual.unwrap() * 1000.0;
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "expected {} ms, got {} ms",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn the_first_frame_only_starts_the_clock() {
+        let stats = stats(&[5.0]);
+        assert_eq!(stats.samples().len(), 0);
+        assert_eq!(stats.percentile(50.0), None);
+        assert_eq!(stats.summary(), None);
+    }
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        // Frame times of 1 to 100 ms.
+        let mut times = vec![0.0];
+        for frame_time in 1..=100 {
+            times.push(times.last().unwrap() + frame_time as f64);
+        }
+        let stats = stats(&times);
+
+        assert_ms(stats.percentile(50.0), 50.0);
+        assert_ms(stats.percentile(95.0), 95.0);
+        assert_ms(stats.percentile(99.0), 99.0);
+        assert_ms(stats.percentile(99.5), 100.0);
+        assert_ms(stats.percentile(0.0), 1.0);
+        assert_ms(stats.percentile(100.0), 100.0);
+    }
+
+    #[test]
+    fn percentiles_of_few_frames() {
+        let stats = stats(&[0.0, 10.0, 40.0]);
+        assert_ms(stats.percentile(50.0), 10.0);
+        assert_ms(stats.percentile(51.0), 30.0);
+        assert_ms(stats.percentile(99.0), 30.0);
+    }
+
+    #[test]
+    fn only_the_last_frames_are_kept() {
+        let mut stats = FrameStats::new(2);
+        for (time, dropped) in [(0.0, 0), (0.1, 1), (0.3, 2), (0.6, 3)] {
+            stats.record(time, 0.0, dropped);
+        }
+
+        let frame_times: Vec<_> = stats
+            .samples()
+            .map(|sample| (sample.frame_time * 10.0).round())
+            .collect();
+        assert_eq!(frame_times, [2.0, 3.0]);
+        assert_eq!(stats.frames(), 3);
+        // Dropped frames count since the start.
+        assert_eq!(stats.summary().unwrap().dropped_frames, 6);
+    }
+
+    #[test]
+    fn summary() {
+        let mut stats = FrameStats::new(10);
+        stats.record(1.0, 0.0, 0);
+        stats.record(1.010, 0.002, 0);
+        stats.record(1.040, 0.004, 1);
+
+        let summary = stats.summary().unwrap();
+        assert_eq!(summary.frames, 2);
+        assert_ms(Some(summary.mean_frame_time), 20.0);
+        assert_ms(Some(summary.max_frame_time), 30.0);
+        assert_ms(Some(summary.mean_present_latency), 3.0);
+        assert_eq!(summary.dropped_frames, 1);
+        assert_eq!(
+            summary.to_string(),
+            "50.0 fps over 2 frames, frame time p50 10.00 ms, p95 30.00 ms, \
+             p99 30.00 ms, max 30.00 ms, present latency 3.00 ms, 1 dropped"
+        );
+    }
+
+    #[test]
+    fn histogram_buckets_and_overflow() {
+        let stats = stats(&[0.0, 1.0, 6.0, 11.5, 16.0, 100.0]);
+        // 1, 5, 5.5, 4.5 and 84 ms in 2 ms buckets up to 6 ms.
+        assert_eq!(stats.histogram(0.002, 4), [1, 0, 3, 1]);
+        assert_eq!(stats.histogram(0.002, 0), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn exports_csv() {
+        let mut stats = FrameStats::new(10);
+        stats.record(1.0, 0.0, 0);
+        stats.record(1.0165, 0.00125, 2);
+
+        let mut csv = Vec::new();
+        stats.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "time_ms,frame_time_ms,present_latency_ms,dropped\n\
+             1016.500,16.500,1.250,2\n"
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+use crate::clock::{ClockTime, ParseClockTimeError, MILLISECONDS_PER_DAY};
+
+/// Where a clock gets the time of day from.
+///
+/// The clocks only ever ask for the current time through this trait, so they
+/// can show the wall clock, a fixed time for screenshots, a time zone other
+/// than the local one, a sped up day for demos, or whatever a test wants.
+pub trait TimeSource {
+    fn now(&self) -> ClockTime;
+}
+
+impl<T: TimeSource + ?Sized> TimeSource for &T {
+    fn now(&self) -> ClockTime {
+        (**self).now()
+    }
+}
+
+impl<T: TimeSource + ?Sized> TimeSource for Box<T> {
+    fn now(&self) -> ClockTime {
+        (**self).now()
+    }
+}
+
+impl<T: TimeSource + ?Sized> TimeSource for Rc<T> {
+    fn now(&self) -> ClockTime {
+        (**self).now()
+    }
+}
+
+/// The wall clock.
+///
+/// On Windows this is the local time from `GetLocalTime`. Elsewhere the
+/// standard library has no notion of time zones, so it is UTC.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl TimeSource for SystemClock {
+    #[cfg(windows)]
+    fn now(&self) -> ClockTime {
+        use windows::Win32::System::SystemInformation::GetLocalTime;
+
+        let mut time = Default::default();
+        unsafe { GetLocalTime(&mut time) };
+
+        ClockTime::new(
+            time.wHour as u8,
+            time.wMinute as u8,
+            time.wSecond as u8,
+            time.wMilliseconds,
+        )
+    }
+
+    #[cfg(not(windows))]
+    fn now(&self) -> ClockTime {
+        use std::time::SystemTime;
+
+        let since_epoch = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        ClockTime::from_millisecond_of_day(since_epoch.as_millis() as u64)
+    }
+}
+
+/// Always the same time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FixedTime(pub ClockTime);
+
+impl TimeSource for FixedTime {
+    fn now(&self) -> ClockTime {
+        self.0
+    }
+}
+
+/// The time source asked for on the command line of a clock: the first of
+/// `args` that isn't an option is the fixed time to show, and without one
+/// the clock shows the wall clock.
+pub fn parse_time_arg<I>(
+    args: I,
+) -> Result<Box<dyn TimeSource>, ParseClockTimeError>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let time = args.into_iter().find(|arg| !arg.as_ref().starts_with("--"));
+    match time {
+        Some(time) => Ok(Box::new(FixedTime(time.as_ref().parse()?))),
+        None => Ok(Box::new(SystemClock)),
+    }
+}
+
+/// Another source shifted by a constant amount, e.g. to show a different
+/// time zone.
+#[derive(Clone, Copy, Debug)]
+pub struct Offset<S> {
+    source: S,
+    milliseconds: i64,
+}
+
+impl<S: TimeSource> Offset<S> {
+    pub fn ahead(source: S, offset: Duration) -> Self {
+        Offset {
+            source,
+            milliseconds: day_milliseconds(offset),
+        }
+    }
+
+    pub fn behind(source: S, offset: Duration) -> Self {
+        Offset {
+            source,
+            milliseconds: -day_milliseconds(offset),
+        }
+    }
+}
+
+impl<S: TimeSource> TimeSource for Offset<S> {
+    fn now(&self) -> ClockTime {
+        self.source
+            .now()
+            .wrapping_add_milliseconds(self.milliseconds)
+    }
+}
+
+/// Another source running `factor` times as fast.
+///
+/// The accelerated time starts out equal to the source (or at the time given
+/// to [`Accelerated::starting_at`]) and from then on advances `factor`
+/// milliseconds for every millisecond the source advances. A negative factor
+/// makes the clock run backwards.
+///
+/// The source is expected to be asked at least once per day: a source that
+/// jumps a whole day between two calls looks like it didn't move at all.
+#[derive(Debug)]
+pub struct Accelerated<S> {
+    source: S,
+    factor: f64,
+    last_source_time: Cell<ClockTime>,
+    // Milliseconds of the day shown, kept as a float so slow factors still
+    // accumulate.
+    millisecond_of_day: Cell<f64>,
+}
+
+impl<S: TimeSource> Accelerated<S> {
+    pub fn new(source: S, factor: f64) -> Self {
+        let start = source.now();
+        Self::starting_at(source, factor, start)
+    }
+
+    pub fn starting_at(source: S, factor: f64, start: ClockTime) -> Self {
+        let last_source_time = Cell::new(source.now());
+        Accelerated {
+            source,
+            factor,
+            last_source_time,
+            millisecond_of_day: Cell::new(start.millisecond_of_day() as f64),
+        }
+    }
+
+    pub fn factor(&self) -> f64 {
+        self.factor
+    }
+}
+
+impl<S: TimeSource> TimeSource for Accelerated<S> {
+    fn now(&self) -> ClockTime {
+        let day = MILLISECONDS_PER_DAY as f64;
+
+        let source_time = self.source.now();
+        let elapsed = (source_time.millisecond_of_day() as f64
+            - self.last_source_time.get().millisecond_of_day() as f64)
+            .rem_euclid(day);
+        self.last_source_time.set(source_time);
+
+        let millisecond_of_day = (self.millisecond_of_day.get()
+            + elapsed * self.factor)
+            .rem_euclid(day);
+        self.millisecond_of_day.set(millisecond_of_day);
+
+        ClockTime::from_millisecond_of_day(millisecond_of_day as u64)
+    }
+}
+
+/// A clock that only moves when told to.
+///
+/// Clones share the same time, so a test can keep one clone to step the
+/// clock while the code under test owns another.
+#[derive(Clone, Debug, Default)]
+pub struct FakeClock {
+    time: Rc<Cell<ClockTime>>,
+}
+
+impl FakeClock {
+    pub fn new(time: ClockTime) -> Self {
+        FakeClock {
+            time: Rc::new(Cell::new(time)),
+        }
+    }
+
+    pub fn set(&self, time: ClockTime) {
+        self.time.set(time);
+    }
+
+    /// Moves the clock forward, wrapping around midnight.
+    pub fn advance(&self, duration: Duration) {
+        self.time.set(
+            self.time
+                .get()
+                .wrapping_add_milliseconds(day_milliseconds(duration)),
+        );
+    }
+}
+
+impl TimeSource for FakeClock {
+    fn now(&self) -> ClockTime {
+        self.time.get()
+    }
+}
+
+// Whole days don't change the time of day, so only the rest is kept.
+fn day_milliseconds(duration: Duration) -> i64 {
+    (duration.as_millis() % MILLISECONDS_PER_DAY as u128) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(milliseconds: u64) -> Duration {
+        Duration::from_millis(milliseconds)
+    }
+
+    #[test]
+    fn system_clock_is_a_valid_time() {
+        let time = SystemClock.now();
+        assert!(time.millisecond_of_day() < MILLISECONDS_PER_DAY);
+    }
+
+    #[test]
+    fn fixed_time_never_moves() {
+        let source = FixedTime(ClockTime::hms(10, 8, 30));
+        assert_eq!(source.now(), ClockTime::hms(10, 8, 30));
+        assert_eq!(source.now(), ClockTime::hms(10, 8, 30));
+    }
+
+    #[test]
+    fn the_time_arg_is_the_first_that_isnt_an_option() {
+        let source = parse_time_arg(["--stats", "10:08:30"]).unwrap();
+        assert_eq!(source.now(), ClockTime::hms(10, 8, 30));
+
+        assert!(parse_time_arg(["--stats"]).is_ok());
+        assert_eq!(
+            parse_time_arg(["25:00"])
+                .err()
+                .map(|error| error.to_string()),
+            Some(
+                "invalid time \"25:00\", expected HH:MM[:SS[.mmm]]".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn fake_clock_is_shared_between_clones() {
+        let clock = FakeClock::new(ClockTime::hms(12, 0, 0));
+        let handle = clock.clone();
+
+        handle.advance(millis(1500));
+        assert_eq!(clock.now(), ClockTime::new(12, 0, 1, 500));
+
+        handle.set(ClockTime::hms(1, 2, 3));
+        assert_eq!(clock.now(), ClockTime::hms(1, 2, 3));
+    }
+
+    #[test]
+    fn fake_clock_wraps_at_midnight() {
+        let clock = FakeClock::new(ClockTime::new(23, 59, 59, 900));
+        clock.advance(millis(200));
+        assert_eq!(clock.now(), ClockTime::new(0, 0, 0, 100));
+
+        clock.advance(Duration::from_secs(3 * 24 * 60 * 60));
+        assert_eq!(clock.now(), ClockTime::new(0, 0, 0, 100));
+    }
+
+    #[test]
+    fn offset_shifts_both_ways() {
+        let clock = FakeClock::new(ClockTime::hms(22, 30, 0));
+        let two_hours = Duration::from_secs(2 * 60 * 60);
+
+        let ahead = Offset::ahead(clock.clone(), two_hours);
+        let behind = Offset::behind(clock.clone(), two_hours);
+        assert_eq!(ahead.now(), ClockTime::hms(0, 30, 0));
+        assert_eq!(behind.now(), ClockTime::hms(20, 30, 0));
+
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(ahead.now(), ClockTime::hms(0, 31, 0));
+        assert_eq!(behind.now(), ClockTime::hms(20, 31, 0));
+    }
+
+    #[test]
+    fn accelerated_runs_faster_than_its_source() {
+        let clock = FakeClock::new(ClockTime::hms(9, 0, 0));
+        let fast = Accelerated::new(&clock, 60.0);
+        assert_eq!(fast.now(), ClockTime::hms(9, 0, 0));
+
+        // One second of the source is a minute of the accelerated clock.
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(fast.now(), ClockTime::hms(9, 1, 0));
+
+        clock.advance(millis(250));
+        assert_eq!(fast.now(), ClockTime::hms(9, 1, 15));
+    }
+
+    #[test]
+    fn accelerated_keeps_going_across_midnight() {
+        let clock = FakeClock::new(ClockTime::hms(23, 59, 59));
+        let fast =
+            Accelerated::starting_at(&clock, 3600.0, ClockTime::hms(23, 0, 0));
+
+        // The source wraps first ...
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(fast.now(), ClockTime::hms(1, 0, 0));
+        // ... and then the accelerated clock wraps on its own.
+        clock.advance(Duration::from_secs(23));
+        assert_eq!(fast.now(), ClockTime::hms(0, 0, 0));
+    }
+
+    #[test]
+    fn accelerated_accumulates_slow_factors() {
+        let clock = FakeClock::new(ClockTime::hms(0, 0, 0));
+        let slow = Accelerated::new(&clock, 0.25);
+
+        for _ in 0..4 {
+            clock.advance(millis(1));
+            slow.now();
+        }
+        assert_eq!(slow.now(), ClockTime::new(0, 0, 0, 1));
+    }
+
+    #[test]
+    fn accelerated_can_run_backwards() {
+        let clock = FakeClock::new(ClockTime::hms(0, 0, 0));
+        let reverse = Accelerated::new(&clock, -2.0);
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(reverse.now(), ClockTime::hms(23, 59, 58));
+    }
+
+    #[test]
+    fn sources_compose_behind_pointers() {
+        let sources: Vec<Box<dyn TimeSource>> = vec![
+            Box::new(FixedTime(ClockTime::hms(3, 0, 0))),
+            Box::new(Offset::ahead(
+                Rc::new(FixedTime(ClockTime::hms(3, 0, 0))),
+                Duration::from_secs(60 * 60),
+            )),
+        ];
+        let hours: Vec<u8> =
+            sources.iter().map(|source| source.now().hour()).collect();
+        assert_eq!(hours, [3, 4]);
+    }
+}
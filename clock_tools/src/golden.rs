@@ -0,0 +1,217 @@
+//! Comparing rendered images against reference ("golden") images.
+
+use renderer::Image;
+
+// The largest possible value of `perceptual_delta`.
+const MAX_PERCEPTUAL_DELTA: f32 = 35215.0;
+
+/// How far an image may stray from its reference and still match.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tolerance {
+    /// Pixels whose channels all differ by at most this much match.
+    pub channel: u8,
+    /// Pixels that differ by more still match if their colors are this close
+    /// perceptually, from 0 (identical) to 1 (black and white).
+    pub perceptual: f32,
+    /// How many pixels may fail both tests before the images are different.
+    pub mismatched_pixels: usize,
+}
+
+impl Default for Tolerance {
+    /// Allows for the anti-aliasing of edges to shift slightly, as it does
+    /// when the floating point math is compiled differently.
+    fn default() -> Self {
+        Tolerance {
+            channel: 2,
+            perceptual: 0.1,
+            mismatched_pixels: 16,
+        }
+    }
+}
+
+/// The result of comparing an image against its reference.
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    /// Pixels that differ by more than the tolerance allows.
+    pub mismatched_pixels: usize,
+    /// Pixels that differ at all.
+    pub different_pixels: usize,
+    /// The largest difference in any channel of any pixel.
+    pub max_channel_difference: u8,
+    /// The reference, faded, with differences within the tolerance marked
+    /// yellow and mismatches red.
+    pub diff: Image,
+}
+
+/// Compares `actual` against `expected`. Images of different sizes never
+/// match, so `None` is returned for them.
+pub fn compare(
+    expected: &Image,
+    actual: &Image,
+    tolerance: Tolerance,
+) -> Option<Comparison> {
+    if (expected.width(), expected.height())
+        != (actual.width(), actual.height())
+    {
+        return None;
+    }
+
+    let max_delta = MAX_PERCEPTUAL_DELTA * tolerance.perceptual.powi(2);
+    let mut comparison = Comparison {
+        mismatched_pixels: 0,
+        different_pixels: 0,
+        max_channel_difference: 0,
+        diff: Image::new(expected.width(), expected.height()),
+    };
+
+    for y in 0..expected.height() {
+        for x in 0..expected.width() {
+            let a = expected.pixel(x, y);
+            let b = actual.pixel(x, y);
+            let difference =
+                a.iter().zip(&b).map(|(a, b)| a.abs_diff(*b)).max().unwrap();
+
+            comparison.max_channel_difference =
+                comparison.max_channel_difference.max(difference);
+            let marker = if difference == 0 {
+                faded(a)
+            } else if difference <= tolerance.channel
+                || perceptual_delta(a, b) <= max_delta
+            {
+                comparison.different_pixels += 1;
+                [255, 255, 0, 255]
+            } else {
+                comparison.different_pixels += 1;
+                comparison.mismatched_pixels += 1;
+                [255, 0, 0, 255]
+            };
+            comparison.diff.set_pixel(x, y, marker);
+        }
+    }
+
+    Some(comparison)
+}
+
+impl Comparison {
+    /// Whether the images match within the tolerance they were compared
+    /// with.
+    pub fn matches(&self, tolerance: Tolerance) -> bool {
+        self.mismatched_pixels <= tolerance.mismatched_pixels
+    }
+}
+
+// The squared distance between two colors in the YIQ color space, weighted
+// by how sensitive the eye is to each component, after blending both over
+// white. See "Measuring perceived color difference using YIQ NTSC
+// transmission color space in mobile applications" by Kotsarenko and Ramos.
+fn perceptual_delta(a: [u8; 4], b: [u8; 4]) -> f32 {
+    let (y1, i1, q1) = yiq(a);
+    let (y2, i2, q2) = yiq(b);
+    let (y, i, q) = (y1 - y2, i1 - i2, q1 - q2);
+    0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q
+}
+
+fn yiq([r, g, b, a]: [u8; 4]) -> (f32, f32, f32) {
+    let alpha = a as f32 / 255.0;
+    let [r, g, b] =
+        [r, g, b].map(|channel| 255.0 + (channel as f32 - 255.0) * alpha);
+    (
+        0.298_895 * r + 0.586_622 * g + 0.114_482 * b,
+        0.595_978 * r - 0.274_176 * g - 0.321_802 * b,
+        0.211_470 * r - 0.522_617 * g + 0.311_147 * b,
+    )
+}
+
+// A light grey version of a pixel, so marked differences stand out.
+fn faded(pixel: [u8; 4]) -> [u8; 4] {
+    let (y, _, _) = yiq(pixel);
+    let grey = (255.0 - (255.0 - y) * 0.1) as u8;
+    [grey, grey, grey, 255]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(pixel: [u8; 4]) -> Image {
+        let mut image = Image::new(4, 4);
+        for y in 0..4 {
+            for x in 0..4 {
+                image.set_pixel(x, y, pixel);
+            }
+        }
+        image
+    }
+
+    const EXACT: Tolerance = Tolerance {
+        channel: 0,
+        perceptual: 0.0,
+        mismatched_pixels: 0,
+    };
+
+    #[test]
+    fn identical_images_match() {
+        let image = filled([10, 20, 30, 255]);
+        let comparison = compare(&image, &image, EXACT).unwrap();
+        assert!(comparison.matches(EXACT));
+        assert_eq!(comparison.different_pixels, 0);
+        assert_eq!(comparison.max_channel_difference, 0);
+    }
+
+    #[test]
+    fn different_sizes_never_match() {
+        assert!(compare(&Image::new(2, 2), &Image::new(2, 3), EXACT).is_none());
+    }
+
+    #[test]
+    fn small_channel_differences_are_tolerated() {
+        let expected = filled([100, 100, 100, 255]);
+        let mut actual = expected.clone();
+        actual.set_pixel(1, 1, [102, 99, 100, 255]);
+
+        let tolerance = Tolerance {
+            channel: 2,
+            ..EXACT
+        };
+        let comparison = compare(&expected, &actual, tolerance).unwrap();
+        assert!(comparison.matches(tolerance));
+        assert_eq!(comparison.different_pixels, 1);
+        assert_eq!(comparison.max_channel_difference, 2);
+        assert_eq!(comparison.diff.pixel(1, 1), [255, 255, 0, 255]);
+
+        assert!(!compare(&expected, &actual, EXACT).unwrap().matches(EXACT));
+    }
+
+    #[test]
+    fn perceptually_close_colors_match() {
+        let expected = filled([255, 255, 0, 255]);
+        let mut actual = expected.clone();
+        // A slightly darker yellow, and black in place of yellow.
+        actual.set_pixel(0, 0, [245, 245, 0, 255]);
+        actual.set_pixel(3, 3, [0, 0, 0, 255]);
+
+        let tolerance = Tolerance {
+            perceptual: 0.1,
+            ..EXACT
+        };
+        let comparison = compare(&expected, &actual, tolerance).unwrap();
+        assert_eq!(comparison.mismatched_pixels, 1);
+        assert_eq!(comparison.diff.pixel(0, 0), [255, 255, 0, 255]);
+        assert_eq!(comparison.diff.pixel(3, 3), [255, 0, 0, 255]);
+        assert!(!comparison.matches(tolerance));
+        assert!(comparison.matches(Tolerance {
+            mismatched_pixels: 1,
+            ..tolerance
+        }));
+    }
+
+    #[test]
+    fn transparent_pixels_are_compared_over_white() {
+        // Any color at zero alpha looks the same.
+        assert_eq!(perceptual_delta([0, 0, 0, 0], [255, 0, 255, 0]), 0.0);
+        // Black and white are nearly as far apart as two colors get.
+        let delta = perceptual_delta([0, 0, 0, 255], [255, 255, 255, 255]);
+        assert!(delta > 0.9 * MAX_PERCEPTUAL_DELTA, "{}", delta);
+        assert!(delta <= MAX_PERCEPTUAL_DELTA, "{}", delta);
+    }
+}
@@ -0,0 +1,223 @@
+//! Renders the clocks of the learn-win32 modules without a window, using the
+//! software renderer.
+
+mod golden;
+
+use std::{path::PathBuf, str::FromStr};
+
+use clock_core::{ClockMode, ClockTime, SystemClock, TimeSource};
+use module_3_applying_transforms::ClockFace;
+use renderer::{
+    Color, Image, RecordingRenderer, Renderer, Size, SoftwareRenderer,
+};
+
+pub use golden::{compare, Comparison, Tolerance};
+
+/// Which module's clock to draw.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Clock {
+    /// The clock face of `module_3_applying_transforms`.
+    #[default]
+    Face,
+    /// The clock of `module_3_bonus`, on its white background but without
+    /// the drop shadow Direct2D adds.
+    Bonus,
+}
+
+impl FromStr for Clock {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "face" => Ok(Clock::Face),
+            "bonus" => Ok(Clock::Bonus),
+            _ => {
+                Err(format!("unknown clock {:?}, expected face or bonus", name))
+            }
+        }
+    }
+}
+
+/// Draws `clock` at `time` into a `width` x `height` image.
+pub fn render(clock: Clock, time: ClockTime, width: u32, height: u32) -> Image {
+    let mut renderer = SoftwareRenderer::new(width, height);
+    draw(clock, &mut renderer, time);
+    renderer.image()
+}
+
+/// Draws `clock` at `time` into a `width` x `height` SVG document.
+pub fn render_svg(
+    clock: Clock,
+    time: ClockTime,
+    width: u32,
+    height: u32,
+) -> String {
+    let mut renderer =
+        RecordingRenderer::new(Size::new(width as f32, height as f32));
+    draw(clock, &mut renderer, time);
+    renderer.to_svg()
+}
+
+fn draw(clock: Clock, renderer: &mut impl Renderer, time: ClockTime) {
+    let angles = ClockMode::Smooth.angles(time);
+
+    match clock {
+        Clock::Face => ClockFace::new(renderer.size()).draw(renderer, angles),
+        Clock::Bonus => {
+            renderer.clear(Color::WHITE);
+            module_3_bonus::draw_clock(renderer, angles);
+        }
+    }
+}
+
+pub const USAGE: &str = "\
+usage: render-to-png [--time HH:MM[:SS[.mmm]]] [--size WIDTHxHEIGHT]
+                     [--clock face|bonus] [--output FILE]
+
+Renders a clock to a PNG file, or to an SVG file if FILE ends in .svg.
+Defaults to the current time, 512x512, the clock face and clock.png.";
+
+/// The command line of `render-to-png`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Options {
+    pub time: ClockTime,
+    pub width: u32,
+    pub height: u32,
+    pub clock: Clock,
+    pub output: PathBuf,
+}
+
+impl Options {
+    /// Parses the arguments following the program name.
+    pub fn parse(
+        arguments: impl IntoIterator<Item = String>,
+    ) -> Result<Self, String> {
+        let mut time = None;
+        let mut size = (512, 512);
+        let mut clock = Clock::default();
+        let mut output = PathBuf::from("clock.png");
+
+        let mut arguments = arguments.into_iter();
+        while let Some(flag) = arguments.next() {
+            let mut value = || {
+                arguments
+                    .next()
+                    .ok_or_else(|| format!("{} needs a value", flag))
+            };
+            match flag.as_str() {
+                "--time" => {
+                    time = Some(
+                        value()?
+                            .parse()
+                            .map_err(|error| format!("{}", error))?,
+                    )
+                }
+                "--size" => size = parse_size(&value()?)?,
+                "--clock" => clock = value()?.parse()?,
+                "--output" => output = PathBuf::from(value()?),
+                _ => return Err(format!("unknown argument {:?}", flag)),
+            }
+        }
+
+        Ok(Options {
+            time: time.unwrap_or_else(|| SystemClock.now()),
+            width: size.0,
+            height: size.1,
+            clock,
+            output,
+        })
+    }
+}
+
+fn parse_size(size: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("invalid size {:?}, expected e.g. 512x512", size);
+
+    let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+    let width: u32 = width.parse().map_err(|_| invalid())?;
+    let height: u32 = height.parse().map_err(|_| invalid())?;
+    if width == 0 || height == 0 || width > 16384 || height > 16384 {
+        return Err(invalid());
+    }
+    Ok((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(arguments: &[&str]) -> Result<Options, String> {
+        Options::parse(arguments.iter().map(|argument| argument.to_string()))
+    }
+
+    #[test]
+    fn parses_every_option() {
+        let options = parse(&[
+            "--time", "10:08:30", "--size", "640x480", "--clock", "bonus",
+            "--output", "out.png",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            options,
+            Options {
+                time: ClockTime::hms(10, 8, 30),
+                width: 640,
+                height: 480,
+                clock: Clock::Bonus,
+                output: PathBuf::from("out.png"),
+            }
+        );
+    }
+
+    #[test]
+    fn defaults() {
+        let options = parse(&[]).unwrap();
+        assert_eq!((options.width, options.height), (512, 512));
+        assert_eq!(options.clock, Clock::Face);
+        assert_eq!(options.output, PathBuf::from("clock.png"));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        for arguments in [
+            &["--time"][..],
+            &["--time", "25:00"],
+            &["--size", "512"],
+            &["--size", "0x512"],
+            &["--size", "axb"],
+            &["--clock", "cuckoo"],
+            &["--verbose"],
+        ] {
+            assert!(parse(arguments).is_err(), "{:?}", arguments);
+        }
+    }
+
+    #[test]
+    fn renders_at_the_requested_size() {
+        let image = render(Clock::Face, ClockTime::hms(10, 8, 30), 64, 48);
+        assert_eq!((image.width(), image.height()), (64, 48));
+
+        // The hands darken the yellow face around its center, the corner
+        // shows the background.
+        let [r, g, b, _] = image.pixel(32, 24);
+        assert!(r < 128 && g < 128 && b == 0, "{:?}", [r, g, b]);
+        assert_eq!(image.pixel(0, 0)[3], 204);
+    }
+
+    #[test]
+    fn renders_svg() {
+        let svg = render_svg(Clock::Bonus, ClockTime::hms(10, 8, 30), 300, 200);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(r#"width="300" height="200""#));
+        assert_eq!(svg.matches("<line").count(), 3);
+    }
+
+    #[test]
+    fn bonus_clock_is_drawn_on_white() {
+        let image = render(Clock::Bonus, ClockTime::hms(10, 8, 30), 300, 300);
+        assert_eq!(image.pixel(0, 0), [255, 255, 255, 255]);
+        // The tip of the second hand at 30 seconds, straight down.
+        let [r, g, b, _] = image.pixel(150, 220);
+        assert!(r > g && g > b, "{:?}", [r, g, b]);
+    }
+}
@@ -0,0 +1,39 @@
+use clock_tools::{render, render_svg, Options, USAGE};
+
+fn main() {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let written = if options.output.extension().is_some_and(|e| e == "svg") {
+        std::fs::write(
+            &options.output,
+            render_svg(
+                options.clock,
+                options.time,
+                options.width,
+                options.height,
+            ),
+        )
+    } else {
+        render(options.clock, options.time, options.width, options.height)
+            .save_png(&options.output)
+    };
+    if let Err(error) = written {
+        eprintln!("Failed to write {}: {}", options.output.display(), error);
+        std::process::exit(1);
+    }
+    println!(
+        "Rendered {} at {} to {}",
+        match options.clock {
+            clock_tools::Clock::Face => "the clock face",
+            clock_tools::Clock::Bonus => "the bonus clock",
+        },
+        options.time,
+        options.output.display()
+    );
+}
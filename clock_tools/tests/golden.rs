@@ -0,0 +1,119 @@
+//! Renders the clocks at fixed times and sizes and compares them against the
+//! reference images in `tests/golden`.
+//!
+//! After an intended change to how the clocks look, run the tests with
+//! `UPDATE_GOLDEN=1` to overwrite the references, and check the new images
+//! in. When an image doesn't match, what was rendered and a diff against the
+//! reference are written next to the test binaries and their paths printed.
+
+use std::{env, fs, path::PathBuf};
+
+use clock_core::ClockTime;
+use clock_tools::{compare, render, Clock, Tolerance};
+
+fn check(clock: Clock, time: ClockTime, width: u32, height: u32) {
+    let name = format!(
+        "{}-{:02}-{:02}-{:02}-{}x{}",
+        match clock {
+            Clock::Face => "face",
+            Clock::Bonus => "bonus",
+        },
+        time.hour(),
+        time.minute(),
+        time.second(),
+        width,
+        height
+    );
+    let reference = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.png", name));
+    let actual = render(clock, time, width, height);
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        actual.save_png(&reference).unwrap();
+        return;
+    }
+
+    let expected =
+        renderer::Image::load_png(&reference).unwrap_or_else(|error| {
+            panic!(
+                "Failed to load {}: {}. Run with UPDATE_GOLDEN=1 to create it.",
+                reference.display(),
+                error
+            )
+        });
+    let tolerance = Tolerance::default();
+    let comparison = compare(&expected, &actual, tolerance);
+    if comparison
+        .as_ref()
+        .is_some_and(|comparison| comparison.matches(tolerance))
+    {
+        return;
+    }
+
+    let output = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    fs::create_dir_all(&output).unwrap();
+    let actual_path = output.join(format!("{}.actual.png", name));
+    actual.save_png(&actual_path).unwrap();
+
+    match comparison {
+        Some(comparison) => {
+            let diff_path = output.join(format!("{}.diff.png", name));
+            comparison.diff.save_png(&diff_path).unwrap();
+            panic!(
+                "{} differs from {}: {} of {} pixels mismatch (up to {} \
+                 allowed), the largest channel difference is {}. Wrote {} \
+                 and {}.",
+                name,
+                reference.display(),
+                comparison.mismatched_pixels,
+                width * height,
+                tolerance.mismatched_pixels,
+                comparison.max_channel_difference,
+                actual_path.display(),
+                diff_path.display()
+            );
+        }
+        None => panic!(
+            "{} is {}x{} but {} is {}x{}. Wrote {}.",
+            name,
+            width,
+            height,
+            reference.display(),
+            expected.width(),
+            expected.height(),
+            actual_path.display()
+        ),
+    }
+}
+
+#[test]
+fn face_at_ten_past_ten() {
+    check(Clock::Face, ClockTime::hms(10, 8, 30), 256, 256);
+}
+
+#[test]
+fn face_at_three_in_a_wide_window() {
+    check(Clock::Face, ClockTime::hms(3, 0, 0), 320, 200);
+}
+
+#[test]
+fn face_just_before_midnight_in_a_tall_window() {
+    check(Clock::Face, ClockTime::hms(23, 59, 59), 200, 320);
+}
+
+#[test]
+fn bonus_at_ten_past_ten() {
+    check(Clock::Bonus, ClockTime::hms(10, 8, 30), 300, 300);
+}
+
+#[test]
+fn bonus_at_half_past_six_in_a_wide_window() {
+    check(Clock::Bonus, ClockTime::hms(6, 30, 15), 400, 300);
+}
+
+#[test]
+fn bonus_below_its_minimum_size() {
+    // The bonus clock keeps the radius it has at 200 pixels and overflows.
+    check(Clock::Bonus, ClockTime::hms(1, 45, 0), 150, 150);
+}
@@ -0,0 +1,41 @@
+//! The window of this module, independent of Win32 so it can be run
+//! without one.
+
+use win32_common::{Message, WindowHandler, WindowHost};
+
+type Fill = Box<dyn FnMut(&mut dyn WindowHost)>;
+
+/// A window that fills its client area whenever it is painted and ends the
+/// program when it is destroyed.
+pub struct SampleWindow {
+    fill: Fill,
+}
+
+impl SampleWindow {
+    /// Paints the window with `fill`, which is given the window to paint.
+    pub fn new(fill: impl FnMut(&mut dyn WindowHost) + 'static) -> Self {
+        SampleWindow {
+            fill: Box::new(fill),
+        }
+    }
+}
+
+impl WindowHandler for SampleWindow {
+    fn handle(
+        &mut self,
+        host: &mut dyn WindowHost,
+        message: Message,
+    ) -> Option<isize> {
+        match message {
+            Message::Paint => {
+                (self.fill)(host);
+                Some(0)
+            }
+            Message::Destroy => {
+                host.quit(0);
+                Some(0)
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! The window of this module from creation to destruction, without Win32.
+
+use std::{cell::RefCell, rc::Rc};
+
+use module_1::SampleWindow;
+use win32_common::{HeadlessHost, Message};
+
+#[test]
+fn fills_the_client_area_until_destroyed() {
+    let filled = Rc::new(RefCell::new(Vec::new()));
+    let window = SampleWindow::new({
+        let filled = filled.clone();
+        move |host| filled.borrow_mut().push(host.client_size())
+    });
+    let mut host = HeadlessHost::new(window).with_client_size(320, 240);
+
+    assert!(host.create());
+    host.paint();
+    host.minimize();
+    host.restore();
+    host.send(Message::MouseMove { x: 10, y: 20 });
+    host.close();
+
+    // Restored windows are painted again.
+    assert_eq!(*filled.borrow(), [(320, 240); 3]);
+    assert!(host.is_destroyed());
+    assert_eq!(host.exit_code(), Some(0));
+    // Everything but painting and destroying is left to Windows.
+    let handled: Vec<_> = host
+        .messages()
+        .iter()
+        .filter(|(_, result)| result.is_some())
+        .map(|(message, _)| message.kind().name())
+        .collect();
+    assert_eq!(handled, ["paint", "paint", "paint", "destroy"]);
+}
@@ -1,14 +1,21 @@
+#[cfg(windows)]
 use windows::core::Result;
-use windows::Win32::Foundation::{HWND, PWSTR};
+#[cfg(windows)]
 use windows::Win32::System::Com::{
-    CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_ALL,
-    COINIT_APARTMENTTHREADED, COINIT_DISABLE_OLE1DDE,
+    CoInitializeEx, CoUninitialize, COINIT_APARTMENTTHREADED,
+    COINIT_DISABLE_OLE1DDE,
 };
-use windows::Win32::UI::Shell::{
-    FileOpenDialog, IFileOpenDialog, SIGDN_FILESYSPATH,
-};
-use windows::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_OK};
 
+use win32_common::{DialogError, FileDialog, MessageBox};
+
+// Without the common item dialog the file is picked, and its path shown, in
+// the terminal.
+#[cfg(not(windows))]
+fn main() -> Result<(), DialogError> {
+    show_file_path()
+}
+
+#[cfg(windows)]
 fn main() -> Result<()> {
     unsafe {
         // Initializes the COM library for use by the calling thread, sets the
@@ -25,38 +32,33 @@ fn main() -> Result<()> {
             std::ptr::null_mut(),
             COINIT_APARTMENTTHREADED | COINIT_DISABLE_OLE1DDE,
         )?;
+    }
+
+    let shown = show_file_path();
+
+    // Closes the COM library on the current thread, unloads all DLLs loaded
+    // by the thread, frees any other resources that the thread maintains,
+    // and forces all RPC connections on the thread to close.
+    unsafe { CoUninitialize() };
+
+    Ok(shown?)
+}
+
+fn show_file_path() -> std::result::Result<(), DialogError> {
+    // Launches the modal file open dialog and waits for the user's choice.
+    // There is nothing to show if they cancel it.
+    let paths = match FileDialog::new().show() {
+        Ok(paths) => paths,
+        Err(DialogError::Cancelled) => return Ok(()),
+        Err(error) => return Err(error),
+    };
 
-        // HINT: https://github.com/microsoft/windows-samples-rs/search?q=CoCreateInstance
-
-        // Create the FileOpenDialog object.
-        //
-        let file_open: IFileOpenDialog =
-            CoCreateInstance(&FileOpenDialog, None, CLSCTX_ALL)?;
-
-        // Launches the modal window.
-        file_open.Show(None)?;
-
-        // Gets the choice that the user made in the dialog.
-        let item = file_open.GetResult()?;
-
-        // Gets the display name of the IShellItem object.
-        let file_path = item.GetDisplayName(SIGDN_FILESYSPATH)?;
-
-        // Launch a simple message box to show the file path of the previously
-        // selected path.
-        MessageBoxW(
-            HWND(0),
-            file_path,
-            // Geert TODO: Figure out how to go from UTF-8 to UTF-16 here to
-            // avoid gibberish text.
-            PWSTR("File Path\0".as_ptr() as *mut u16),
-            MB_OK,
-        );
-
-        // Closes the COM library on the current thread, unloads all DLLs loaded
-        // by the thread, frees any other resources that the thread maintains,
-        // and forces all RPC connections on the thread to close.
-        CoUninitialize();
+    // Launch a simple message box to show the file path of the previously
+    // selected path.
+    for path in paths {
+        MessageBox::new(&path.to_string_lossy())
+            .caption("File Path")
+            .show()?;
     }
     Ok(())
 }
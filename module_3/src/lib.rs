@@ -0,0 +1,150 @@
+//! The window of this module, drawing a circle filling its client area to
+//! any [`Surface`], independent of Win32 so it can be run without one.
+
+use clock_core::Point;
+use renderer::{Color, Ellipse, Error, Renderer, Size, Surface, WindowSurface};
+use win32_common::{FrameHandler, Message, Wait, WindowHandler, WindowHost};
+
+const BACKGROUND: Color =
+    Color::rgba(135.0 / 256.0, 206.0 / 256.0, 235.0 / 256.0, 0.8);
+const FILL: Color = Color::rgb(0.2, 0.5, 0.2);
+
+/// The largest circle that fits a render target of `size`, centered on it.
+pub fn layout(size: Size) -> Ellipse {
+    let center = Point::new(size.width / 2.0, size.height / 2.0);
+    Ellipse::circle(center, center.x.min(center.y))
+}
+
+/// Draws a frame of the window.
+pub fn draw(renderer: &mut impl Renderer) {
+    let ellipse = layout(renderer.size());
+    renderer.clear(BACKGROUND);
+    renderer.set_brush_color(FILL);
+    renderer.fill_ellipse(ellipse);
+}
+
+/// A window drawing the circle to a surface that is created with the window.
+///
+/// A window that fails to create its surface or to draw keeps the error and
+/// tries again after [`RETRY_DELAY`], rather than giving up.
+///
+/// [`RETRY_DELAY`]: renderer::RETRY_DELAY
+pub struct CircleWindow<S> {
+    surface: WindowSurface<S>,
+}
+
+impl<S: Surface> CircleWindow<S> {
+    /// Draws to the surface that `create_surface` creates on `WM_CREATE`.
+    pub fn new<F>(create_surface: F) -> Self
+    where
+        F: FnMut(&dyn WindowHost) -> Result<S, Error> + 'static,
+    {
+        CircleWindow {
+            surface: WindowSurface::new(create_surface),
+        }
+    }
+
+    pub fn surface(&self) -> Option<&S> {
+        self.surface.get()
+    }
+
+    /// Why the last frame wasn't drawn, unless the device was lost.
+    pub fn error(&self) -> Option<&Error> {
+        self.surface.error()
+    }
+
+    fn paint(&mut self, host: &mut dyn WindowHost) {
+        let surface = match self.surface.create(host) {
+            Some(surface) => surface,
+            None => return,
+        };
+
+        let mut frame = |mut renderer: &mut dyn Renderer| draw(&mut renderer);
+        let presented = surface.draw(&mut frame);
+        self.surface.presented(host, presented);
+    }
+}
+
+impl<S: Surface> WindowHandler for CircleWindow<S> {
+    fn handle(
+        &mut self,
+        host: &mut dyn WindowHost,
+        message: Message,
+    ) -> Option<isize> {
+        match message {
+            Message::Create => {
+                self.surface.create(host);
+                Some(0)
+            }
+            Message::Paint => {
+                self.paint(host);
+                Some(0)
+            }
+            Message::Size { .. } => {
+                // A surface that lost its device is created again at the
+                // new size.
+                self.surface.resize(host);
+                // The circle is laid out for the new size.
+                host.invalidate();
+                Some(0)
+            }
+            Message::Destroy => {
+                self.surface.release();
+                host.quit(0);
+                Some(0)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<S: Surface> FrameHandler for CircleWindow<S> {
+    /// The circle is only drawn when the window is painted, unless the last
+    /// frame failed: then the window is painted again after [`RETRY_DELAY`].
+    ///
+    /// [`RETRY_DELAY`]: renderer::RETRY_DELAY
+    fn frame(&mut self, host: &mut dyn WindowHost) -> Wait {
+        if self.surface.error().is_none() {
+            return Wait::ForMessage;
+        }
+        match self.surface.retry_in(host) {
+            Some(wait) => Wait::For(wait),
+            None => {
+                host.invalidate();
+                Wait::ForMessage
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use renderer::{Command, RecordingRenderer};
+
+    #[test]
+    fn the_circle_fills_the_shorter_side() {
+        assert_eq!(
+            layout(Size::new(300.0, 200.0)),
+            Ellipse::circle(Point::new(150.0, 100.0), 100.0)
+        );
+    }
+
+    #[test]
+    fn draws_the_circle_over_the_background() {
+        let mut renderer = RecordingRenderer::new(Size::new(100.0, 100.0));
+        draw(&mut renderer);
+
+        assert_eq!(
+            renderer.commands(),
+            [
+                Command::Clear(BACKGROUND),
+                Command::SetBrushColor(FILL),
+                Command::FillEllipse(Ellipse::circle(
+                    Point::new(50.0, 50.0),
+                    50.0
+                )),
+            ]
+        );
+    }
+}
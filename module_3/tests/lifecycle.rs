@@ -0,0 +1,157 @@
+//! The circle window being created, resized, minimized and destroyed, and
+//! recovering from a lost device or a failed frame, with its frames recorded
+//! instead of drawn with Direct2D.
+
+use clock_core::Point;
+use module_3::CircleWindow;
+use renderer::{
+    Command, Ellipse, Error, FaultInjectingSurface, RecordingSurface,
+    ResourceKind, RETRY_DELAY,
+};
+use win32_common::{dispatch, HeadlessHost, Message, WindowHost};
+
+fn window() -> CircleWindow<RecordingSurface> {
+    CircleWindow::new(|host: &dyn WindowHost| {
+        let (width, height) = host.client_size();
+        Ok(RecordingSurface::new(width, height))
+    })
+}
+
+fn circle(frame: &[Command]) -> Ellipse {
+    frame
+        .iter()
+        .find_map(|command| match command {
+            Command::FillEllipse(ellipse) => Some(*ellipse),
+            _ => None,
+        })
+        .expect("No circle drawn")
+}
+
+#[test]
+fn the_circle_follows_the_client_area() {
+    let mut host = HeadlessHost::new(window()).with_client_size(400, 300);
+    assert!(host.create());
+    host.resize(200, 400);
+    host.minimize();
+    host.restore();
+
+    let frames = host.handler().surface().unwrap().frames();
+    assert_eq!(frames.len(), 3);
+    assert_eq!(
+        circle(&frames[0]),
+        Ellipse::circle(Point::new(200.0, 150.0), 150.0)
+    );
+    assert_eq!(
+        circle(&frames[1]),
+        Ellipse::circle(Point::new(100.0, 200.0), 100.0)
+    );
+    // Minimizing doesn't draw, and restoring draws at the restored size.
+    assert_eq!(frames[2], frames[1]);
+}
+
+#[test]
+fn destroying_the_window_releases_the_surface() {
+    let mut host = HeadlessHost::new(window());
+    host.create();
+    host.deactivate();
+    host.activate();
+    host.close();
+
+    assert_eq!(host.exit_code(), Some(0));
+    assert!(host.handler().surface().is_none());
+}
+
+#[test]
+fn the_circle_is_drawn_again_after_losing_the_device() {
+    let window = CircleWindow::new(|host: &dyn WindowHost| {
+        let (width, height) = host.client_size();
+        Ok(FaultInjectingSurface::new(width, height).with_device_lost_at([0]))
+    });
+    let mut host = HeadlessHost::new(window).with_client_size(400, 300);
+    host.create();
+
+    // The first frame is lost, which leaves the window invalidated.
+    assert!(host.paint_if_invalidated());
+    assert!(!host.paint_if_invalidated());
+
+    let surface = host.handler().surface().unwrap();
+    assert_eq!(surface.frames_drawn(), 2);
+    assert_eq!(
+        circle(surface.last_frame().unwrap()),
+        Ellipse::circle(Point::new(200.0, 150.0), 150.0)
+    );
+    let resources = surface.resources();
+    assert_eq!(resources.devices_lost(), 1);
+    assert_eq!(resources.created(ResourceKind::Target), 2);
+    assert_eq!(resources.created(ResourceKind::Brush), 2);
+}
+
+// Remembers whether the window was last invalidated or validated.
+struct PaintLog {
+    calls: Vec<&'static str>,
+}
+
+impl WindowHost for PaintLog {
+    fn handle(&self) -> isize {
+        0
+    }
+
+    fn client_size(&self) -> (u32, u32) {
+        (400, 300)
+    }
+
+    fn dpi(&self) -> u32 {
+        96
+    }
+
+    fn now(&self) -> f64 {
+        0.0
+    }
+
+    fn invalidate(&mut self) {
+        self.calls.push("invalidate");
+    }
+
+    fn validate(&mut self) {
+        self.calls.push("validate");
+    }
+
+    fn quit(&mut self, _exit_code: i32) {}
+}
+
+#[test]
+fn losing_the_device_while_painting_leaves_the_window_invalidated() {
+    let mut window = CircleWindow::new(|host: &dyn WindowHost| {
+        let (width, height) = host.client_size();
+        Ok(FaultInjectingSurface::new(width, height).with_device_lost_at([0]))
+    });
+    let mut log = PaintLog { calls: Vec::new() };
+    dispatch(&mut window, &mut log, Message::Create);
+    dispatch(&mut window, &mut log, Message::Paint);
+
+    // Validating after the handler would drop the frame it asked for.
+    assert_eq!(log.calls, ["validate", "invalidate"]);
+}
+
+#[test]
+fn a_window_that_failed_to_draw_tries_again_after_a_delay() {
+    let window = CircleWindow::new(|host: &dyn WindowHost| {
+        let (width, height) = host.client_size();
+        let surface = FaultInjectingSurface::new(width, height);
+        Ok(surface.with_creation_failing_at([0]))
+    });
+    let mut host = HeadlessHost::new(window).with_client_size(400, 300);
+    assert!(host.create());
+    assert!(matches!(
+        host.handler().error(),
+        Some(Error::ResourceCreation(ResourceKind::Target, _))
+    ));
+    // Unlike losing the device, failing doesn't ask for another frame.
+    assert!(!host.paint_if_invalidated());
+
+    host.run_for(RETRY_DELAY / 2.0);
+    assert!(host.handler().error().is_some());
+    host.run_for(RETRY_DELAY);
+    assert_eq!(host.handler().error(), None);
+    assert_eq!(host.handler().surface().unwrap().frames().len(), 1);
+}
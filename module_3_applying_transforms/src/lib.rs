@@ -0,0 +1,157 @@
+//! The clock drawn by this module, independent of the window and of Direct2D
+//! so it can be drawn by any [`Renderer`], and the window that draws it
+//! independent of Win32 so it can be run without one.
+
+mod window;
+
+use clock_core::{HandAngles, Matrix, Point, Tick, TickLayout};
+use renderer::{Color, Ellipse, Renderer, Size};
+
+pub use window::{ClockWindow, FRAME_RATE, MODE};
+
+const BACKGROUND: Color =
+    Color::rgba(135.0 / 256.0, 206.0 / 256.0, 235.0 / 256.0, 0.8);
+const FILL: Color = Color::rgb(1.0, 1.0, 0.0);
+const STROKE: Color = Color::BLACK;
+
+/// The layout of a clock face filling a render target of a given size.
+#[derive(Clone, Debug, Default)]
+pub struct ClockFace {
+    dial: Ellipse,
+    ticks: Vec<Tick>,
+}
+
+impl ClockFace {
+    pub fn new(size: Size) -> Self {
+        let center = Point::new(size.width / 2.0, size.height / 2.0);
+        let radius = center.x.min(center.y);
+
+        ClockFace {
+            dial: Ellipse::circle(center, radius),
+            ticks: TickLayout::default().ticks(center, radius),
+        }
+    }
+
+    pub fn draw(&self, renderer: &mut impl Renderer, angles: HandAngles) {
+        renderer.clear(BACKGROUND);
+        renderer.set_transform(Matrix::identity());
+
+        renderer.set_brush_color(FILL);
+        renderer.fill_ellipse(self.dial);
+        renderer.set_brush_color(STROKE);
+        renderer.draw_ellipse(self.dial, 1.0);
+
+        for tick in &self.ticks {
+            renderer.draw_line(tick.outer, tick.inner, tick.width);
+        }
+
+        self.draw_clock_hand(renderer, 0.6, angles.hour, 6.0);
+        self.draw_clock_hand(renderer, 0.85, angles.minute, 4.0);
+        self.draw_clock_hand(renderer, 0.85, angles.second, 1.0);
+
+        // Restore the identity transformation.
+        renderer.set_transform(Matrix::identity());
+    }
+
+    fn draw_clock_hand(
+        &self,
+        renderer: &mut impl Renderer,
+        hand_length: f32,
+        angle: f32,
+        stroke_width: f32,
+    ) {
+        let center = self.dial.center;
+        renderer.set_transform(Matrix::rotation(angle, center));
+
+        // end_point defines one end of the hand.
+        let end_point =
+            Point::new(center.x, center.y - self.dial.radius_y * hand_length);
+
+        // Draw a line from the center of the ellipse to end_point.
+        renderer.draw_line(center, end_point, stroke_width);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock_core::{ClockMode, ClockTime};
+    use renderer::{Command, RecordingRenderer};
+
+    fn draw(size: Size, time: ClockTime) -> Vec<Command> {
+        let mut renderer = RecordingRenderer::new(size);
+        ClockFace::new(size)
+            .draw(&mut renderer, ClockMode::Smooth.angles(time));
+        renderer.take_commands()
+    }
+
+    #[test]
+    fn dial_fills_the_shorter_side() {
+        let commands = draw(Size::new(400.0, 300.0), ClockTime::hms(0, 0, 0));
+        let dial = Ellipse::circle(Point::new(200.0, 150.0), 150.0);
+
+        assert_eq!(commands[0], Command::Clear(BACKGROUND));
+        assert!(commands.contains(&Command::FillEllipse(dial)));
+        assert!(commands.contains(&Command::DrawEllipse {
+            ellipse: dial,
+            stroke_width: 1.0
+        }));
+    }
+
+    #[test]
+    fn draws_every_tick_and_three_hands() {
+        let commands = draw(Size::new(200.0, 200.0), ClockTime::hms(10, 8, 30));
+        let lines = commands
+            .iter()
+            .filter(|command| matches!(command, Command::DrawLine { .. }))
+            .count();
+        assert_eq!(lines, 60 + 3);
+    }
+
+    #[test]
+    fn hands_are_rotated_around_the_center() {
+        let size = Size::new(200.0, 200.0);
+        let center = Point::new(100.0, 100.0);
+        let commands = draw(size, ClockTime::hms(3, 0, 0));
+
+        let rotations: Vec<_> = commands
+            .iter()
+            .filter_map(|command| match command {
+                Command::SetTransform(transform) => Some(*transform),
+                _ => None,
+            })
+            .collect();
+        let hour = ClockMode::Smooth.angles(ClockTime::hms(3, 0, 0)).hour;
+        assert_eq!(
+            rotations,
+            [
+                Matrix::identity(),
+                Matrix::rotation(hour, center),
+                Matrix::rotation(0.0, center),
+                Matrix::rotation(0.0, center),
+                Matrix::identity(),
+            ]
+        );
+
+        // The hour hand points at three once it is rotated.
+        let hour_hand = commands
+            .iter()
+            .filter_map(|command| match command {
+                Command::DrawLine { to, .. } => Some(*to),
+                _ => None,
+            })
+            .nth(60)
+            .unwrap();
+        let tip = Matrix::rotation(hour, center).transform_point(hour_hand);
+        assert!(tip.distance(Point::new(160.0, 100.0)) < 1e-3);
+    }
+
+    #[test]
+    fn resizing_recomputes_the_layout() {
+        let small = ClockFace::new(Size::new(100.0, 100.0));
+        let large = ClockFace::new(Size::new(800.0, 600.0));
+        assert_eq!(small.dial.radius_x, 50.0);
+        assert_eq!(large.dial.radius_x, 300.0);
+        assert_eq!(large.ticks[0].outer, Point::new(400.0, 0.0));
+    }
+}
@@ -0,0 +1,257 @@
+use clock_core::{ClockMode, FrameScheduler, FrameStats, Pacing, TimeSource};
+use renderer::{
+    draw_frame_stats, report_frame_stats, Error, Renderer, Size, Surface,
+    WindowSurface,
+};
+use win32_common::{FrameHandler, Message, Wait, WindowHandler, WindowHost};
+
+use crate::ClockFace;
+
+/// How the clock is drawn, and so how often it needs drawing.
+pub const MODE: ClockMode = ClockMode::Smooth;
+pub const FRAME_RATE: f64 = 60.0;
+
+/// The window of this module, drawing the clock face to a surface that is
+/// created with the window.
+///
+/// A window that fails to create its surface or to draw tries again later,
+/// see [`WindowSurface`].
+pub struct ClockWindow<S> {
+    surface: WindowSurface<S>,
+    // Laid out for the size of the last frame.
+    face: ClockFace,
+    face_size: Size,
+    time_source: Box<dyn TimeSource>,
+    scheduler: FrameScheduler,
+    missed_frames: u32,
+    // Only collected, and drawn over the clock, when asked for.
+    stats: Option<FrameStats>,
+}
+
+impl<S: Surface> ClockWindow<S> {
+    /// Shows the time of `time_source`, drawing to the surface that
+    /// `create_surface` creates on `WM_CREATE`.
+    pub fn new<F>(time_source: Box<dyn TimeSource>, create_surface: F) -> Self
+    where
+        F: FnMut(&dyn WindowHost) -> Result<S, Error> + 'static,
+    {
+        ClockWindow {
+            surface: WindowSurface::new(create_surface),
+            face: ClockFace::default(),
+            face_size: Size::default(),
+            time_source,
+            scheduler: FrameScheduler::new(FRAME_RATE)
+                .with_pacing(Pacing::for_mode(MODE)),
+            missed_frames: 0,
+            stats: None,
+        }
+    }
+
+    /// Collects frame statistics in `stats`, draws them over the clock and
+    /// reports them when the window is destroyed.
+    pub fn with_stats(mut self, stats: FrameStats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    pub fn surface(&self) -> Option<&S> {
+        self.surface.get()
+    }
+
+    pub fn stats(&self) -> Option<&FrameStats> {
+        self.stats.as_ref()
+    }
+
+    /// Why the last frame wasn't drawn, unless the device was lost.
+    pub fn error(&self) -> Option<&Error> {
+        self.surface.error()
+    }
+
+    /// Invalidates the window once the next frame is due. Until then,
+    /// returns how many seconds are left.
+    pub fn tick(&mut self, host: &mut dyn WindowHost) -> f64 {
+        let now = host.now();
+        let time = self.time_source.now();
+        if let Some(wait) = self.surface.retry_in(host) {
+            return wait;
+        }
+
+        let wait = self.scheduler.time_until_next_frame(now, time);
+        if wait == 0.0 {
+            self.missed_frames =
+                self.scheduler.begin_frame(now, time).missed_frames;
+            host.invalidate();
+        }
+        wait
+    }
+
+    fn paint(&mut self, host: &mut dyn WindowHost) {
+        let ClockWindow {
+            surface,
+            face,
+            face_size,
+            time_source,
+            stats,
+            missed_frames,
+            ..
+        } = self;
+        let target = match surface.create(host) {
+            Some(target) => target,
+            None => return,
+        };
+
+        let started = host.now();
+        let mut drawn = started;
+        let angles = MODE.angles(time_source.now());
+        let presented = target.draw(&mut |mut renderer: &mut dyn Renderer| {
+            let size = renderer.size();
+            if size != *face_size {
+                *face = ClockFace::new(size);
+                *face_size = size;
+            }
+            face.draw(&mut renderer, angles);
+            if let Some(stats) = stats.as_ref() {
+                draw_frame_stats(&mut renderer, stats, 1.0 / FRAME_RATE);
+            }
+            drawn = host.now();
+        });
+
+        if let Some(stats) = stats {
+            stats.record(started, host.now() - drawn, *missed_frames);
+        }
+        surface.presented(host, presented);
+    }
+}
+
+impl<S: Surface> WindowHandler for ClockWindow<S> {
+    fn handle(
+        &mut self,
+        host: &mut dyn WindowHost,
+        message: Message,
+    ) -> Option<isize> {
+        match message {
+            Message::Create => {
+                self.surface.create(host);
+                Some(0)
+            }
+            Message::Paint | Message::DisplayChange { .. } => {
+                self.paint(host);
+                Some(0)
+            }
+            Message::Size { .. } => {
+                // A surface that lost its device while resizing is created
+                // again at the new size when it is drawn next.
+                self.surface.resize(host);
+                host.invalidate();
+                Some(0)
+            }
+            Message::Destroy => {
+                self.surface.release();
+                if let Some(stats) = &self.stats {
+                    report_frame_stats(stats);
+                }
+                host.quit(0);
+                Some(0)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<S: Surface> FrameHandler for ClockWindow<S> {
+    /// Invalidates the window once the next frame is due, see [`tick`].
+    ///
+    /// [`tick`]: ClockWindow::tick
+    fn frame(&mut self, host: &mut dyn WindowHost) -> Wait {
+        Wait::For(self.tick(host))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock_core::{ClockTime, FixedTime};
+    use renderer::{RecordingSurface, RETRY_DELAY};
+    use win32_common::replay::VirtualWindow;
+
+    fn window() -> ClockWindow<RecordingSurface> {
+        let time = Box::new(FixedTime(ClockTime::hms(10, 8, 30)));
+        ClockWindow::new(time, |host: &dyn WindowHost| {
+            let (width, height) = host.client_size();
+            Ok(RecordingSurface::new(width, height))
+        })
+    }
+
+    #[test]
+    fn frames_are_invalidated_at_the_frame_rate() {
+        let mut window = window();
+        let mut host = VirtualWindow::default();
+
+        assert_eq!(window.tick(&mut host), 0.0);
+        assert!(host.invalidated);
+
+        host.invalidated = false;
+        host.time = 0.01;
+        assert!(window.tick(&mut host) > 0.0);
+        assert!(!host.invalidated);
+        host.time = 1.0 / FRAME_RATE;
+        assert_eq!(window.tick(&mut host), 0.0);
+        assert!(host.invalidated);
+    }
+
+    #[test]
+    fn nothing_is_drawn_without_a_surface() {
+        let time = Box::new(FixedTime(ClockTime::hms(10, 8, 30)));
+        let error = Error::ResourceCreation(
+            renderer::ResourceKind::Target,
+            "no adapter".to_string(),
+        );
+        let failed = error.clone();
+        let mut window: ClockWindow<RecordingSurface> =
+            ClockWindow::new(time, move |_: &dyn WindowHost| {
+                Err(failed.clone())
+            });
+        let mut host = VirtualWindow::default();
+
+        window.handle(&mut host, Message::Create);
+        assert_eq!(window.handle(&mut host, Message::Paint), Some(0));
+        assert!(window.surface().is_none());
+        assert_eq!(window.error(), Some(&error));
+    }
+
+    #[test]
+    fn a_failed_window_waits_before_trying_again() {
+        let time = Box::new(FixedTime(ClockTime::hms(10, 8, 30)));
+        let mut window: ClockWindow<RecordingSurface> =
+            ClockWindow::new(time, |_: &dyn WindowHost| {
+                Err(Error::Draw("failing on purpose".to_string()))
+            });
+        let mut host = VirtualWindow::default();
+        window.handle(&mut host, Message::Create);
+
+        host.time = 0.25;
+        assert_eq!(window.tick(&mut host), RETRY_DELAY - 0.25);
+        assert!(!host.invalidated);
+        host.time = RETRY_DELAY;
+        assert_eq!(window.tick(&mut host), 0.0);
+        assert!(host.invalidated);
+    }
+
+    #[test]
+    fn stats_are_collected_per_frame() {
+        let mut window =
+            window().with_stats(FrameStats::new(10 * FRAME_RATE as usize));
+        let mut host = VirtualWindow {
+            client_size: (300, 200),
+            ..VirtualWindow::default()
+        };
+
+        window.handle(&mut host, Message::Create);
+        for frame in 0..3 {
+            host.time = frame as f64 / FRAME_RATE;
+            window.handle(&mut host, Message::Paint);
+        }
+        // The first frame only starts the clock.
+        assert_eq!(window.stats().unwrap().frames(), 2);
+    }
+}
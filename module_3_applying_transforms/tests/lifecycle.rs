@@ -0,0 +1,88 @@
+//! The clock face window drawing at its frame rate, collecting frame
+//! statistics and recovering from a lost device, run through the message
+//! loop of `main` with its frames recorded instead of drawn with Direct2D.
+
+use clock_core::{ClockTime, FixedTime, FrameStats};
+use module_3_applying_transforms::{ClockWindow, FRAME_RATE};
+use renderer::{FaultInjectingSurface, RecordingSurface, ResourceKind, Size};
+use win32_common::{HeadlessHost, WindowHost};
+
+fn window() -> ClockWindow<RecordingSurface> {
+    let time = Box::new(FixedTime(ClockTime::hms(10, 8, 30)));
+    ClockWindow::new(time, |host: &dyn WindowHost| {
+        let (width, height) = host.client_size();
+        Ok(RecordingSurface::new(width, height))
+    })
+}
+
+fn host() -> HeadlessHost<ClockWindow<RecordingSurface>> {
+    HeadlessHost::new(window()).with_client_size(300, 300)
+}
+
+#[test]
+fn draws_at_the_frame_rate_until_closed() {
+    let mut host = host();
+    assert!(host.create());
+    host.run_for(1.0);
+
+    let frames = host.handler().surface().unwrap().frames().len();
+    // The first frame is drawn when the window is shown.
+    assert!(
+        (frames as f64 - FRAME_RATE).abs() <= 2.0,
+        "{} frames",
+        frames
+    );
+
+    host.close();
+    assert!(host.handler().surface().is_none());
+    assert_eq!(host.exit_code(), Some(0));
+}
+
+#[test]
+fn the_surface_follows_the_window_through_minimizing() {
+    let mut host = host();
+    host.create();
+    host.minimize();
+    host.restore();
+    host.resize(500, 200);
+
+    let surface = host.handler().surface().unwrap();
+    assert_eq!(surface.size(), Size::new(500.0, 200.0));
+    let frames = surface.frames();
+    // Only the restored window is painted, and the clock is drawn once more
+    // at its new size.
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[0], frames[1]);
+    assert_ne!(frames[1], frames[2]);
+}
+
+#[test]
+fn the_stats_cover_every_frame_but_the_first() {
+    let stats = FrameStats::new(10 * FRAME_RATE as usize);
+    let mut host = HeadlessHost::new(window().with_stats(stats));
+    host.create();
+    host.run_for(0.5);
+
+    let frames = host.handler().surface().unwrap().frames().len();
+    assert_eq!(host.handler().stats().unwrap().frames(), frames as u64 - 1);
+}
+
+#[test]
+fn drawing_recovers_every_time_the_device_is_lost() {
+    let time = Box::new(FixedTime(ClockTime::hms(10, 8, 30)));
+    let window = ClockWindow::new(time, |host: &dyn WindowHost| {
+        let (width, height) = host.client_size();
+        let surface = FaultInjectingSurface::new(width, height);
+        Ok(surface.with_device_lost_at([0, 5, 6, 30]))
+    });
+    let mut host = HeadlessHost::new(window).with_client_size(300, 300);
+    host.create();
+    host.run_for(1.0);
+
+    let surface = host.handler().surface().unwrap();
+    assert_eq!(surface.frames_drawn() - surface.frames().len(), 4);
+    assert!(surface.frames().len() as f64 >= FRAME_RATE - 4.0);
+    let resources = surface.resources();
+    assert_eq!(resources.devices_lost(), 4);
+    assert_eq!(resources.created(ResourceKind::Target), 5);
+}
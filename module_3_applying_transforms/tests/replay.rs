@@ -0,0 +1,113 @@
+//! Replays recorded message streams against the clock window, drawing to a
+//! recording surface instead of Direct2D.
+//!
+//! To reproduce a bug seen on Windows, run the module with
+//! `WIN32_TRACE=binary:session.trace`, load the file with
+//! `Recording::load` and replay it like the recordings below.
+
+use clock_core::{ClockTime, FixedTime, Point};
+use module_3_applying_transforms::ClockWindow;
+use renderer::{Command, Ellipse, RecordingSurface};
+use win32_common::{
+    replay::{replay, Recording, VirtualWindow},
+    Message, SizeKind, WindowHost,
+};
+
+fn window() -> ClockWindow<RecordingSurface> {
+    let time = Box::new(FixedTime(ClockTime::hms(10, 8, 30)));
+    ClockWindow::new(time, |host: &dyn WindowHost| {
+        let (width, height) = host.client_size();
+        Ok(RecordingSurface::new(width, height))
+    })
+}
+
+fn size(width: u16, height: u16) -> Message {
+    Message::Size {
+        kind: SizeKind::Restored,
+        width,
+        height,
+    }
+}
+
+fn dial(frame: &[Command]) -> Ellipse {
+    frame
+        .iter()
+        .find_map(|command| match command {
+            Command::FillEllipse(ellipse) => Some(*ellipse),
+            _ => None,
+        })
+        .expect("No dial drawn")
+}
+
+#[test]
+fn resizing_before_the_first_paint_lays_out_the_new_size() {
+    // The window is created before its first `WM_SIZE`, so the surface
+    // starts out empty and has to follow the size it is given later.
+    let mut recording = Recording::new();
+    recording.push(0.0, Message::Create);
+    recording.push(0.0, size(400, 300));
+    recording.push(0.1, Message::Paint);
+    recording.push(0.2, size(200, 100));
+    recording.push(0.3, Message::Paint);
+
+    let mut window = window();
+    let mut host = VirtualWindow::default();
+    replay(&recording, &mut window, &mut host);
+
+    let frames = window.surface().unwrap().frames();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(
+        dial(&frames[0]),
+        Ellipse::circle(Point::new(200.0, 150.0), 150.0)
+    );
+    assert_eq!(
+        dial(&frames[1]),
+        Ellipse::circle(Point::new(100.0, 50.0), 50.0)
+    );
+}
+
+#[test]
+fn a_saved_session_replays_the_same_frames() {
+    let mut recording = Recording::new();
+    recording.push(0.0, Message::Create);
+    recording.push(0.0, size(320, 240));
+    recording.push(
+        0.0,
+        Message::Activate {
+            active: true,
+            minimized: false,
+        },
+    );
+    for frame in 1..=5 {
+        recording.push(frame as f64 / 60.0, Message::Paint);
+    }
+    recording.push(0.5, Message::MouseMove { x: 10, y: 10 });
+    recording.push(0.6, Message::Close);
+    recording.push(0.6, Message::Destroy);
+
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("applying-transforms-session.trace");
+    recording.save(&path).unwrap();
+    let loaded = Recording::load(&path).unwrap();
+    assert_eq!(loaded, recording);
+
+    let mut first = window();
+    let mut host = VirtualWindow::default();
+    let results = replay(&loaded, &mut first, &mut host);
+
+    // Activation, input and closing are left to the default procedure.
+    assert_eq!(results[2], None);
+    assert_eq!(results[8], None);
+    assert_eq!(results[9], None);
+    assert_eq!(host.exit_code, Some(0));
+    // The surface goes with the window.
+    assert!(first.surface().is_none());
+
+    // Up to destroying the window, a second replay draws exactly the same.
+    let until_destroyed = Recording::from_events(loaded.events()[..8].to_vec());
+    let mut second = window();
+    replay(&until_destroyed, &mut second, &mut VirtualWindow::default());
+    let frames = second.surface().unwrap().frames();
+    assert_eq!(frames.len(), 5);
+    assert!(frames.windows(2).all(|pair| pair[0] == pair[1]));
+}
@@ -0,0 +1,249 @@
+//! The clock drawn by this module, independent of the window and of Direct2D
+//! so it can be drawn by any [`Renderer`], and the window that draws it
+//! independent of Win32 so it can be run without one.
+
+mod window;
+
+use clock_core::{
+    animation::{Manager, Storyboard, Transition, Variable},
+    HandAngles, Matrix, Point,
+};
+use renderer::{CapStyle, Color, Ellipse, Renderer, StrokeStyle};
+
+pub use window::{
+    ClockWindow, ShadowSurface, FRAME_RATE, MODE, OCCLUSION_MESSAGE,
+};
+
+// The brush used to be this color at 80% opacity.
+const COLOR: Color = Color::rgba(0.92, 0.38, 0.208, 0.8);
+
+// https://docs.microsoft.com/en-us/windows/win32/api/d2d1/ns-d2d1-d2d1_stroke_style_properties
+const HAND_STYLE: StrokeStyle = StrokeStyle {
+    start_cap: CapStyle::Round,
+    end_cap: CapStyle::Triangle,
+};
+
+/// The variables animated when the clock appears, all going from 0 to 1.
+#[derive(Clone, Debug)]
+pub struct Intro {
+    pub dial: Variable,
+    pub second: Variable,
+    pub minute: Variable,
+    pub hour: Variable,
+    pub shadow: Variable,
+}
+
+impl Intro {
+    /// The dial springs in, the hands sweep into place one after the other
+    /// and then the shadow fades in.
+    pub fn new(manager: &Manager) -> (Intro, Storyboard) {
+        let intro = Intro::at(manager, 0.0);
+
+        let sweep = Transition::accelerate_decelerate(2.5, 1.0, 0.2, 0.8)
+            .expect("Invalid transition");
+        let mut storyboard = Storyboard::new();
+        storyboard.add_transition(
+            &intro.dial,
+            Transition::spring(1.0, 1.0, 0.4).expect("Invalid transition"),
+        );
+        storyboard.add_delay(&intro.second, 0.5);
+        storyboard.add_transition(&intro.second, sweep);
+        storyboard.add_delay(&intro.minute, 1.0);
+        storyboard.add_transition(&intro.minute, sweep);
+        storyboard.add_delay(&intro.hour, 1.5);
+        let last = storyboard.add_transition(&intro.hour, sweep);
+        let swept = storyboard.add_keyframe_after_transition(last);
+        storyboard
+            .add_transition_at_keyframe(
+                &intro.shadow,
+                Transition::linear(0.5, 1.0).expect("Invalid transition"),
+                swept,
+            )
+            .expect("The shadow has no other transitions");
+
+        (intro, storyboard)
+    }
+
+    /// The variables as the intro leaves them, for a clock shown without it.
+    pub fn finished(manager: &Manager) -> Intro {
+        Intro::at(manager, 1.0)
+    }
+
+    fn at(manager: &Manager, value: f64) -> Intro {
+        Intro {
+            dial: manager.create_variable(value),
+            second: manager.create_variable(value),
+            minute: manager.create_variable(value),
+            hour: manager.create_variable(value),
+            shadow: manager.create_variable(value),
+        }
+    }
+}
+
+/// Draws the clock centered on the target, leaving room for its shadow.
+pub fn draw_clock(renderer: &mut impl Renderer, angles: HandAngles) {
+    draw_clock_scaled(renderer, angles, 1.0);
+}
+
+/// Draws the clock like [`draw_clock`] with its radius scaled by `scale`.
+pub fn draw_clock_scaled(
+    renderer: &mut impl Renderer,
+    angles: HandAngles,
+    scale: f32,
+) {
+    let size = renderer.size();
+
+    let radius =
+        (size.width.min(size.height).max(200.0) / 2.0 - 50.0) * scale.max(0.0);
+    let translation = Matrix::translation(size.width / 2.0, size.height / 2.0);
+
+    renderer.set_brush_color(COLOR);
+    renderer.set_stroke_style(HAND_STYLE);
+    renderer.set_transform(translation);
+    renderer
+        .draw_ellipse(Ellipse::circle(Point::default(), radius), radius / 20.0);
+
+    let hands = [
+        (angles.second, 0.75, radius / 25.0),
+        (angles.minute, 0.75, radius / 15.0),
+        (angles.hour, 0.5, radius / 10.0),
+    ];
+    for (angle, length, width) in hands {
+        renderer.set_transform(
+            Matrix::rotation(angle, Point::default()) * translation,
+        );
+        renderer.draw_line(
+            Point::default(),
+            Point::new(0.0, -(radius * length)),
+            width,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use renderer::{Command, RecordingRenderer, Size};
+
+    fn lines(commands: &[Command]) -> Vec<(Matrix, Point, f32)> {
+        let mut transform = Matrix::identity();
+        let mut lines = Vec::new();
+        for command in commands {
+            match command {
+                Command::SetTransform(matrix) => transform = *matrix,
+                Command::DrawLine {
+                    to, stroke_width, ..
+                } => lines.push((transform, *to, *stroke_width)),
+                _ => {}
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn dial_is_centered_and_leaves_a_margin() {
+        let mut renderer = RecordingRenderer::new(Size::new(600.0, 400.0));
+        draw_clock(&mut renderer, HandAngles::default());
+
+        let commands = renderer.commands();
+        assert!(commands.contains(&Command::SetTransform(
+            Matrix::translation(300.0, 200.0)
+        )));
+        assert!(commands.contains(&Command::DrawEllipse {
+            ellipse: Ellipse::circle(Point::default(), 150.0),
+            stroke_width: 7.5,
+        }));
+        assert!(commands.contains(&Command::SetStrokeStyle(HAND_STYLE)));
+    }
+
+    #[test]
+    fn small_targets_keep_a_minimum_radius() {
+        let mut renderer = RecordingRenderer::new(Size::new(50.0, 50.0));
+        draw_clock(&mut renderer, HandAngles::default());
+
+        assert!(renderer.commands().contains(&Command::DrawEllipse {
+            ellipse: Ellipse::circle(Point::default(), 50.0),
+            stroke_width: 2.5,
+        }));
+    }
+
+    #[test]
+    fn the_intro_sweeps_the_hands_in_sequence() {
+        let mut manager = Manager::new();
+        let (intro, storyboard) = Intro::new(&manager);
+        assert_eq!(storyboard.duration(), Some(4.5));
+        manager.schedule(storyboard, 0.0).unwrap();
+
+        manager.update(1.25);
+        assert_eq!(intro.dial.value(), 1.0);
+        assert!(intro.second.value() > intro.minute.value());
+        assert!(intro.minute.value() > 0.0);
+        assert_eq!(intro.hour.value(), 0.0);
+
+        manager.update(4.0);
+        assert_eq!(intro.second.value(), 1.0);
+        assert_eq!(intro.shadow.value(), 0.0);
+        manager.update(4.5);
+        for variable in [&intro.minute, &intro.hour, &intro.shadow] {
+            assert_eq!(variable.value(), 1.0);
+        }
+        assert!(!manager.is_busy());
+    }
+
+    #[test]
+    fn scaling_shrinks_the_dial() {
+        let mut renderer = RecordingRenderer::new(Size::new(400.0, 400.0));
+        draw_clock_scaled(&mut renderer, HandAngles::default(), 0.5);
+
+        assert!(renderer.commands().contains(&Command::DrawEllipse {
+            ellipse: Ellipse::circle(Point::default(), 75.0),
+            stroke_width: 3.75,
+        }));
+    }
+
+    #[test]
+    fn hands_point_at_their_angles() {
+        let mut renderer = RecordingRenderer::new(Size::new(400.0, 400.0));
+        let angles = HandAngles {
+            hour: 90.0,
+            minute: 180.0,
+            second: 270.0,
+        };
+        draw_clock(&mut renderer, angles);
+
+        // Radius 150 around (200, 200).
+        let expected = [
+            (Point::new(87.5, 200.0), 6.0),
+            (Point::new(200.0, 312.5), 10.0),
+            (Point::new(275.0, 200.0), 15.0),
+        ];
+        let lines = lines(renderer.commands());
+        assert_eq!(lines.len(), 3);
+        for ((transform, to, width), (tip, expected_width)) in
+            lines.into_iter().zip(expected)
+        {
+            assert!(transform.transform_point(to).distance(tip) < 1e-3);
+            assert_eq!(width, expected_width);
+        }
+    }
+
+    #[test]
+    fn the_intro_ends_with_every_variable_at_one() {
+        let mut manager = Manager::new();
+        let (intro, storyboard) = Intro::new(&manager);
+        manager.schedule(storyboard, 0.0).unwrap();
+        manager.update(10.0);
+
+        let finished = Intro::finished(&manager);
+        for (ended, skipped) in [
+            (&intro.dial, &finished.dial),
+            (&intro.second, &finished.second),
+            (&intro.minute, &finished.minute),
+            (&intro.hour, &finished.hour),
+            (&intro.shadow, &finished.shadow),
+        ] {
+            assert!((ended.value() - 1.0).abs() < 1e-3);
+            assert_eq!(skipped.value(), 1.0);
+        }
+    }
+}
@@ -1,105 +1,166 @@
+#[cfg(windows)]
+use clock_core::{parse_time_arg, FrameStats};
+#[cfg(windows)]
+use module_3_bonus::{
+    ClockWindow, ShadowSurface, FRAME_RATE, OCCLUSION_MESSAGE,
+};
+#[cfg(windows)]
+use renderer::{
+    DeviceResource, DeviceResources, Direct2DRenderer, Error, Renderer,
+    ResourceKind, Surface,
+};
+#[cfg(windows)]
+use std::process::ExitCode;
+#[cfg(windows)]
+use win32_common::{
+    handler_wndproc, run_frame_loop, trace, ClassStyle, Cursor, WindowBuilder,
+    WindowHost, WindowState,
+};
+#[cfg(windows)]
 use windows::{
     core::*, Foundation::Numerics::*, Win32::Foundation::*,
     Win32::Graphics::Direct2D::Common::*, Win32::Graphics::Direct2D::*,
     Win32::Graphics::Direct3D::*, Win32::Graphics::Direct3D11::*,
     Win32::Graphics::Dxgi::Common::*, Win32::Graphics::Dxgi::*,
-    Win32::Graphics::Gdi::*, Win32::System::Com::*,
-    Win32::System::LibraryLoader::*, Win32::System::Performance::*,
-    Win32::System::SystemInformation::GetLocalTime, Win32::UI::Animation::*,
-    Win32::UI::WindowsAndMessaging::*,
+    Win32::System::Com::*, Win32::UI::WindowsAndMessaging::*,
 };
 
-fn main() -> Result<()> {
+#[cfg(windows)]
+type Window = ClockWindow<SwapChainSurface>;
+
+#[cfg(windows)]
+const USAGE: &str = "\
+usage: module_3_bonus [HH:MM[:SS[.mmm]]] [--stats]
+
+Shows a clock at the given time, or at the local time if there is none.
+With --stats the frame statistics are drawn over the clock, and the frame
+times are written to frame-stats.csv once the window is closed.";
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("module_3_bonus only runs on Windows.");
+}
+
+#[cfg(windows)]
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            error.exit_code()
+        }
+    }
+}
+
+#[cfg(windows)]
+fn run() -> std::result::Result<(), Error> {
+    // Set `WIN32_TRACE=text` to log every message the window handles, see
+    // `win32_common::trace`.
+    let _trace = trace::install_from_env();
+
+    // Pass a time like `10:08:30` to show it instead of the local time, and
+    // `--stats` for frame statistics.
+    let stats = std::env::args().skip(1).any(|arg| arg == "--stats");
+    let time_source = parse_time_arg(std::env::args().skip(1))
+        .map_err(|error| Error::Usage(format!("{}\n\n{}", error, USAGE)))?;
+
     unsafe {
-        CoInitializeEx(std::ptr::null_mut(), COINIT_MULTITHREADED)?;
+        CoInitializeEx(std::ptr::null_mut(), COINIT_MULTITHREADED)
+            .map_err(|error| Error::ComInit(error.to_string()))?;
     }
-    let mut window = Window::new()?;
-    window.run()
+
+    let mut window = Window::new(time_source, |host: &dyn WindowHost| {
+        SwapChainSurface::new(HWND(host.handle()), host.dpi() as f32)
+    });
+    if stats {
+        // The last ten seconds of frames.
+        window = window.with_stats(FrameStats::new(10 * FRAME_RATE as usize));
+    }
+
+    // The window owns its handler from `WM_NCCREATE` until `WM_NCDESTROY`,
+    // see `win32_common::handler_wndproc`.
+    let handle = WindowBuilder::new("Sample Window")
+        .cursor(Cursor::Hand)
+        .class_style(ClassStyle::HREDRAW | ClassStyle::VREDRAW)
+        .user_data(WindowState::create_param(window))
+        .build(handler_wndproc::<Window>)
+        .map_err(|error| Error::WindowCreation(error.to_string()))?;
+
+    run_frame_loop::<Window>(handle)
+        .map_err(|error| Error::Timer(error.to_string()))
 }
 
-struct Window {
-    handle: HWND,
+/// Draws to a flip model swap chain of a window through a Direct2D device
+/// context. The clock is drawn to a bitmap of its own, which the shadow
+/// effect takes as its input.
+///
+/// The device and everything created with it are created when a frame is
+/// drawn, and again after the device was lost, see
+/// `renderer::DeviceResources`.
+#[cfg(windows)]
+pub struct SwapChainSurface {
+    window: HWND,
     factory: ID2D1Factory1,
     dxfactory: IDXGIFactory2,
-    style: ID2D1StrokeStyle,
-    manager: IUIAnimationManager,
-    variable: IUIAnimationVariable,
-
-    target: Option<ID2D1DeviceContext>,
-    swapchain: Option<IDXGISwapChain1>,
-    brush: Option<ID2D1SolidColorBrush>,
-    shadow: Option<ID2D1Effect>,
-    clock: Option<ID2D1Bitmap1>,
     dpi: f32,
-    visible: bool,
+
+    resources: DeviceResources,
+    device: DeviceResource<Device>,
+    swapchain: DeviceResource<IDXGISwapChain1>,
+    // The back buffer of the swap chain and the bitmap the clock is drawn
+    // to, both the size of the client area.
+    buffer: DeviceResource<ID2D1Bitmap1>,
+    clock: DeviceResource<ID2D1Bitmap1>,
+    shadow: DeviceResource<ID2D1Effect>,
+    // Owns the brush.
+    renderer: DeviceResource<Direct2DRenderer>,
+    // The cookie of the registration for the occlusion status, `0` while
+    // the window is not occluded.
     occlusion: u32,
-    frequency: i64,
-    angles: Angles,
 }
 
-// Windows struct
-//
+/// A Direct3D device and the Direct2D device context that draws with it.
+#[cfg(windows)]
+struct Device {
+    device: ID3D11Device,
+    context: ID2D1DeviceContext,
+}
 
-impl Window {
-    pub fn new() -> Result<Self> {
-        let factory = create_factory()?;
+/// What drawing a frame takes, see
+/// [`SwapChainSurface::create_device_resources`].
+#[cfg(windows)]
+struct Frame<'a> {
+    target: ID2D1DeviceContext,
+    swapchain: IDXGISwapChain1,
+    clock: ID2D1Bitmap1,
+    shadow: ID2D1Effect,
+    renderer: &'a mut Direct2DRenderer,
+}
+
+#[cfg(windows)]
+impl SwapChainSurface {
+    pub fn new(window: HWND, dpi: f32) -> std::result::Result<Self, Error> {
+        let factory =
+            create_factory().map_err(creation_error(ResourceKind::Target))?;
         // Use a DXGI 1.1 factory to generate objects that enumerate adapters,
         // create swap chains, and associate a window with the alt+enter key
         // sequence for toggling to and from the full-screen display mode.
-        let dxfactory: IDXGIFactory2 = unsafe { CreateDXGIFactory1()? };
-        let style = create_style(&factory)?;
-
-        // Defines the animation manager, which provides a central interface for
-        // creating and managing animations.
-        let manager: IUIAnimationManager =
-            // Creates and default-initializes a single object of the class associated with a specified CLSID.
-            unsafe { CoCreateInstance(&UIAnimationManager, None, CLSCTX_ALL)? };
-        let transition = create_transition()?;
-
-        // Retrieves the current desktop dots per inch (DPI).
-        let mut dpi = 0.0;
-        let mut dpiy = 0.0;
-        unsafe { factory.GetDesktopDpi(&mut dpi, &mut dpiy) };
-
-        // Retrieves the frequency of the performance counter. The frequency of
-        // the performance counter is fixed at system boot and is consistent
-        // across all processors. Therefore, the frequency need only be queried
-        // upon application initialization, and the result can be cached.
-        // If the installed hardware supports a high-resolution performance
-        // counter, the return value is nonzero.
-        let mut frequency = 0;
-        unsafe { QueryPerformanceFrequency(&mut frequency).ok()? };
-
-        let variable = unsafe {
-            let variable = manager.CreateAnimationVariable(0.0)?;
-
-            // Creates and schedules a single-transition storyboard.
-            manager.ScheduleTransition(
-                &variable,
-                transition,
-                get_time(frequency)?,
-            )?;
-
-            variable
-        };
+        let dxfactory: IDXGIFactory2 = unsafe { CreateDXGIFactory1() }
+            .map_err(creation_error(ResourceKind::SwapChain))?;
 
-        return Ok(Window {
-            handle: HWND(0),
+        return Ok(SwapChainSurface {
+            window,
             factory,
             dxfactory,
-            style,
-            manager,
-            variable,
-            target: None,
-            swapchain: None,
-            brush: None,
-            shadow: None,
-            clock: None,
             dpi,
-            visible: false,
+            resources: DeviceResources::new(),
+            device: DeviceResource::new(ResourceKind::Target),
+            swapchain: DeviceResource::new(ResourceKind::SwapChain),
+            buffer: DeviceResource::new(ResourceKind::Bitmap),
+            clock: DeviceResource::new(ResourceKind::Bitmap),
+            shadow: DeviceResource::new(ResourceKind::Effect),
+            renderer: DeviceResource::new(ResourceKind::Brush),
             occlusion: 0,
-            frequency,
-            angles: Angles::now(),
         });
 
         // Internal helper functions
@@ -119,146 +180,79 @@ impl Window {
                     D2D1_FACTORY_TYPE_SINGLE_THREADED,
                     &ID2D1Factory1::IID,
                     &options,
-                    std::mem::transmute(&mut result),
+                    &mut result as *mut Option<ID2D1Factory1> as *mut _,
                 )
                 .map(|()| result.unwrap())
             }
         }
-
-        fn create_style(factory: &ID2D1Factory1) -> Result<ID2D1StrokeStyle> {
-            // https://docs.microsoft.com/en-us/windows/win32/api/d2d1/ns-d2d1-d2d1_stroke_style_properties
-            let props = D2D1_STROKE_STYLE_PROPERTIES {
-                startCap: D2D1_CAP_STYLE_ROUND,
-                endCap: D2D1_CAP_STYLE_TRIANGLE,
-                ..Default::default()
-            };
-
-            unsafe { factory.CreateStrokeStyle(&props, std::ptr::null(), 0) }
-        }
-
-        // Defines a transition, which determines how an animation variable
-        // changes over time.
-        fn create_transition() -> Result<IUIAnimationTransition> {
-            unsafe {
-                let library: IUIAnimationTransitionLibrary = CoCreateInstance(
-                    &UIAnimationTransitionLibrary,
-                    None,
-                    CLSCTX_ALL,
-                )?;
-                // https://docs.microsoft.com/en-us/windows/win32/api/uianimation/nf-uianimation-iuianimationtransitionlibrary-createacceleratedeceleratetransition
-                library.CreateAccelerateDecelerateTransition(5.0, 1.0, 0.2, 0.8)
-            }
-        }
     }
 
-    // Actual window creation and loop initialization.
-    pub fn run(&mut self) -> Result<()> {
-        unsafe {
-            let instance = GetModuleHandleA(None);
-            debug_assert!(instance.0 != 0);
-
-            let wc = WNDCLASSA {
-                hCursor: LoadCursorW(None, IDC_HAND),
-                hInstance: instance,
-                lpszClassName: PSTR(b"window\0".as_ptr() as _),
-
-                style: CS_HREDRAW | CS_VREDRAW,
-                lpfnWndProc: Some(Self::wndproc),
-                ..Default::default()
-            };
-
-            let atom = RegisterClassA(&wc);
-            debug_assert!(atom != 0);
-
-            let handle = CreateWindowExA(
-                Default::default(),
-                PSTR(b"window\0".as_ptr() as _),
-                PSTR(b"Sample Window\0".as_ptr() as _),
-                WS_OVERLAPPEDWINDOW | WS_VISIBLE,
-                CW_USEDEFAULT,
-                CW_USEDEFAULT,
-                CW_USEDEFAULT,
-                CW_USEDEFAULT,
-                None,
-                None,
-                instance,
-                self as *mut _ as _,
-            );
-
-            debug_assert!(handle.0 != 0);
-            debug_assert!(handle == self.handle);
-            let mut message = MSG::default();
-
-            loop {
-                // Initially the window is not visible
-                if self.visible {
-                    self.render()?;
-
-                    while PeekMessageA(&mut message, None, 0, 0, PM_REMOVE)
-                        .into()
-                    {
-                        if message.message == WM_QUIT {
-                            return Ok(());
-                        }
-                        DispatchMessageA(&message);
-                    }
-                } else {
-                    // this will call wndproc for the first time with the
-                    // `WM_NCCREATE` message, allowing ous to register the user
-                    // data with the Windows window.
-                    GetMessageA(&mut message, None, 0, 0);
-
-                    if message.message == WM_QUIT {
-                        return Ok(());
-                    }
-
-                    DispatchMessageA(&message);
-                }
-            }
-        }
+    pub fn resources(&self) -> &DeviceResources {
+        &self.resources
     }
 
-    fn render(&mut self) -> Result<()> {
-        // Initial setting things up
-        if self.target.is_none() {
-            let device = create_device()?;
-            let target = create_render_target(&self.factory, &device)?;
-            unsafe { target.SetDpi(self.dpi, self.dpi) };
-
-            let swapchain = create_swapchain(&device, self.handle)?;
-            create_swapchain_bitmap(&swapchain, &target)?;
-
-            self.brush = create_brush(&target).ok();
-            self.target = Some(target);
-            self.swapchain = Some(swapchain);
-            self.create_device_size_resources()?;
-        }
-
-        // Drawing our clock in the bitmap
-        let target = self.target.as_ref().unwrap();
-        unsafe { target.BeginDraw() };
-        self.draw(target)?;
-
-        unsafe {
-            target.EndDraw(std::ptr::null_mut(), std::ptr::null_mut())?;
-        }
-
-        if let Err(error) = self.present(1, 0) {
-            if error.code() == DXGI_STATUS_OCCLUDED {
-                self.occlusion = unsafe {
-                    self.dxfactory
-                        .RegisterOcclusionStatusWindow(self.handle, WM_USER)?
-                };
-                self.visible = false;
-            } else {
-                self.release_device();
-            }
-        }
+    /// Creates whatever is missing for drawing a frame, and targets the
+    /// back buffer of the swap chain.
+    fn create_device_resources(
+        &mut self,
+    ) -> std::result::Result<Frame<'_>, Error> {
+        let SwapChainSurface {
+            window,
+            factory,
+            dpi,
+            resources,
+            device,
+            swapchain,
+            buffer,
+            clock,
+            shadow,
+            renderer,
+            ..
+        } = self;
+
+        let device = resources.get(device, || {
+            create_device(factory, *dpi)
+                .map_err(creation_error(ResourceKind::Target))
+        })?;
+        let target = device.context.clone();
+        let swapchain = resources
+            .get(swapchain, || {
+                create_swapchain(&device.device, *window)
+                    .map_err(creation_error(ResourceKind::SwapChain))
+            })?
+            .clone();
+        let buffer = resources.get(buffer, || {
+            create_swapchain_bitmap(&swapchain, &target)
+                .map_err(creation_error(ResourceKind::Bitmap))
+        })?;
+        unsafe { target.SetTarget(&*buffer) };
+
+        // The size of the target is that of the back buffer.
+        let clock = resources.get(clock, || {
+            create_clock(&target, *dpi)
+                .map_err(creation_error(ResourceKind::Bitmap))
+        })?;
+        let shadow = resources.get(shadow, || {
+            unsafe { target.CreateEffect(&CLSID_D2D1Shadow) }
+                .map_err(creation_error(ResourceKind::Effect))
+        })?;
+        unsafe { shadow.SetInput(0, &*clock, true) };
+
+        return Ok(Frame {
+            clock: clock.clone(),
+            shadow: shadow.clone(),
+            renderer: resources.get(renderer, || {
+                Direct2DRenderer::new(target.clone())
+                    .map_err(creation_error(ResourceKind::Brush))
+            })?,
+            target,
+            swapchain,
+        });
 
-        // Helper functions for the render method
+        // Helper functions for creating the device resources
         //
 
-        fn create_device() -> Result<ID3D11Device> {
+        fn create_device(factory: &ID2D1Factory1, dpi: f32) -> Result<Device> {
             let mut result = create_device_with_type(D3D_DRIVER_TYPE_HARDWARE);
 
             if let Err(err) = &result {
@@ -267,7 +261,10 @@ impl Window {
                 }
             }
 
-            return result;
+            let device = result?;
+            let context = create_render_target(factory, &device)?;
+            unsafe { context.SetDpi(dpi, dpi) };
+            return Ok(Device { device, context });
 
             // Helper functions for create_device function
             //
@@ -367,82 +364,145 @@ impl Window {
             }
         }
 
-        fn create_brush(
+        fn create_clock(
             target: &ID2D1DeviceContext,
-        ) -> Result<ID2D1SolidColorBrush> {
-            let color = D2D1_COLOR_F {
-                r: 0.92,
-                g: 0.38,
-                b: 0.208,
-                a: 1.0,
+            dpi: f32,
+        ) -> Result<ID2D1Bitmap1> {
+            let size_f = unsafe { target.GetSize() };
+
+            let size_u = D2D_SIZE_U {
+                width: (size_f.width * dpi / 96.0) as u32,
+                height: (size_f.height * dpi / 96.0) as u32,
             };
 
-            let properties = D2D1_BRUSH_PROPERTIES {
-                opacity: 0.8,
-                transform: Matrix3x2::identity(),
+            let properties = D2D1_BITMAP_PROPERTIES1 {
+                pixelFormat: D2D1_PIXEL_FORMAT {
+                    format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                    alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED,
+                },
+                dpiX: dpi,
+                dpiY: dpi,
+                bitmapOptions: D2D1_BITMAP_OPTIONS_TARGET,
+                colorContext: None,
             };
 
-            unsafe { target.CreateSolidColorBrush(&color, &properties) }
+            unsafe {
+                target.CreateBitmap2(size_u, std::ptr::null(), 0, &properties)
+            }
         }
-
-        Ok(())
     }
 
-    fn create_device_size_resources(&mut self) -> Result<()> {
-        let target = self.target.as_ref().unwrap();
-        let clock = self.create_clock(target)?;
-        self.shadow = create_shadow(target, &clock).ok();
-        self.clock = Some(clock);
-
-        return Ok(());
+    /// Releases every resource at once. Stale resources would only be
+    /// released one by one as they are created again, but the window can't
+    /// have a new swap chain while the buffers of the old one are around.
+    // Anything but a lost device leaves the resources as they are.
+    fn draw_error(&mut self, error: windows::core::Error) -> Error {
+        if is_device_lost(&error) {
+            self.lose_device();
+            return Error::DeviceLost;
+        }
+        Error::Draw(error.to_string())
+    }
 
-        fn create_shadow(
-            target: &ID2D1DeviceContext,
-            clock: &ID2D1Bitmap1,
-        ) -> Result<ID2D1Effect> {
-            unsafe {
-                let shadow = target.CreateEffect(&CLSID_D2D1Shadow)?;
+    fn lose_device(&mut self) {
+        self.resources.device_lost();
+        self.renderer.release();
+        self.shadow.release();
+        self.clock.release();
+        self.buffer.release();
+        self.swapchain.release();
+        self.device.release();
+    }
+}
 
-                shadow.SetInput(0, clock, true);
-                Ok(shadow)
-            }
+#[cfg(windows)]
+impl Surface for SwapChainSurface {
+    fn resize(
+        &mut self,
+        _width: u32,
+        _height: u32,
+    ) -> std::result::Result<(), Error> {
+        let swapchain = match self.resources.current(&mut self.swapchain) {
+            Some(swapchain) => swapchain.clone(),
+            None => return Ok(()),
+        };
+        // The buffers can only be resized once nothing refers to them.
+        if let Some(device) = self.resources.current(&mut self.device) {
+            unsafe { device.context.SetTarget(None) };
         }
+        self.buffer.release();
+        self.clock.release();
+
+        // Zeros keep the buffer count and format and take the size of the
+        // client area.
+        let resized =
+            unsafe { swapchain.ResizeBuffers(0, 0, 0, DXGI_FORMAT_UNKNOWN, 0) };
+        resized.map_err(|error| self.draw_error(error))
     }
 
-    fn create_clock(
-        &self,
-        target: &ID2D1DeviceContext,
-    ) -> Result<ID2D1Bitmap1> {
-        let size_f = unsafe { target.GetSize() };
+    fn draw(
+        &mut self,
+        draw: &mut dyn FnMut(&mut dyn Renderer),
+    ) -> std::result::Result<(), Error> {
+        // The clock layer without a shadow.
+        self.draw_shadowed(0.0, draw, &mut |_| {})
+    }
 
-        let size_u = D2D_SIZE_U {
-            width: (size_f.width * self.dpi / 96.0) as u32,
-            height: (size_f.height * self.dpi / 96.0) as u32,
+    fn is_occluded(&mut self) -> bool {
+        if self.occlusion == 0 {
+            return false;
+        }
+        let occluded = match self.resources.current(&mut self.swapchain) {
+            Some(swapchain) => {
+                present(swapchain, 0, DXGI_PRESENT_TEST) == DXGI_STATUS_OCCLUDED
+            }
+            None => false,
         };
+        if !occluded {
+            unsafe { self.dxfactory.UnregisterOcclusionStatus(self.occlusion) };
+            self.occlusion = 0;
+        }
+        occluded
+    }
+}
 
-        let properties = D2D1_BITMAP_PROPERTIES1 {
-            pixelFormat: D2D1_PIXEL_FORMAT {
-                format: DXGI_FORMAT_B8G8R8A8_UNORM,
-                alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED,
-            },
-            dpiX: self.dpi,
-            dpiY: self.dpi,
-            bitmapOptions: D2D1_BITMAP_OPTIONS_TARGET,
-            colorContext: None,
+#[cfg(windows)]
+impl ShadowSurface for SwapChainSurface {
+    fn draw_shadowed(
+        &mut self,
+        shadow_opacity: f32,
+        clock: &mut dyn FnMut(&mut dyn Renderer),
+        overlay: &mut dyn FnMut(&mut dyn Renderer),
+    ) -> std::result::Result<(), Error> {
+        let Frame {
+            target,
+            swapchain,
+            clock: bitmap,
+            shadow,
+            renderer,
+        } = self.create_device_resources()?;
+
+        // The shadow fades in by making its color more opaque.
+        let color = D2D_VECTOR_4F {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: shadow_opacity,
         };
-
         unsafe {
-            target.CreateBitmap2(size_u, std::ptr::null(), 0, &properties)
+            shadow.SetValue(
+                D2D1_SHADOW_PROP_COLOR.0,
+                D2D1_PROPERTY_TYPE_VECTOR4,
+                &color as *const _ as *const u8,
+                std::mem::size_of::<D2D_VECTOR_4F>() as u32,
+            )
         }
-    }
-
-    fn draw(&self, target: &ID2D1DeviceContext) -> Result<()> {
-        let clock = self.clock.as_ref().unwrap();
-        let shadow = self.shadow.as_ref().unwrap();
+        .map_err(|error| Error::Draw(error.to_string()))?;
 
+        // Drawing our clock in the bitmap
+        let mut previous = None;
         unsafe {
-            self.manager.Update(get_time(self.frequency)?)?;
-
+            target.BeginDraw();
             target.Clear(&D2D1_COLOR_F {
                 r: 1.0,
                 g: 1.0,
@@ -450,11 +510,13 @@ impl Window {
                 a: 1.0,
             });
 
-            let mut previous = None;
             target.GetTarget(&mut previous);
-            target.SetTarget(clock);
+            target.SetTarget(&bitmap);
             target.Clear(std::ptr::null());
-            self.draw_clock()?;
+        }
+        clock(renderer);
+
+        unsafe {
             target.SetTarget(previous);
             target.SetTransform(&Matrix3x2::translation(5.0, 5.0));
 
@@ -472,7 +534,7 @@ impl Window {
             target.SetTransform(&Matrix3x2::identity());
 
             target.DrawImage(
-                clock,
+                bitmap,
                 std::ptr::null(),
                 std::ptr::null(),
                 D2D1_INTERPOLATION_MODE_LINEAR,
@@ -480,221 +542,44 @@ impl Window {
             );
         }
 
-        Ok(())
-    }
-
-    fn draw_clock(&self) -> Result<()> {
-        let target = self.target.as_ref().unwrap();
-        let brush = self.brush.as_ref().unwrap();
-
-        let size = unsafe { target.GetSize() };
+        // Over the clock and its shadow, on the swap chain's bitmap.
+        overlay(renderer);
 
-        let radius = size.width.min(size.height).max(200.0) / 2.0 - 50.0;
-        let translation =
-            Matrix3x2::translation(size.width / 2.0, size.height / 2.0);
-        unsafe { target.SetTransform(&translation) };
-
-        let ellipse = D2D1_ELLIPSE {
-            point: D2D_POINT_2F::default(),
-            radiusX: radius,
-            radiusY: radius,
+        let ended = unsafe {
+            target.EndDraw(std::ptr::null_mut(), std::ptr::null_mut())
         };
-
-        let swing = unsafe {
-            target.DrawEllipse(&ellipse, brush, radius / 20.0, None);
-            self.variable.GetValue()?
+        let presented = match ended {
+            Ok(()) => present(&swapchain, 1, 0),
+            Err(error) => error.code(),
         };
-        let mut angles = Angles::now();
-
-        if swing < 1.0 {
-            if self.angles.second > angles.second {
-                angles.second += 360.0;
-            }
-            if self.angles.minute > angles.minute {
-                angles.minute += 360.0;
-            }
-            if self.angles.hour > angles.hour {
-                angles.hour += 360.0;
-            }
-
-            angles.second *= swing as f32;
-            angles.minute *= swing as f32;
-            angles.hour *= swing as f32;
-        }
-
-        unsafe {
-            target.SetTransform(
-                &(Matrix3x2::rotation(angles.second, 0.0, 0.0) * &translation),
-            );
 
-            target.DrawLine(
-                D2D_POINT_2F::default(),
-                D2D_POINT_2F {
-                    x: 0.0,
-                    y: -(radius * 0.75),
-                },
-                brush,
-                radius / 25.0,
-                &self.style,
-            );
-
-            target.SetTransform(
-                &(Matrix3x2::rotation(angles.minute, 0.0, 0.0) * &translation),
-            );
-
-            target.DrawLine(
-                D2D_POINT_2F::default(),
-                D2D_POINT_2F {
-                    x: 0.0,
-                    y: -(radius * 0.75),
-                },
-                brush,
-                radius / 15.0,
-                &self.style,
-            );
-
-            target.SetTransform(
-                &(Matrix3x2::rotation(angles.hour, 0.0, 0.0) * &translation),
-            );
-
-            target.DrawLine(
-                D2D_POINT_2F::default(),
-                D2D_POINT_2F {
-                    x: 0.0,
-                    y: -(radius * 0.5),
-                },
-                brush,
-                radius / 10.0,
-                &self.style,
-            );
-        }
-
-        Ok(())
-    }
-
-    fn resize_swapchain_bitmap(&mut self) -> Result<()> {
-        if let Some(target) = &self.target {
-            let swapchain = self.swapchain.as_ref().unwrap();
-            unsafe { target.SetTarget(None) };
-
-            if unsafe {
-                swapchain
-                    .ResizeBuffers(0, 0, 0, DXGI_FORMAT_UNKNOWN, 0)
-                    .is_ok()
-            } {
-                create_swapchain_bitmap(swapchain, &target)?;
-                self.create_device_size_resources()?;
-            } else {
-                self.release_device();
-            }
-
-            self.render()?;
-        }
-
-        Ok(())
-    }
-
-    fn message_handler(
-        &mut self,
-        message: u32,
-        wparam: WPARAM,
-        lparam: LPARAM,
-    ) -> LRESULT {
-        unsafe {
-            match message {
-                WM_PAINT => {
-                    let mut ps = PAINTSTRUCT::default();
-                    BeginPaint(self.handle, &mut ps);
-                    self.render().unwrap();
-                    EndPaint(self.handle, &ps);
-                    LRESULT(0)
-                }
-                WM_SIZE => {
-                    if wparam.0 != SIZE_MINIMIZED as usize {
-                        self.resize_swapchain_bitmap().unwrap();
-                    }
-                    LRESULT(0)
-                }
-                WM_DISPLAYCHANGE => {
-                    self.render().unwrap();
-                    LRESULT(0)
-                }
-                WM_USER => {
-                    if self.present(0, DXGI_PRESENT_TEST).is_ok() {
-                        self.dxfactory
-                            .UnregisterOcclusionStatus(self.occlusion);
-                        self.occlusion = 0;
-                        self.visible = true;
-                    }
-                    LRESULT(0)
-                }
-                WM_ACTIVATE => {
-                    self.visible = true; // TODO: unpack !HIWORD(wparam);
-                    LRESULT(0)
-                }
-                WM_DESTROY => {
-                    PostQuitMessage(0);
-                    LRESULT(0)
-                }
-                _ => DefWindowProcA(self.handle, message, wparam, lparam),
-            }
-        }
-    }
-
-    fn present(&self, sync: u32, flags: u32) -> Result<()> {
-        unsafe { self.swapchain.as_ref().unwrap().Present(sync, flags) }
-    }
-
-    fn release_device(&mut self) {
-        self.target = None;
-        self.swapchain = None;
-        self.release_device_resources();
-    }
-
-    fn release_device_resources(&mut self) {
-        self.brush = None;
-        self.clock = None;
-        self.shadow = None;
-    }
-
-    extern "system" fn wndproc(
-        window: HWND,
-        message: u32,
-        wparam: WPARAM,
-        lparam: LPARAM,
-    ) -> LRESULT {
-        unsafe {
-            if message == WM_NCCREATE {
-                let cs = lparam.0 as *const CREATESTRUCTA;
-                let this = (*cs).lpCreateParams as *mut Self;
-                (*this).handle = window;
-
-                // Associate the initial usewr data state with the window
-                // instance
-                SetWindowLong(window, GWLP_USERDATA, this as _);
-            } else {
-                // retrieve the current user data associated with this window
-                // instance.
-                let this = GetWindowLong(window, GWLP_USERDATA) as *mut Self;
-
-                // Make sure there is a pointer to the user data.
-                // The user data structure handles the messages.
-                if !this.is_null() {
-                    return (*this).message_handler(message, wparam, lparam);
+        if presented == DXGI_STATUS_OCCLUDED {
+            // DXGI sends the window the occlusion message once it can be
+            // seen again.
+            if self.occlusion == 0 {
+                self.occlusion = unsafe {
+                    self.dxfactory.RegisterOcclusionStatusWindow(
+                        self.window,
+                        WM_USER + OCCLUSION_MESSAGE,
+                    )
                 }
+                .map_err(|error| Error::Draw(error.to_string()))?;
             }
-
-            DefWindowProcA(window, message, wparam, lparam)
+            return Ok(());
         }
+        // Only a lost device is recovered from by itself, by creating every
+        // resource again for the next frame.
+        presented.ok().map_err(|error| self.draw_error(error))
     }
 }
 
 // Helper functions
 //
+#[cfg(windows)]
 fn create_swapchain_bitmap(
     swapchain: &IDXGISwapChain1,
     target: &ID2D1DeviceContext,
-) -> Result<()> {
+) -> Result<ID2D1Bitmap1> {
     let surface: IDXGISurface = unsafe { swapchain.GetBuffer(0)? };
 
     let props = D2D1_BITMAP_PROPERTIES1 {
@@ -709,81 +594,40 @@ fn create_swapchain_bitmap(
         colorContext: None,
     };
 
-    unsafe {
-        let bitmap = target.CreateBitmapFromDxgiSurface(&surface, &props)?;
-        target.SetTarget(bitmap);
-    };
-
-    Ok(())
+    unsafe { target.CreateBitmapFromDxgiSurface(&surface, &props) }
 }
 
-// Retrieves the current value of the performance counter, which is a
-// high resolution (<1us) time stamp that can be used for
-// time-interval measurements.
-fn get_time(frequency: i64) -> Result<f64> {
+/// Presents the back buffer, returning the status as well as errors.
+///
+/// The bindings turn every success code into `Ok`, including
+/// `DXGI_STATUS_OCCLUDED` for a window that can't be seen, so this calls
+/// `Present` through the vtable instead.
+#[cfg(windows)]
+fn present(swapchain: &IDXGISwapChain1, sync: u32, flags: u32) -> HRESULT {
     unsafe {
-        let mut time = 0;
-        QueryPerformanceCounter(&mut time).ok()?;
-        Ok(time as f64 / frequency as f64)
+        (Interface::vtable(swapchain).8)(
+            std::mem::transmute_copy(swapchain),
+            sync,
+            flags,
+        )
     }
 }
 
-// Angles struct
-//
-
-#[derive(Default)]
-struct Angles {
-    second: f32,
-    minute: f32,
-    hour: f32,
-}
-
-impl Angles {
-    fn now() -> Self {
-        let mut time = Default::default();
-        unsafe { GetLocalTime(&mut time) };
-
-        let second =
-            (time.wSecond as f32 + time.wMilliseconds as f32 / 1000.0) * 6.0;
-        let minute = time.wMinute as f32 * 6.0 + second / 60.0;
-        let hour = (time.wHour % 12) as f32 * 30.0 + minute / 12.0;
-
-        Self {
-            second,
-            minute,
-            hour,
-        }
-    }
-}
-
-#[allow(non_snake_case)]
-#[cfg(target_pointer_width = "32")]
-unsafe fn SetWindowLong(
-    window: HWND,
-    index: WINDOW_LONG_PTR_INDEX,
-    value: isize,
-) -> isize {
-    SetWindowLongA(window, index, value as _) as _
-}
-
-#[allow(non_snake_case)]
-#[cfg(target_pointer_width = "64")]
-unsafe fn SetWindowLong(
-    window: HWND,
-    index: WINDOW_LONG_PTR_INDEX,
-    value: isize,
-) -> isize {
-    SetWindowLongPtrA(window, index, value)
-}
-
-#[allow(non_snake_case)]
-#[cfg(target_pointer_width = "32")]
-unsafe fn GetWindowLong(window: HWND, index: WINDOW_LONG_PTR_INDEX) -> isize {
-    GetWindowLongA(window, index) as _
+/// Whether the device is gone, so that everything created with it has to
+/// be created again. Any other error is a bug.
+#[cfg(windows)]
+fn is_device_lost(error: &windows::core::Error) -> bool {
+    [
+        DXGI_ERROR_DEVICE_REMOVED,
+        DXGI_ERROR_DEVICE_RESET,
+        D2DERR_RECREATE_TARGET,
+    ]
+    .contains(&error.code())
 }
 
-#[allow(non_snake_case)]
-#[cfg(target_pointer_width = "64")]
-unsafe fn GetWindowLong(window: HWND, index: WINDOW_LONG_PTR_INDEX) -> isize {
-    GetWindowLongPtrA(window, index)
+#[cfg(windows)]
+fn creation_error(
+    kind: ResourceKind,
+) -> impl FnOnce(windows::core::Error) -> Error {
+    move |error| Error::ResourceCreation(kind, error.to_string())
 }
@@ -0,0 +1,383 @@
+use clock_core::{
+    animation::{Manager, Storyboard},
+    interpolate_angle, ClockMode, FrameScheduler, FrameStats, HandAngles,
+    Pacing, Sweep, TimeSource,
+};
+use renderer::{
+    draw_frame_stats, report_frame_stats, Error, FaultInjectingSurface,
+    RecordingSurface, Renderer, Surface, WindowSurface,
+};
+use win32_common::{
+    FrameHandler, Message, SizeKind, Wait, WindowHandler, WindowHost,
+};
+
+use crate::{draw_clock_scaled, Intro};
+
+/// How the clock is drawn, and so how often it needs drawing once the intro
+/// is over.
+pub const MODE: ClockMode = ClockMode::Smooth;
+pub const FRAME_RATE: f64 = 60.0;
+/// The window is sent `WM_USER` plus this once it is no longer occluded,
+/// see [`ShadowSurface`].
+pub const OCCLUSION_MESSAGE: u32 = 0;
+
+// Where the intro swings the hands from, all pointing at twelve o'clock.
+const INTRO_ANGLES: HandAngles = HandAngles {
+    hour: 0.0,
+    minute: 0.0,
+    second: 0.0,
+};
+
+/// A surface that draws the clock with a drop shadow.
+///
+/// Presenting to a surface that other windows cover completely reports it
+/// as occluded. The surface then has the window sent
+/// [`OCCLUSION_MESSAGE`] once that is no longer the case.
+pub trait ShadowSurface: Surface {
+    /// Draws `clock` with a shadow of `shadow_opacity` under it, then
+    /// `overlay` over both, and presents the frame.
+    fn draw_shadowed(
+        &mut self,
+        shadow_opacity: f32,
+        clock: &mut dyn FnMut(&mut dyn Renderer),
+        overlay: &mut dyn FnMut(&mut dyn Renderer),
+    ) -> Result<(), Error>;
+}
+
+impl ShadowSurface for RecordingSurface {
+    /// Records the clock and then the overlay as one frame. The shadow is an
+    /// effect applied to the clock, which a recording can't show.
+    fn draw_shadowed(
+        &mut self,
+        _shadow_opacity: f32,
+        clock: &mut dyn FnMut(&mut dyn Renderer),
+        overlay: &mut dyn FnMut(&mut dyn Renderer),
+    ) -> Result<(), Error> {
+        self.draw(&mut |renderer: &mut dyn Renderer| {
+            clock(&mut *renderer);
+            overlay(renderer);
+        })
+    }
+}
+
+impl ShadowSurface for FaultInjectingSurface {
+    /// Draws the clock and then the overlay as one frame, like a
+    /// [`RecordingSurface`].
+    fn draw_shadowed(
+        &mut self,
+        _shadow_opacity: f32,
+        clock: &mut dyn FnMut(&mut dyn Renderer),
+        overlay: &mut dyn FnMut(&mut dyn Renderer),
+    ) -> Result<(), Error> {
+        self.draw(&mut |renderer: &mut dyn Renderer| {
+            clock(&mut *renderer);
+            overlay(renderer);
+        })
+    }
+}
+
+/// The window of this module, drawing the clock to a surface that is
+/// created with the window.
+///
+/// The window only draws while it is visible, which it isn't before it is
+/// first activated, while it is minimized and while it is occluded. A
+/// window that fails to create its surface or to draw tries again later,
+/// see [`WindowSurface`].
+pub struct ClockWindow<S> {
+    surface: WindowSurface<S>,
+    manager: Manager,
+    intro: Intro,
+    // Scheduled once the window is created.
+    storyboard: Option<Storyboard>,
+    scheduler: FrameScheduler,
+    // When the current frame started and how many frames were missed before
+    // it, for the statistics.
+    frame: (f64, u32),
+    stats: Option<FrameStats>,
+    visible: bool,
+    time_source: Box<dyn TimeSource>,
+}
+
+impl<S: ShadowSurface> ClockWindow<S> {
+    /// Shows the time of `time_source`, drawing to the surface that
+    /// `create_surface` creates on `WM_CREATE`.
+    pub fn new<F>(time_source: Box<dyn TimeSource>, create_surface: F) -> Self
+    where
+        F: FnMut(&dyn WindowHost) -> Result<S, Error> + 'static,
+    {
+        // The dial springs in, the hands swing into place one after the
+        // other and the shadow fades in.
+        let manager = Manager::new();
+        let (intro, storyboard) = Intro::new(&manager);
+
+        ClockWindow {
+            surface: WindowSurface::new(create_surface),
+            manager,
+            intro,
+            storyboard: Some(storyboard),
+            scheduler: FrameScheduler::new(FRAME_RATE),
+            frame: (0.0, 0),
+            stats: None,
+            visible: false,
+            time_source,
+        }
+    }
+
+    /// Collects frame statistics in `stats`, draws them over the clock and
+    /// reports them when the window is destroyed.
+    pub fn with_stats(mut self, stats: FrameStats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    pub fn surface(&self) -> Option<&S> {
+        self.surface.get()
+    }
+
+    pub fn surface_mut(&mut self) -> Option<&mut S> {
+        self.surface.get_mut()
+    }
+
+    pub fn stats(&self) -> Option<&FrameStats> {
+        self.stats.as_ref()
+    }
+
+    pub fn intro(&self) -> &Intro {
+        &self.intro
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Why the last frame wasn't drawn, unless the device was lost.
+    pub fn error(&self) -> Option<&Error> {
+        self.surface.error()
+    }
+
+    /// Draws the next frame once it is due. Until then, returns how many
+    /// seconds are left.
+    pub fn tick(&mut self, host: &mut dyn WindowHost) -> f64 {
+        if let Some(wait) = self.surface.retry_in(host) {
+            return wait;
+        }
+        let wait = self
+            .scheduler
+            .time_until_next_frame(host.now(), self.time_source.now());
+        if wait == 0.0 {
+            self.render(host);
+        }
+        wait
+    }
+
+    fn render(&mut self, host: &mut dyn WindowHost) {
+        if self.surface.create(host).is_none() {
+            return;
+        }
+
+        // The animation moves on in fixed steps. Once the intro is over a
+        // clock whose hands only tick needs drawing once a second.
+        let now = host.now();
+        let frame = self.scheduler.begin_frame(now, self.time_source.now());
+        self.frame = (now, frame.missed_frames);
+        for time in frame.update_times() {
+            self.manager.update(time);
+        }
+        if !self.manager.is_busy() {
+            self.scheduler.set_pacing(Pacing::for_mode(MODE));
+        }
+
+        let angles = self.hand_angles();
+        let scale = self.intro.dial.value() as f32;
+        let shadow = self.intro.shadow.value() as f32;
+        let ClockWindow { surface, stats, .. } = self;
+        let target = match surface.get_mut() {
+            Some(target) => target,
+            None => return,
+        };
+
+        let mut drawn = now;
+        let presented = target.draw_shadowed(
+            shadow,
+            &mut |mut renderer: &mut dyn Renderer| {
+                draw_clock_scaled(&mut renderer, angles, scale)
+            },
+            // Over the clock and its shadow.
+            &mut |mut renderer: &mut dyn Renderer| {
+                if let Some(stats) = stats.as_ref() {
+                    draw_frame_stats(&mut renderer, stats, 1.0 / FRAME_RATE);
+                }
+                drawn = host.now();
+            },
+        );
+
+        if let Some(stats) = stats {
+            let (started, missed_frames) = self.frame;
+            stats.record(started, host.now() - drawn, missed_frames);
+        }
+        // Nothing is drawn until the surface has the window told that it is
+        // no longer occluded.
+        if target.is_occluded() {
+            self.visible = false;
+        }
+        surface.presented(host, presented);
+    }
+
+    fn hand_angles(&self) -> HandAngles {
+        let mut angles = MODE.angles(self.time_source.now());
+
+        let hands = [
+            (&mut angles.second, INTRO_ANGLES.second, &self.intro.second),
+            (&mut angles.minute, INTRO_ANGLES.minute, &self.intro.minute),
+            (&mut angles.hour, INTRO_ANGLES.hour, &self.intro.hour),
+        ];
+        // Every hand swings clockwise from where the intro starts it to
+        // where it belongs, however far round that is.
+        for (angle, start, swing) in hands {
+            *angle = interpolate_angle(
+                start,
+                *angle,
+                swing.value() as f32,
+                Sweep::Clockwise,
+            );
+        }
+        angles
+    }
+}
+
+impl<S: ShadowSurface> WindowHandler for ClockWindow<S> {
+    fn handle(
+        &mut self,
+        host: &mut dyn WindowHost,
+        message: Message,
+    ) -> Option<isize> {
+        match message {
+            Message::Create => {
+                self.surface.create(host);
+                let storyboard = self.storyboard.take();
+                let scheduled = storyboard.map_or(Ok(()), |storyboard| {
+                    self.manager.schedule(storyboard, host.now())
+                });
+                if let Err(error) = scheduled {
+                    // The clock is shown as the intro would leave it.
+                    self.intro = Intro::finished(&self.manager);
+                    let error = Error::Animation(error.to_string());
+                    self.surface.fail(host, error);
+                }
+                Some(0)
+            }
+            Message::Paint | Message::DisplayChange { .. } => {
+                self.render(host);
+                Some(0)
+            }
+            Message::Size { kind, .. } => {
+                if kind != SizeKind::Minimized {
+                    // A surface that lost its device while resizing is
+                    // created again at the new size.
+                    self.surface.resize(host);
+                    self.render(host);
+                }
+                Some(0)
+            }
+            // Sent once the window is no longer occluded, see
+            // `ShadowSurface`.
+            Message::User(OCCLUSION_MESSAGE) => {
+                if let Some(surface) = self.surface.get_mut() {
+                    self.visible = !surface.is_occluded();
+                }
+                Some(0)
+            }
+            // A minimized window isn't visible, even while it is active.
+            Message::Activate { minimized, .. } => {
+                self.visible = !minimized;
+                Some(0)
+            }
+            Message::Destroy => {
+                self.surface.release();
+                if let Some(stats) = &self.stats {
+                    report_frame_stats(stats);
+                }
+                host.quit(0);
+                Some(0)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<S: ShadowSurface> FrameHandler for ClockWindow<S> {
+    /// Draws the next frame once it is due, see [`tick`]. A window that
+    /// isn't visible waits for the message that shows it again.
+    ///
+    /// [`tick`]: ClockWindow::tick
+    fn frame(&mut self, host: &mut dyn WindowHost) -> Wait {
+        if self.visible {
+            Wait::For(self.tick(host))
+        } else {
+            Wait::ForMessage
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock_core::{ClockTime, FixedTime};
+    use win32_common::replay::VirtualWindow;
+
+    fn window() -> ClockWindow<RecordingSurface> {
+        let time = Box::new(FixedTime(ClockTime::hms(10, 8, 30)));
+        ClockWindow::new(time, |host: &dyn WindowHost| {
+            let (width, height) = host.client_size();
+            Ok(RecordingSurface::new(width, height))
+        })
+    }
+
+    #[test]
+    fn the_intro_starts_with_the_window() {
+        let mut window = window();
+        let mut host = VirtualWindow {
+            time: 10.0,
+            ..VirtualWindow::default()
+        };
+        window.handle(&mut host, Message::Create);
+
+        // Half a second in the dial springs, and the hands are yet to move.
+        host.time = 10.5;
+        window.handle(&mut host, Message::Paint);
+        assert!(window.intro().dial.value() > 0.0);
+        assert_eq!(window.intro().second.value(), 0.0);
+    }
+
+    #[test]
+    fn frames_are_only_drawn_once_due() {
+        let mut window = window();
+        let mut host = VirtualWindow::default();
+        window.handle(&mut host, Message::Create);
+
+        assert_eq!(window.tick(&mut host), 0.0);
+        host.time = 0.5 / FRAME_RATE;
+        assert!(window.tick(&mut host) > 0.0);
+        assert_eq!(window.surface().unwrap().frames().len(), 1);
+    }
+
+    #[test]
+    fn the_stats_are_drawn_over_the_clock() {
+        let mut window =
+            window().with_stats(FrameStats::new(10 * FRAME_RATE as usize));
+        let mut host = VirtualWindow {
+            client_size: (400, 400),
+            ..VirtualWindow::default()
+        };
+        window.handle(&mut host, Message::Create);
+        for frame in 0..3 {
+            host.time = frame as f64 / FRAME_RATE;
+            window.handle(&mut host, Message::Paint);
+        }
+
+        // The first frame only starts the clock.
+        assert_eq!(window.stats().unwrap().frames(), 2);
+        let frames = window.surface().unwrap().frames();
+        assert!(frames[2].len() > frames[0].len());
+    }
+}
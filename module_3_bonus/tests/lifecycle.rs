@@ -0,0 +1,156 @@
+//! The bonus clock window playing its intro, pausing while minimized or
+//! occluded and recovering from failures, run through the message loop of
+//! `main` with its frames recorded instead of presented to a swap chain.
+
+use clock_core::{ClockTime, FixedTime};
+use module_3_bonus::{
+    draw_clock, ClockWindow, FRAME_RATE, MODE, OCCLUSION_MESSAGE,
+};
+use renderer::{
+    FaultInjectingSurface, RecordingRenderer, RecordingSurface, ResourceKind,
+    Size, RETRY_DELAY,
+};
+use win32_common::{HeadlessHost, Message, WindowHost};
+
+fn time() -> ClockTime {
+    ClockTime::hms(10, 8, 30)
+}
+
+fn host() -> HeadlessHost<ClockWindow<RecordingSurface>> {
+    let window = ClockWindow::new(
+        Box::new(FixedTime(time())),
+        |host: &dyn WindowHost| {
+            let (width, height) = host.client_size();
+            Ok(RecordingSurface::new(width, height))
+        },
+    );
+    HeadlessHost::new(window).with_client_size(400, 300)
+}
+
+fn frames(host: &HeadlessHost<ClockWindow<RecordingSurface>>) -> usize {
+    host.handler().surface().unwrap().frames().len()
+}
+
+#[test]
+fn the_intro_plays_and_ends_with_the_clock() {
+    let mut host = host();
+    assert!(!host.handler().is_visible());
+    assert!(host.create());
+    assert!(host.handler().is_visible());
+
+    host.run_for(5.0);
+
+    let mut expected = RecordingRenderer::new(Size::new(400.0, 300.0));
+    draw_clock(&mut expected, MODE.angles(time()));
+    let surface = host.handler().surface().unwrap();
+    assert_eq!(surface.last_frame().unwrap(), expected.commands());
+    // Drawn at the frame rate while animating.
+    assert!(surface.frames().len() as f64 > 4.0 * FRAME_RATE);
+}
+
+#[test]
+fn nothing_is_drawn_while_minimized() {
+    let mut host = host();
+    host.create();
+    host.minimize();
+    assert!(!host.handler().is_visible());
+
+    let drawn = frames(&host);
+    host.run_for(1.0);
+    assert_eq!(frames(&host), drawn);
+
+    // Restoring resizes the surface and draws right away.
+    host.restore();
+    assert!(host.handler().is_visible());
+    assert!(frames(&host) > drawn);
+}
+
+#[test]
+fn drawing_resumes_once_no_longer_occluded() {
+    let mut host = host();
+    host.create();
+    host.run_for(0.5);
+
+    // Presenting finds the window covered.
+    host.handler_mut().surface_mut().unwrap().set_occluded(true);
+    host.run_for(0.1);
+    assert!(!host.handler().is_visible());
+    let drawn = frames(&host);
+    host.run_for(1.0);
+    assert_eq!(frames(&host), drawn);
+
+    // Still covered when told otherwise, then uncovered for real.
+    host.send(Message::User(OCCLUSION_MESSAGE));
+    assert!(!host.handler().is_visible());
+    host.handler_mut()
+        .surface_mut()
+        .unwrap()
+        .set_occluded(false);
+    host.send(Message::User(OCCLUSION_MESSAGE));
+    assert!(host.handler().is_visible());
+    host.run_for(0.1);
+    assert!(frames(&host) > drawn);
+}
+
+#[test]
+fn closing_releases_the_surface_and_quits() {
+    let mut host = host();
+    host.create();
+    host.resize(800, 600);
+    assert_eq!(
+        host.handler().surface().unwrap().size(),
+        Size::new(800.0, 600.0)
+    );
+    host.close();
+
+    assert!(host.handler().surface().is_none());
+    assert_eq!(host.exit_code(), Some(0));
+}
+
+#[test]
+fn the_intro_carries_on_after_losing_the_device() {
+    let window = ClockWindow::new(
+        Box::new(FixedTime(time())),
+        |host: &dyn WindowHost| {
+            let (width, height) = host.client_size();
+            let surface = FaultInjectingSurface::new(width, height);
+            Ok(surface.with_device_lost_at([0, 10, 11]))
+        },
+    );
+    let mut host = HeadlessHost::new(window).with_client_size(400, 300);
+    host.create();
+    host.run_for(5.0);
+
+    let mut expected = RecordingRenderer::new(Size::new(400.0, 300.0));
+    draw_clock(&mut expected, MODE.angles(time()));
+    let surface = host.handler().surface().unwrap();
+    assert_eq!(surface.last_frame().unwrap(), expected.commands());
+    let resources = surface.resources();
+    assert_eq!(resources.devices_lost(), 3);
+    assert_eq!(resources.created(ResourceKind::Target), 4);
+}
+
+#[test]
+fn drawing_is_tried_again_a_while_after_failing() {
+    let window = ClockWindow::new(
+        Box::new(FixedTime(time())),
+        |host: &dyn WindowHost| {
+            let (width, height) = host.client_size();
+            let surface = FaultInjectingSurface::new(width, height);
+            Ok(surface.with_creation_failing_at([0, 1]))
+        },
+    );
+    let mut host = HeadlessHost::new(window).with_client_size(400, 300);
+    // Resizing and painting the new window both fail.
+    host.create();
+    assert!(host.handler().error().is_some());
+
+    host.run_for(RETRY_DELAY / 2.0);
+    let surface = host.handler().surface().unwrap();
+    assert_eq!(surface.frames_drawn(), 2);
+    assert!(surface.frames().is_empty());
+
+    host.run_for(RETRY_DELAY);
+    assert!(host.handler().error().is_none());
+    assert!(!host.handler().surface().unwrap().frames().is_empty());
+}
@@ -0,0 +1,173 @@
+use clock_core::{Matrix, Point};
+use windows::{
+    core::Result,
+    Foundation::Numerics::Matrix3x2,
+    Win32::Graphics::Direct2D::{
+        Common::{D2D1_COLOR_F, D2D_POINT_2F},
+        ID2D1Factory, ID2D1RenderTarget, ID2D1SolidColorBrush,
+        ID2D1StrokeStyle, D2D1_BRUSH_PROPERTIES, D2D1_CAP_STYLE,
+        D2D1_CAP_STYLE_FLAT, D2D1_CAP_STYLE_ROUND, D2D1_CAP_STYLE_SQUARE,
+        D2D1_CAP_STYLE_TRIANGLE, D2D1_ELLIPSE, D2D1_STROKE_STYLE_PROPERTIES,
+    },
+};
+
+use crate::{
+    primitives::{CapStyle, Color, Ellipse, Size, StrokeStyle},
+    renderer::Renderer,
+};
+
+/// Draws to a Direct2D render target, e.g. an `ID2D1HwndRenderTarget` or an
+/// `ID2D1DeviceContext`.
+///
+/// The renderer owns the one brush it draws with and the stroke styles it
+/// created, so it is a device dependent resource: drop it together with the
+/// render target when the device is lost. Calling `BeginDraw` and `EndDraw`
+/// remains up to the owner of the target.
+pub struct Direct2DRenderer {
+    target: ID2D1RenderTarget,
+    brush: ID2D1SolidColorBrush,
+    stroke_style: StrokeStyle,
+    // Stroke styles are device independent factory resources, so the ones
+    // in use are created once and kept around.
+    stroke_styles: Vec<(StrokeStyle, ID2D1StrokeStyle)>,
+}
+
+impl Direct2DRenderer {
+    pub fn new(target: impl Into<ID2D1RenderTarget>) -> Result<Self> {
+        let target = target.into();
+        let brush = unsafe {
+            target.CreateSolidColorBrush(
+                &Color::BLACK.into(),
+                &D2D1_BRUSH_PROPERTIES {
+                    opacity: 1.0,
+                    transform: Matrix3x2::identity(),
+                },
+            )?
+        };
+
+        Ok(Direct2DRenderer {
+            target,
+            brush,
+            stroke_style: StrokeStyle::default(),
+            stroke_styles: Vec::new(),
+        })
+    }
+
+    pub fn target(&self) -> &ID2D1RenderTarget {
+        &self.target
+    }
+
+    // `None` draws with Direct2D's default style, which is also ours.
+    fn current_stroke_style(&mut self) -> Option<ID2D1StrokeStyle> {
+        if self.stroke_style == StrokeStyle::default() {
+            return None;
+        }
+        if let Some((_, created)) = self
+            .stroke_styles
+            .iter()
+            .find(|(style, _)| *style == self.stroke_style)
+        {
+            return Some(created.clone());
+        }
+
+        let properties = D2D1_STROKE_STYLE_PROPERTIES {
+            startCap: self.stroke_style.start_cap.into(),
+            endCap: self.stroke_style.end_cap.into(),
+            ..Default::default()
+        };
+        let created = unsafe {
+            let mut factory: Option<ID2D1Factory> = None;
+            self.target.GetFactory(&mut factory);
+            factory?
+                .CreateStrokeStyle(&properties, std::ptr::null(), 0)
+                .ok()?
+        };
+        self.stroke_styles
+            .push((self.stroke_style, created.clone()));
+        Some(created)
+    }
+}
+
+impl Renderer for Direct2DRenderer {
+    fn size(&self) -> Size {
+        let size = unsafe { self.target.GetSize() };
+        Size::new(size.width, size.height)
+    }
+
+    fn clear(&mut self, color: Color) {
+        unsafe { self.target.Clear(&color.into()) };
+    }
+
+    fn set_brush_color(&mut self, color: Color) {
+        unsafe { self.brush.SetColor(&color.into()) };
+    }
+
+    fn set_stroke_style(&mut self, style: StrokeStyle) {
+        self.stroke_style = style;
+    }
+
+    fn set_transform(&mut self, transform: Matrix) {
+        unsafe { self.target.SetTransform(&transform.into()) };
+    }
+
+    fn fill_ellipse(&mut self, ellipse: Ellipse) {
+        unsafe { self.target.FillEllipse(&ellipse.into(), &self.brush) };
+    }
+
+    fn draw_ellipse(&mut self, ellipse: Ellipse, stroke_width: f32) {
+        let style = self.current_stroke_style();
+        unsafe {
+            self.target.DrawEllipse(
+                &ellipse.into(),
+                &self.brush,
+                stroke_width,
+                style,
+            )
+        };
+    }
+
+    fn draw_line(&mut self, from: Point, to: Point, stroke_width: f32) {
+        let style = self.current_stroke_style();
+        unsafe {
+            self.target.DrawLine(
+                D2D_POINT_2F::from(from),
+                D2D_POINT_2F::from(to),
+                &self.brush,
+                stroke_width,
+                style,
+            )
+        };
+    }
+}
+
+impl From<Color> for D2D1_COLOR_F {
+    fn from(color: Color) -> Self {
+        D2D1_COLOR_F {
+            r: color.r,
+            g: color.g,
+            b: color.b,
+            a: color.a,
+        }
+    }
+}
+
+impl From<Ellipse> for D2D1_ELLIPSE {
+    fn from(ellipse: Ellipse) -> Self {
+        D2D1_ELLIPSE {
+            point: ellipse.center.into(),
+            radiusX: ellipse.radius_x,
+            radiusY: ellipse.radius_y,
+        }
+    }
+}
+
+impl From<CapStyle> for D2D1_CAP_STYLE {
+    fn from(cap: CapStyle) -> Self {
+        match cap {
+            CapStyle::Flat => D2D1_CAP_STYLE_FLAT,
+            CapStyle::Square => D2D1_CAP_STYLE_SQUARE,
+            CapStyle::Round => D2D1_CAP_STYLE_ROUND,
+            CapStyle::Triangle => D2D1_CAP_STYLE_TRIANGLE,
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::{fmt, process::ExitCode};
+
+use crate::resources::ResourceKind;
+
+/// Why a window couldn't be set up, or a frame couldn't be drawn.
+///
+/// Only [`Error::DeviceLost`] is expected to go away by itself. A window
+/// that runs into any other error while drawing keeps it, and its frame loop
+/// tries again after [`RETRY_DELAY`], or sooner if the window is painted.
+///
+/// [`RETRY_DELAY`]: crate::RETRY_DELAY
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The device went away, like Direct2D's `D2DERR_RECREATE_TARGET`, so
+    /// the frame wasn't presented. The surface released its device
+    /// dependent resources and creates them again for the next frame.
+    DeviceLost,
+    /// A device dependent resource couldn't be created.
+    ResourceCreation(ResourceKind, String),
+    /// Drawing or presenting a frame failed with the device still there.
+    Draw(String),
+    /// The window couldn't be created.
+    WindowCreation(String),
+    /// Waiting until the next frame is due failed.
+    Timer(String),
+    /// COM couldn't be initialized for the thread.
+    ComInit(String),
+    /// An animation couldn't be scheduled.
+    Animation(String),
+    /// The command line couldn't be parsed, with how to use it.
+    Usage(String),
+}
+
+impl Error {
+    /// Whether this is [`Error::DeviceLost`], which the surface recovers
+    /// from by itself when the next frame is drawn.
+    pub fn is_device_lost(&self) -> bool {
+        *self == Error::DeviceLost
+    }
+
+    /// How a program that failed with this error exits: `2` for
+    /// [`Error::Usage`], like other command line tools, and `1` otherwise.
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            Error::Usage(_) => ExitCode::from(2),
+            _ => ExitCode::FAILURE,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DeviceLost => write!(f, "the graphics device was lost"),
+            Error::ResourceCreation(kind, reason) => {
+                write!(f, "failed to create a {}: {}", kind, reason)
+            }
+            Error::Draw(reason) => write!(f, "failed to draw: {}", reason),
+            Error::WindowCreation(reason) => {
+                write!(f, "failed to create the window: {}", reason)
+            }
+            Error::Timer(reason) => {
+                write!(f, "failed to wait for the next frame: {}", reason)
+            }
+            Error::ComInit(reason) => {
+                write!(f, "failed to initialize COM: {}", reason)
+            }
+            Error::Animation(reason) => {
+                write!(f, "failed to schedule the animation: {}", reason)
+            }
+            Error::Usage(usage) => write!(f, "{}", usage),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_name_what_failed() {
+        let error = Error::ResourceCreation(
+            ResourceKind::SwapChain,
+            "out of memory".to_string(),
+        );
+        assert_eq!(
+            error.to_string(),
+            "failed to create a swap chain: out of memory"
+        );
+        assert!(!error.is_device_lost());
+        assert!(Error::DeviceLost.is_device_lost());
+    }
+}
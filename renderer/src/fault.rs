@@ -0,0 +1,184 @@
+use crate::{
+    primitives::Size,
+    recording::{Command, RecordingRenderer},
+    renderer::Renderer,
+    resources::{DeviceResource, DeviceResources, ResourceKind},
+    surface::Surface,
+    Error,
+};
+
+/// A surface on a simulated device that is lost while drawing chosen
+/// frames, or fails to create its target for them, for testing how windows
+/// recover without a GPU.
+///
+/// Its target and brush are device resources like those of a Direct2D
+/// surface, created when drawing and again after the device was lost. The
+/// frames that were presented are recorded like a [`RecordingSurface`]
+/// does.
+///
+/// [`RecordingSurface`]: crate::RecordingSurface
+#[derive(Debug)]
+pub struct FaultInjectingSurface {
+    size: Size,
+    resources: DeviceResources,
+    target: DeviceResource<()>,
+    brush: DeviceResource<()>,
+    // The frames to lose the device at, counting every frame drawn.
+    lose_device_at: Vec<usize>,
+    fail_creation_at: Vec<usize>,
+    drawn: usize,
+    frames: Vec<Vec<Command>>,
+}
+
+impl FaultInjectingSurface {
+    pub fn new(width: u32, height: u32) -> Self {
+        FaultInjectingSurface {
+            size: Size::new(width as f32, height as f32),
+            resources: DeviceResources::new(),
+            target: DeviceResource::new(ResourceKind::Target),
+            brush: DeviceResource::new(ResourceKind::Brush),
+            lose_device_at: Vec::new(),
+            fail_creation_at: Vec::new(),
+            drawn: 0,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Loses the device while drawing each of `frames`, where `0` is the
+    /// first frame drawn. Lost frames are drawn but not presented.
+    pub fn with_device_lost_at(
+        mut self,
+        frames: impl IntoIterator<Item = usize>,
+    ) -> Self {
+        self.lose_device_at.extend(frames);
+        self
+    }
+
+    /// Fails to create the target, if it has to be created, for each of
+    /// `frames`. Nothing is drawn for those frames.
+    pub fn with_creation_failing_at(
+        mut self,
+        frames: impl IntoIterator<Item = usize>,
+    ) -> Self {
+        self.fail_creation_at.extend(frames);
+        self
+    }
+
+    pub fn resources(&self) -> &DeviceResources {
+        &self.resources
+    }
+
+    /// How many frames were drawn, presented or not.
+    pub fn frames_drawn(&self) -> usize {
+        self.drawn
+    }
+
+    /// The frames presented so far, oldest first.
+    pub fn frames(&self) -> &[Vec<Command>] {
+        &self.frames
+    }
+
+    pub fn last_frame(&self) -> Option<&[Command]> {
+        self.frames.last().map(Vec::as_slice)
+    }
+}
+
+impl Surface for FaultInjectingSurface {
+    fn resize(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        self.size = Size::new(width as f32, height as f32);
+        Ok(())
+    }
+
+    fn draw(
+        &mut self,
+        draw: &mut dyn FnMut(&mut dyn Renderer),
+    ) -> Result<(), Error> {
+        let frame = self.drawn;
+        self.drawn += 1;
+
+        let fails = self.fail_creation_at.contains(&frame);
+        self.resources.get(&mut self.target, || {
+            if fails {
+                let reason = format!("failing frame {} on purpose", frame);
+                return Err(Error::ResourceCreation(
+                    ResourceKind::Target,
+                    reason,
+                ));
+            }
+            Ok(())
+        })?;
+        self.resources.get(&mut self.brush, || Ok::<_, Error>(()))?;
+
+        let mut renderer = RecordingRenderer::new(self.size);
+        draw(&mut renderer);
+
+        if self.lose_device_at.contains(&frame) {
+            self.resources.device_lost();
+            return Err(Error::DeviceLost);
+        }
+        self.frames.push(renderer.take_commands());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+
+    #[test]
+    fn lost_frames_are_not_presented() {
+        let mut surface =
+            FaultInjectingSurface::new(100, 100).with_device_lost_at([1, 2]);
+        let mut red = 0.0;
+        let mut clear = |renderer: &mut dyn Renderer| {
+            red += 0.25;
+            renderer.clear(Color::rgb(red, 0.0, 0.0));
+        };
+
+        let presented: Vec<_> =
+            (0..4).map(|_| surface.draw(&mut clear).is_ok()).collect();
+
+        assert_eq!(presented, [true, false, false, true]);
+        assert_eq!(surface.frames_drawn(), 4);
+        assert_eq!(
+            surface.frames(),
+            [
+                vec![Command::Clear(Color::rgb(0.25, 0.0, 0.0))],
+                vec![Command::Clear(Color::rgb(1.0, 0.0, 0.0))],
+            ]
+        );
+    }
+
+    #[test]
+    fn resources_are_created_again_for_every_device() {
+        let mut surface =
+            FaultInjectingSurface::new(100, 100).with_device_lost_at([0, 3]);
+        for _ in 0..5 {
+            surface.draw(&mut |_| {}).ok();
+        }
+
+        let resources = surface.resources();
+        assert_eq!(resources.devices_lost(), 2);
+        assert_eq!(resources.created(ResourceKind::Target), 3);
+        assert_eq!(resources.created(ResourceKind::Brush), 3);
+    }
+
+    #[test]
+    fn failing_creation_draws_nothing_until_it_succeeds() {
+        let mut surface = FaultInjectingSurface::new(100, 100)
+            .with_creation_failing_at([0, 1, 3]);
+        let drawn: Vec<_> =
+            (0..4).map(|_| surface.draw(&mut |_| {}).is_ok()).collect();
+
+        // The target is already there for the last frame.
+        assert_eq!(drawn, [false, false, true, true]);
+        assert_eq!(surface.frames().len(), 2);
+        assert!(matches!(
+            FaultInjectingSurface::new(1, 1)
+                .with_creation_failing_at([0])
+                .draw(&mut |_| {}),
+            Err(Error::ResourceCreation(ResourceKind::Target, _))
+        ));
+    }
+}
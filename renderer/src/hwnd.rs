@@ -0,0 +1,194 @@
+use windows::{
+    core::{Interface, Result},
+    Win32::{
+        Foundation::{D2DERR_RECREATE_TARGET, HWND, RECT},
+        Graphics::{
+            Direct2D::{
+                Common::{
+                    D2D1_ALPHA_MODE_UNKNOWN, D2D1_PIXEL_FORMAT, D2D_SIZE_U,
+                },
+                D2D1CreateFactory, ID2D1Factory, ID2D1HwndRenderTarget,
+                D2D1_DEBUG_LEVEL_INFORMATION, D2D1_FACTORY_OPTIONS,
+                D2D1_FACTORY_TYPE_SINGLE_THREADED, D2D1_FEATURE_LEVEL_DEFAULT,
+                D2D1_HWND_RENDER_TARGET_PROPERTIES, D2D1_PRESENT_OPTIONS_NONE,
+                D2D1_RENDER_TARGET_PROPERTIES, D2D1_RENDER_TARGET_TYPE_DEFAULT,
+                D2D1_RENDER_TARGET_USAGE_NONE,
+            },
+            Dxgi::Common::DXGI_FORMAT_UNKNOWN,
+        },
+        UI::WindowsAndMessaging::GetClientRect,
+    },
+};
+
+use crate::{
+    direct2d::Direct2DRenderer,
+    renderer::Renderer,
+    resources::{DeviceResource, DeviceResources, ResourceKind},
+    surface::Surface,
+    Error,
+};
+
+/// Draws to the client area of a window through an
+/// `ID2D1HwndRenderTarget`, which is created when the first frame is drawn
+/// and again after the device was lost.
+pub struct HwndSurface {
+    factory: ID2D1Factory,
+    window_handle: HWND,
+    resources: DeviceResources,
+    render_target: DeviceResource<ID2D1HwndRenderTarget>,
+    // Owns the brush.
+    renderer: DeviceResource<Direct2DRenderer>,
+}
+
+impl HwndSurface {
+    pub fn new(window_handle: HWND) -> std::result::Result<Self, Error> {
+        Ok(HwndSurface {
+            factory: create_factory()
+                .map_err(creation_error(ResourceKind::Target))?,
+            window_handle,
+            resources: DeviceResources::new(),
+            render_target: DeviceResource::new(ResourceKind::Target),
+            renderer: DeviceResource::new(ResourceKind::Brush),
+        })
+    }
+
+    pub fn resources(&self) -> &DeviceResources {
+        &self.resources
+    }
+}
+
+impl Surface for HwndSurface {
+    fn resize(
+        &mut self,
+        width: u32,
+        height: u32,
+    ) -> std::result::Result<(), Error> {
+        // Without a render target there is nothing to resize, the next one
+        // is created at the size of the client area.
+        let render_target =
+            match self.resources.current(&mut self.render_target) {
+                Some(render_target) => render_target,
+                None => return Ok(()),
+            };
+        let resized =
+            unsafe { render_target.Resize(&D2D_SIZE_U { width, height }) };
+        resized.map_err(|error| self.draw_error(error))
+    }
+
+    fn draw(
+        &mut self,
+        draw: &mut dyn FnMut(&mut dyn Renderer),
+    ) -> std::result::Result<(), Error> {
+        let HwndSurface {
+            factory,
+            window_handle,
+            resources,
+            render_target,
+            renderer,
+        } = self;
+        let render_target = resources
+            .get(render_target, || {
+                create_render_target(factory, *window_handle)
+                    .map_err(creation_error(ResourceKind::Target))
+            })?
+            .clone();
+        let renderer = resources.get(renderer, || {
+            Direct2DRenderer::new(render_target.clone())
+                .map_err(creation_error(ResourceKind::Brush))
+        })?;
+
+        // Initiates Direct2D drawing on this render target.
+        unsafe { render_target.BeginDraw() };
+        draw(renderer);
+
+        // `EndDraw` presents the frame of an `ID2D1HwndRenderTarget`. Every
+        // resource is created again for the next frame once the device is
+        // lost.
+        let ended = unsafe {
+            render_target.EndDraw(std::ptr::null_mut(), std::ptr::null_mut())
+        };
+        ended.map_err(|error| self.draw_error(error))
+    }
+}
+
+impl HwndSurface {
+    // Once the device is lost every resource is created again for the next
+    // frame.
+    fn draw_error(&mut self, error: windows::core::Error) -> Error {
+        if error.code() == D2DERR_RECREATE_TARGET {
+            self.resources.device_lost();
+            return Error::DeviceLost;
+        }
+        Error::Draw(error.to_string())
+    }
+}
+
+fn creation_error(
+    kind: ResourceKind,
+) -> impl FnOnce(windows::core::Error) -> Error {
+    move |error| Error::ResourceCreation(kind, error.to_string())
+}
+
+// A render target the size of the client area of `window_handle`.
+fn create_render_target(
+    factory: &ID2D1Factory,
+    window_handle: HWND,
+) -> Result<ID2D1HwndRenderTarget> {
+    let mut rect: RECT = RECT::default();
+    unsafe { GetClientRect(window_handle, &mut rect).ok()? };
+    let pixel_size = D2D_SIZE_U {
+        width: (rect.right - rect.left) as u32,
+        height: (rect.bottom - rect.top) as u32,
+    };
+
+    let render_properties = {
+        let pixel_format = D2D1_PIXEL_FORMAT {
+            format: DXGI_FORMAT_UNKNOWN,
+            alphaMode: D2D1_ALPHA_MODE_UNKNOWN,
+        };
+
+        D2D1_RENDER_TARGET_PROPERTIES {
+            r#type: D2D1_RENDER_TARGET_TYPE_DEFAULT,
+            pixelFormat: pixel_format,
+            dpiX: 0.0,
+            dpiY: 0.0,
+            usage: D2D1_RENDER_TARGET_USAGE_NONE,
+            minLevel: D2D1_FEATURE_LEVEL_DEFAULT,
+        }
+    };
+
+    let hwnd_render_properties = D2D1_HWND_RENDER_TARGET_PROPERTIES {
+        hwnd: window_handle,
+        pixelSize: pixel_size,
+        presentOptions: D2D1_PRESENT_OPTIONS_NONE,
+    };
+
+    // Creates an ID2D1HwndRenderTarget, a render target that renders to a
+    // window.
+    unsafe {
+        factory
+            .CreateHwndRenderTarget(&render_properties, &hwnd_render_properties)
+    }
+}
+
+fn create_factory() -> Result<ID2D1Factory> {
+    // Contains the debugging level of an ID2D1Factory object.
+    let mut options = D2D1_FACTORY_OPTIONS::default();
+
+    // Direct2D sends error messages, warnings, and additional diagnostic
+    // information to the debug layer in debug builds.
+    if cfg!(debug_assertions) {
+        options.debugLevel = D2D1_DEBUG_LEVEL_INFORMATION;
+    }
+
+    let mut result = None;
+    unsafe {
+        D2D1CreateFactory(
+            D2D1_FACTORY_TYPE_SINGLE_THREADED,
+            &ID2D1Factory::IID,
+            &options,
+            &mut result as *mut Option<ID2D1Factory> as *mut _,
+        )
+        .map(|()| result.unwrap())
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+/// An 8-bit RGBA image with straight (not premultiplied) alpha, stored row
+/// by row from the top left.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    /// A fully transparent image.
+    pub fn new(width: u32, height: u32) -> Self {
+        Image {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    /// Wraps RGBA bytes, `None` if there aren't exactly four per pixel.
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Option<Self> {
+        if pixels.len() != width as usize * height as usize * 4 {
+            return None;
+        }
+        Some(Image {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The RGBA bytes of all pixels.
+    pub fn as_rgba(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let offset = self.offset(x, y);
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&self.pixels[offset..offset + 4]);
+        pixel
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, pixel: [u8; 4]) {
+        let offset = self.offset(x, y);
+        self.pixels[offset..offset + 4].copy_from_slice(&pixel);
+    }
+
+    fn offset(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+
+    pub fn write_png(&self, writer: impl Write) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels))
+            .map_err(io::Error::other)
+    }
+
+    /// Reads an 8-bit RGB or RGBA PNG.
+    pub fn read_png(reader: impl Read) -> io::Result<Self> {
+        let invalid = |error| io::Error::new(io::ErrorKind::InvalidData, error);
+
+        let mut reader = png::Decoder::new(reader)
+            .read_info()
+            .map_err(|error| invalid(error.to_string()))?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut buffer)
+            .map_err(|error| invalid(error.to_string()))?;
+        buffer.truncate(info.buffer_size());
+
+        let pixels = match (info.color_type, info.bit_depth) {
+            (png::ColorType::Rgba, png::BitDepth::Eight) => buffer,
+            (png::ColorType::Rgb, png::BitDepth::Eight) => buffer
+                .chunks_exact(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], u8::MAX])
+                .collect(),
+            (color, depth) => {
+                return Err(invalid(format!(
+                    "unsupported PNG format {:?} {:?}",
+                    color, depth
+                )))
+            }
+        };
+
+        Ok(Image {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_png(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load_png(path: impl AsRef<Path>) -> io::Result<Self> {
+        Image::read_png(BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn png_round_trip() {
+        let mut image = Image::new(3, 2);
+        image.set_pixel(0, 0, [255, 0, 0, 255]);
+        image.set_pixel(2, 1, [10, 20, 30, 40]);
+
+        let mut png = Vec::new();
+        image.write_png(&mut png).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+
+        let decoded = Image::read_png(png.as_slice()).unwrap();
+        assert_eq!(decoded, image);
+        assert_eq!(decoded.pixel(2, 1), [10, 20, 30, 40]);
+    }
+
+    #[test]
+    fn rgb_png_is_read_as_opaque() {
+        let mut png = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut png, 2, 1);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&[1, 2, 3, 4, 5, 6]).unwrap();
+        }
+
+        let image = Image::read_png(png.as_slice()).unwrap();
+        assert_eq!(image.as_rgba(), [1, 2, 3, 255, 4, 5, 6, 255]);
+    }
+
+    #[test]
+    fn garbage_is_not_a_png() {
+        let error = Image::read_png(&b"not a png"[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn from_rgba_checks_the_length() {
+        assert!(Image::from_rgba(2, 2, vec![0; 16]).is_some());
+        assert!(Image::from_rgba(2, 2, vec![0; 15]).is_none());
+    }
+}
@@ -0,0 +1,37 @@
+//! Drawing for the clocks behind a small [`Renderer`] trait, so the same
+//! scene code can draw with Direct2D on Windows, or be recorded or rasterized
+//! to an image anywhere else.
+
+#[cfg(windows)]
+mod direct2d;
+mod error;
+mod fault;
+#[cfg(windows)]
+mod hwnd;
+mod image;
+mod overlay;
+mod primitives;
+mod recording;
+mod renderer;
+mod resources;
+mod software;
+mod surface;
+mod svg;
+mod window_surface;
+
+#[cfg(windows)]
+pub use direct2d::Direct2DRenderer;
+pub use error::Error;
+pub use fault::FaultInjectingSurface;
+#[cfg(windows)]
+pub use hwnd::HwndSurface;
+pub use image::Image;
+pub use overlay::{draw_frame_stats, report_frame_stats, STATS_PATH};
+pub use primitives::{CapStyle, Color, Ellipse, Size, StrokeStyle};
+pub use recording::{Command, RecordingRenderer};
+pub use renderer::Renderer;
+pub use resources::{DeviceResource, DeviceResources, ResourceKind};
+pub use software::SoftwareRenderer;
+pub use surface::{RecordingSurface, Surface};
+pub use svg::to_svg;
+pub use window_surface::{CreateSurface, WindowSurface, RETRY_DELAY};
@@ -0,0 +1,175 @@
+use std::{fs::File, io::BufWriter};
+
+use clock_core::{FrameStats, Matrix, Point};
+
+use crate::{
+    primitives::{Color, StrokeStyle},
+    renderer::Renderer,
+};
+
+const MARGIN: f32 = 10.0;
+const HEIGHT: f32 = 60.0;
+const BAR_WIDTH: f32 = 2.0;
+
+const BACKGROUND: Color = Color::rgba(0.0, 0.0, 0.0, 0.5);
+const ON_TIME: Color = Color::rgb(0.2, 0.8, 0.2);
+const LATE: Color = Color::rgb(0.95, 0.75, 0.1);
+const DROPPED: Color = Color::rgb(0.9, 0.15, 0.1);
+const BUDGET: Color = Color::rgba(1.0, 1.0, 1.0, 0.6);
+const P99: Color = Color::rgba(0.9, 0.15, 0.1, 0.6);
+
+/// Where the frame times are left when a window collecting them closes.
+pub const STATS_PATH: &str = "frame-stats.csv";
+
+/// Draws the frame times kept by `stats` as a bar graph in the bottom left
+/// corner, newest on the right, with lines at the frame `budget` in seconds
+/// and at the 99th percentile.
+///
+/// The budget line is halfway up, so bars reaching the top took twice the
+/// budget or longer. Bars are green within the budget, yellow within twice
+/// the budget and red beyond that or when frames were dropped before them.
+pub fn draw_frame_stats(
+    renderer: &mut impl Renderer,
+    stats: &FrameStats,
+    budget: f64,
+) {
+    let size = renderer.size();
+    let bars = (((size.width - 2.0 * MARGIN) / BAR_WIDTH).max(0.0) as usize)
+        .min(stats.capacity());
+    if bars == 0 || budget <= 0.0 {
+        return;
+    }
+
+    let left = MARGIN;
+    let bottom = size.height - MARGIN;
+    let width = bars as f32 * BAR_WIDTH;
+    // Two budgets fill the whole height.
+    let y =
+        |time: f64| bottom - (time / (2.0 * budget)).min(1.0) as f32 * HEIGHT;
+
+    renderer.set_transform(Matrix::identity());
+    renderer.set_stroke_style(StrokeStyle::default());
+    renderer.set_brush_color(BACKGROUND);
+    renderer.draw_line(
+        Point::new(left, bottom - HEIGHT / 2.0),
+        Point::new(left + width, bottom - HEIGHT / 2.0),
+        HEIGHT,
+    );
+
+    let samples = stats.samples();
+    let skip = samples.len().saturating_sub(bars);
+    let first = bars - (samples.len() - skip);
+    for (index, sample) in samples.skip(skip).enumerate() {
+        renderer.set_brush_color(
+            if sample.dropped > 0 || sample.frame_time > 2.0 * budget {
+                DROPPED
+            } else if sample.frame_time > budget {
+                LATE
+            } else {
+                ON_TIME
+            },
+        );
+        let x = left + ((first + index) as f32 + 0.5) * BAR_WIDTH;
+        renderer.draw_line(
+            Point::new(x, bottom),
+            Point::new(x, y(sample.frame_time)),
+            BAR_WIDTH,
+        );
+    }
+
+    let mut mark = |color, time| {
+        renderer.set_brush_color(color);
+        renderer.draw_line(
+            Point::new(left, y(time)),
+            Point::new(left + width, y(time)),
+            1.0,
+        );
+    };
+    mark(BUDGET, budget);
+    if let Some(p99) = stats.percentile(99.0) {
+        mark(P99, p99);
+    }
+}
+
+/// Prints the summary of `stats` and writes its frame times to
+/// [`STATS_PATH`], as a window collecting them does when it closes.
+pub fn report_frame_stats(stats: &FrameStats) {
+    if let Some(summary) = stats.summary() {
+        println!("{}", summary);
+    }
+    let written = File::create(STATS_PATH)
+        .and_then(|file| stats.write_csv(BufWriter::new(file)));
+    match written {
+        Ok(()) => println!("Wrote the frame times to {}", STATS_PATH),
+        Err(error) => eprintln!("Failed to write {}: {}", STATS_PATH, error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Command, RecordingRenderer, Size};
+
+    fn lines(commands: &[Command]) -> Vec<(Color, Point, Point, f32)> {
+        let mut color = Color::default();
+        let mut lines = Vec::new();
+        for command in commands {
+            match *command {
+                Command::SetBrushColor(c) => color = c,
+                Command::DrawLine {
+                    from,
+                    to,
+                    stroke_width,
+                } => lines.push((color, from, to, stroke_width)),
+                _ => {}
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn bars_are_colored_by_budget() {
+        let mut stats = FrameStats::new(100);
+        stats.record(0.0, 0.0, 0);
+        stats.record(0.010, 0.0, 0);
+        stats.record(0.025, 0.0, 0);
+        stats.record(0.075, 0.0, 0);
+        stats.record(0.085, 0.0, 1);
+
+        let mut renderer = RecordingRenderer::new(Size::new(400.0, 300.0));
+        draw_frame_stats(&mut renderer, &stats, 0.0125);
+        let lines = lines(renderer.commands());
+
+        // The background, four bars, the budget and the 99th percentile.
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[0].0, BACKGROUND);
+        let colors: Vec<_> = lines[1..5].iter().map(|line| line.0).collect();
+        assert_eq!(colors, [ON_TIME, LATE, DROPPED, DROPPED]);
+
+        // 10 ms of a 25 ms graph, and the 50 ms bar is cut off at the top.
+        assert_eq!(lines[1].2, Point::new(203.0, 266.0));
+        assert_eq!(lines[3].2.y, 230.0);
+        assert_eq!(lines[5].1.y, 260.0);
+        assert_eq!(lines[6].1.y, 230.0);
+    }
+
+    #[test]
+    fn only_the_newest_frames_that_fit_are_drawn() {
+        let mut stats = FrameStats::new(1000);
+        for frame in 0..500 {
+            stats.record(frame as f64 / 60.0, 0.0, 0);
+        }
+
+        let mut renderer = RecordingRenderer::new(Size::new(120.0, 100.0));
+        draw_frame_stats(&mut renderer, &stats, 1.0 / 60.0);
+        // 50 bars fit between the margins.
+        assert_eq!(lines(renderer.commands()).len(), 1 + 50 + 2);
+    }
+
+    #[test]
+    fn nothing_to_draw() {
+        let mut renderer = RecordingRenderer::new(Size::new(10.0, 10.0));
+        draw_frame_stats(&mut renderer, &FrameStats::new(10), 0.016);
+        assert!(renderer.commands().is_empty());
+    }
+}
@@ -0,0 +1,71 @@
+use clock_core::Point;
+
+/// A straight (not premultiplied) RGBA color with components in `0.0..=1.0`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0.0, 0.0, 0.0);
+    pub const WHITE: Color = Color::rgb(1.0, 1.0, 1.0);
+    pub const TRANSPARENT: Color = Color::rgba(0.0, 0.0, 0.0, 0.0);
+
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Color { r, g, b, a: 1.0 }
+    }
+
+    pub const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Color { r, g, b, a }
+    }
+}
+
+/// The size of a render target in device independent pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Size {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Size {
+    pub const fn new(width: f32, height: f32) -> Self {
+        Size { width, height }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Ellipse {
+    pub center: Point,
+    pub radius_x: f32,
+    pub radius_y: f32,
+}
+
+impl Ellipse {
+    pub const fn circle(center: Point, radius: f32) -> Self {
+        Ellipse {
+            center,
+            radius_x: radius,
+            radius_y: radius,
+        }
+    }
+}
+
+/// The shape at the end of a line, as in `D2D1_CAP_STYLE`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CapStyle {
+    #[default]
+    Flat,
+    Square,
+    Round,
+    Triangle,
+}
+
+/// How lines are stroked. The default is a plain line with flat ends.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct StrokeStyle {
+    pub start_cap: CapStyle,
+    pub end_cap: CapStyle,
+}
@@ -0,0 +1,151 @@
+use clock_core::{Matrix, Point};
+
+use crate::{
+    primitives::{Color, Ellipse, Size, StrokeStyle},
+    renderer::Renderer,
+};
+
+/// One call made on a [`RecordingRenderer`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Clear(Color),
+    SetBrushColor(Color),
+    SetStrokeStyle(StrokeStyle),
+    SetTransform(Matrix),
+    FillEllipse(Ellipse),
+    DrawEllipse {
+        ellipse: Ellipse,
+        stroke_width: f32,
+    },
+    DrawLine {
+        from: Point,
+        to: Point,
+        stroke_width: f32,
+    },
+}
+
+/// A renderer that draws nothing and remembers every call instead, so
+/// scenes can be inspected without a GPU or a window.
+#[derive(Clone, Debug, Default)]
+pub struct RecordingRenderer {
+    size: Size,
+    commands: Vec<Command>,
+}
+
+impl RecordingRenderer {
+    pub fn new(size: Size) -> Self {
+        RecordingRenderer {
+            size,
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    /// Hands out the commands recorded so far and starts over, e.g. once per
+    /// frame.
+    pub fn take_commands(&mut self) -> Vec<Command> {
+        std::mem::take(&mut self.commands)
+    }
+
+    pub fn set_size(&mut self, size: Size) {
+        self.size = size;
+    }
+}
+
+impl Renderer for RecordingRenderer {
+    fn size(&self) -> Size {
+        self.size
+    }
+
+    fn clear(&mut self, color: Color) {
+        self.commands.push(Command::Clear(color));
+    }
+
+    fn set_brush_color(&mut self, color: Color) {
+        self.commands.push(Command::SetBrushColor(color));
+    }
+
+    fn set_stroke_style(&mut self, style: StrokeStyle) {
+        self.commands.push(Command::SetStrokeStyle(style));
+    }
+
+    fn set_transform(&mut self, transform: Matrix) {
+        self.commands.push(Command::SetTransform(transform));
+    }
+
+    fn fill_ellipse(&mut self, ellipse: Ellipse) {
+        self.commands.push(Command::FillEllipse(ellipse));
+    }
+
+    fn draw_ellipse(&mut self, ellipse: Ellipse, stroke_width: f32) {
+        self.commands.push(Command::DrawEllipse {
+            ellipse,
+            stroke_width,
+        });
+    }
+
+    fn draw_line(&mut self, from: Point, to: Point, stroke_width: f32) {
+        self.commands.push(Command::DrawLine {
+            from,
+            to,
+            stroke_width,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw_something(renderer: &mut impl Renderer) {
+        renderer.clear(Color::WHITE);
+        renderer.set_brush_color(Color::rgb(1.0, 0.0, 0.0));
+        renderer.set_transform(Matrix::translation(10.0, 20.0));
+        renderer.fill_ellipse(Ellipse::circle(Point::default(), 5.0));
+        renderer.draw_line(Point::default(), Point::new(0.0, -5.0), 2.0);
+    }
+
+    #[test]
+    fn records_calls_in_order() {
+        let mut renderer = RecordingRenderer::new(Size::new(100.0, 50.0));
+        draw_something(&mut renderer);
+
+        assert_eq!(
+            renderer.commands(),
+            [
+                Command::Clear(Color::WHITE),
+                Command::SetBrushColor(Color::rgb(1.0, 0.0, 0.0)),
+                Command::SetTransform(Matrix::translation(10.0, 20.0)),
+                Command::FillEllipse(Ellipse::circle(Point::default(), 5.0)),
+                Command::DrawLine {
+                    from: Point::default(),
+                    to: Point::new(0.0, -5.0),
+                    stroke_width: 2.0
+                },
+            ]
+        );
+        assert_eq!(renderer.size(), Size::new(100.0, 50.0));
+    }
+
+    #[test]
+    fn take_commands_starts_a_new_recording() {
+        let mut renderer = RecordingRenderer::default();
+        draw_something(&mut renderer);
+
+        assert_eq!(renderer.take_commands().len(), 5);
+        assert!(renderer.commands().is_empty());
+    }
+
+    #[test]
+    fn records_through_a_trait_object() {
+        let mut recording = RecordingRenderer::default();
+        {
+            let mut renderer: &mut dyn Renderer = &mut recording;
+            draw_something(&mut renderer);
+        }
+        assert_eq!(recording.commands().len(), 5);
+    }
+}
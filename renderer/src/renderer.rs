@@ -0,0 +1,63 @@
+use clock_core::{Matrix, Point};
+
+use crate::primitives::{Color, Ellipse, Size, StrokeStyle};
+
+/// The drawing primitives the clocks need, modelled after Direct2D's
+/// `ID2D1RenderTarget`.
+///
+/// Like a render target, a renderer has drawing state: every shape is drawn
+/// with the current brush color and transform, and lines with the current
+/// stroke style. A new renderer draws black, untransformed lines with the
+/// default [`StrokeStyle`].
+pub trait Renderer {
+    fn size(&self) -> Size;
+
+    /// Fills the whole target with `color`, ignoring the transform.
+    fn clear(&mut self, color: Color);
+
+    fn set_brush_color(&mut self, color: Color);
+
+    fn set_stroke_style(&mut self, style: StrokeStyle);
+
+    fn set_transform(&mut self, transform: Matrix);
+
+    fn fill_ellipse(&mut self, ellipse: Ellipse);
+
+    fn draw_ellipse(&mut self, ellipse: Ellipse, stroke_width: f32);
+
+    fn draw_line(&mut self, from: Point, to: Point, stroke_width: f32);
+}
+
+impl<R: Renderer + ?Sized> Renderer for &mut R {
+    fn size(&self) -> Size {
+        (**self).size()
+    }
+
+    fn clear(&mut self, color: Color) {
+        (**self).clear(color)
+    }
+
+    fn set_brush_color(&mut self, color: Color) {
+        (**self).set_brush_color(color)
+    }
+
+    fn set_stroke_style(&mut self, style: StrokeStyle) {
+        (**self).set_stroke_style(style)
+    }
+
+    fn set_transform(&mut self, transform: Matrix) {
+        (**self).set_transform(transform)
+    }
+
+    fn fill_ellipse(&mut self, ellipse: Ellipse) {
+        (**self).fill_ellipse(ellipse)
+    }
+
+    fn draw_ellipse(&mut self, ellipse: Ellipse, stroke_width: f32) {
+        (**self).draw_ellipse(ellipse, stroke_width)
+    }
+
+    fn draw_line(&mut self, from: Point, to: Point, stroke_width: f32) {
+        (**self).draw_line(from, to, stroke_width)
+    }
+}
@@ -0,0 +1,195 @@
+use std::fmt;
+
+/// What a device dependent resource is, for counting them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResourceKind {
+    /// A render target or device context, and the device behind it.
+    Target,
+    SwapChain,
+    Brush,
+    Bitmap,
+    Effect,
+}
+
+impl ResourceKind {
+    pub const ALL: [ResourceKind; 5] = [
+        ResourceKind::Target,
+        ResourceKind::SwapChain,
+        ResourceKind::Brush,
+        ResourceKind::Bitmap,
+        ResourceKind::Effect,
+    ];
+
+    fn index(self) -> usize {
+        ResourceKind::ALL
+            .iter()
+            .position(|kind| *kind == self)
+            .unwrap()
+    }
+}
+
+impl fmt::Display for ResourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ResourceKind::Target => "render target",
+            ResourceKind::SwapChain => "swap chain",
+            ResourceKind::Brush => "brush",
+            ResourceKind::Bitmap => "bitmap",
+            ResourceKind::Effect => "effect",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A resource that only works with the device it was created on, like a
+/// Direct2D brush or a DXGI swap chain.
+///
+/// It is created by [`DeviceResources::get`] when it is first needed, and
+/// again after the device was lost or the resource was released.
+#[derive(Debug)]
+pub struct DeviceResource<T> {
+    kind: ResourceKind,
+    value: Option<T>,
+    // The device the value was created on.
+    device: u64,
+}
+
+impl<T> DeviceResource<T> {
+    pub fn new(kind: ResourceKind) -> Self {
+        DeviceResource {
+            kind,
+            value: None,
+            device: 0,
+        }
+    }
+
+    pub fn kind(&self) -> ResourceKind {
+        self.kind
+    }
+
+    /// Drops the resource so it is created again when it is needed next,
+    /// e.g. because it depends on the size of a target that was resized.
+    pub fn release(&mut self) {
+        self.value = None;
+    }
+}
+
+/// Keeps track of the device that [`DeviceResource`]s are created on.
+///
+/// Once the device is lost every resource created on it is stale. Stale
+/// resources aren't dropped all at once: like any missing resource, each is
+/// created again the next time it is asked for, so resources that depend on
+/// others can be asked for in order.
+#[derive(Clone, Debug, Default)]
+pub struct DeviceResources {
+    // Counts up with every device, starting at 1 for the first one.
+    device: u64,
+    lost: u32,
+    created: [u32; ResourceKind::ALL.len()],
+}
+
+impl DeviceResources {
+    pub fn new() -> Self {
+        DeviceResources {
+            device: 1,
+            ..DeviceResources::default()
+        }
+    }
+
+    /// The resource, created with `create` unless it was created on the
+    /// current device before.
+    pub fn get<'a, T, E>(
+        &mut self,
+        resource: &'a mut DeviceResource<T>,
+        create: impl FnOnce() -> Result<T, E>,
+    ) -> Result<&'a mut T, E> {
+        if resource.device != self.device {
+            resource.value = None;
+        }
+        if resource.value.is_none() {
+            resource.value = Some(create()?);
+            resource.device = self.device;
+            self.created[resource.kind.index()] += 1;
+        }
+        Ok(resource.value.as_mut().unwrap())
+    }
+
+    /// The resource if it was created on the current device.
+    pub fn current<'a, T>(
+        &self,
+        resource: &'a mut DeviceResource<T>,
+    ) -> Option<&'a mut T> {
+        if resource.device != self.device {
+            return None;
+        }
+        resource.value.as_mut()
+    }
+
+    /// The device went away, so every resource has to be created again.
+    pub fn device_lost(&mut self) {
+        self.device += 1;
+        self.lost += 1;
+    }
+
+    /// How often the device was lost.
+    pub fn devices_lost(&self) -> u32 {
+        self.lost
+    }
+
+    /// How many resources of `kind` were created so far.
+    pub fn created(&self, kind: ResourceKind) -> u32 {
+        self.created[kind.index()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resources_are_created_once_per_device() {
+        let mut resources = DeviceResources::new();
+        let mut brush = DeviceResource::new(ResourceKind::Brush);
+        let mut next = 0;
+        let mut create = || -> Result<u32, ()> {
+            next += 1;
+            Ok(next)
+        };
+
+        assert_eq!(*resources.get(&mut brush, &mut create).unwrap(), 1);
+        assert_eq!(*resources.get(&mut brush, &mut create).unwrap(), 1);
+        assert_eq!(resources.current(&mut brush), Some(&mut 1));
+
+        resources.device_lost();
+        assert_eq!(resources.current(&mut brush), None);
+        assert_eq!(*resources.get(&mut brush, &mut create).unwrap(), 2);
+        assert_eq!(resources.created(ResourceKind::Brush), 2);
+        assert_eq!(resources.created(ResourceKind::Bitmap), 0);
+        assert_eq!(resources.devices_lost(), 1);
+    }
+
+    #[test]
+    fn released_resources_are_created_again() {
+        let mut resources = DeviceResources::new();
+        let mut bitmap = DeviceResource::new(ResourceKind::Bitmap);
+        resources.get(&mut bitmap, || Ok::<_, ()>("small")).unwrap();
+
+        bitmap.release();
+        let created = resources.get(&mut bitmap, || Ok::<_, ()>("large"));
+        assert_eq!(created, Ok(&mut "large"));
+        assert_eq!(resources.created(ResourceKind::Bitmap), 2);
+    }
+
+    #[test]
+    fn failing_to_create_leaves_the_resource_missing() {
+        let mut resources = DeviceResources::new();
+        let mut effect = DeviceResource::<u32>::new(ResourceKind::Effect);
+
+        assert_eq!(
+            resources.get(&mut effect, || Err("no device")),
+            Err("no device")
+        );
+        assert_eq!(resources.current(&mut effect), None);
+        assert_eq!(resources.created(ResourceKind::Effect), 0);
+    }
+}
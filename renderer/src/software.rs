@@ -0,0 +1,445 @@
+use clock_core::{Matrix, Point};
+
+use crate::{
+    image::Image,
+    primitives::{CapStyle, Color, Ellipse, Size, StrokeStyle},
+    renderer::Renderer,
+};
+
+// Every pixel is sampled on a SAMPLES x SAMPLES grid for anti-aliasing.
+const SAMPLES: u32 = 4;
+
+/// Rasterizes on the CPU into a pixel buffer, one device independent pixel
+/// per pixel.
+///
+/// Shapes are anti-aliased by supersampling and blended source-over like
+/// Direct2D does, and stroke widths scale with the transform. The result
+/// looks close to, but not exactly like, what Direct2D draws.
+#[derive(Clone, Debug)]
+pub struct SoftwareRenderer {
+    width: u32,
+    height: u32,
+    // Premultiplied RGBA.
+    pixels: Vec<[f32; 4]>,
+    color: Color,
+    stroke_style: StrokeStyle,
+    transform: Matrix,
+}
+
+impl SoftwareRenderer {
+    /// A transparent target of `width` x `height` pixels.
+    pub fn new(width: u32, height: u32) -> Self {
+        SoftwareRenderer {
+            width,
+            height,
+            pixels: vec![[0.0; 4]; width as usize * height as usize],
+            color: Color::BLACK,
+            stroke_style: StrokeStyle::default(),
+            transform: Matrix::identity(),
+        }
+    }
+
+    /// What has been drawn so far.
+    pub fn image(&self) -> Image {
+        let mut bytes = Vec::with_capacity(self.pixels.len() * 4);
+        for &[r, g, b, a] in &self.pixels {
+            let unpremultiply = if a > 0.0 { 1.0 / a } else { 0.0 };
+            bytes.extend([
+                to_byte(r * unpremultiply),
+                to_byte(g * unpremultiply),
+                to_byte(b * unpremultiply),
+                to_byte(a),
+            ]);
+        }
+
+        Image::from_rgba(self.width, self.height, bytes).unwrap()
+    }
+
+    /// Blends the current color into every pixel whose samples fall inside
+    /// the shape. `bounds` are the top left and bottom right corners of a
+    /// box around the shape and `inside` tests a point, both in the
+    /// untransformed coordinates the shape was given in.
+    fn fill(&mut self, bounds: (Point, Point), inside: impl Fn(Point) -> bool) {
+        let inverse = match self.transform.invert() {
+            Some(inverse) => inverse,
+            None => return,
+        };
+
+        let (min, max) = bounds;
+        let corners =
+            [min, Point::new(max.x, min.y), max, Point::new(min.x, max.y)]
+                .map(|corner| self.transform.transform_point(corner));
+        let left = corners.iter().map(|p| p.x).fold(f32::INFINITY, f32::min);
+        let right = corners.iter().map(|p| p.x).fold(-f32::INFINITY, f32::max);
+        let top = corners.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+        let bottom = corners.iter().map(|p| p.y).fold(-f32::INFINITY, f32::max);
+
+        let columns = pixel_range(left, right, self.width);
+        let rows = pixel_range(top, bottom, self.height);
+
+        let color = self.color;
+        for y in rows {
+            for x in columns.clone() {
+                let mut covered = 0;
+                for sample_y in 0..SAMPLES {
+                    for sample_x in 0..SAMPLES {
+                        let sample = Point::new(
+                            x as f32 + (sample_x as f32 + 0.5) / SAMPLES as f32,
+                            y as f32 + (sample_y as f32 + 0.5) / SAMPLES as f32,
+                        );
+                        if inside(inverse.transform_point(sample)) {
+                            covered += 1;
+                        }
+                    }
+                }
+
+                if covered > 0 {
+                    let coverage = covered as f32 / (SAMPLES * SAMPLES) as f32;
+                    let pixel = &mut self.pixels[(y * self.width + x) as usize];
+                    blend(pixel, color, coverage);
+                }
+            }
+        }
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    fn size(&self) -> Size {
+        Size::new(self.width as f32, self.height as f32)
+    }
+
+    fn clear(&mut self, color: Color) {
+        let pixel = premultiply(color, 1.0);
+        self.pixels.fill(pixel);
+    }
+
+    fn set_brush_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    fn set_stroke_style(&mut self, style: StrokeStyle) {
+        self.stroke_style = style;
+    }
+
+    fn set_transform(&mut self, transform: Matrix) {
+        self.transform = transform;
+    }
+
+    fn fill_ellipse(&mut self, ellipse: Ellipse) {
+        let (radius_x, radius_y) = (ellipse.radius_x, ellipse.radius_y);
+        self.fill(ellipse_bounds(ellipse, 0.0), |point| {
+            inside_ellipse(point, ellipse.center, radius_x, radius_y)
+        });
+    }
+
+    fn draw_ellipse(&mut self, ellipse: Ellipse, stroke_width: f32) {
+        // The stroke is the ring between the ellipse grown and shrunk by
+        // half the stroke width, which is exact for circles.
+        let half = stroke_width / 2.0;
+        let (radius_x, radius_y) = (ellipse.radius_x, ellipse.radius_y);
+        self.fill(ellipse_bounds(ellipse, half), |point| {
+            inside_ellipse(
+                point,
+                ellipse.center,
+                radius_x + half,
+                radius_y + half,
+            ) && (radius_x <= half
+                || radius_y <= half
+                || !inside_ellipse(
+                    point,
+                    ellipse.center,
+                    radius_x - half,
+                    radius_y - half,
+                ))
+        });
+    }
+
+    fn draw_line(&mut self, from: Point, to: Point, stroke_width: f32) {
+        let half = stroke_width / 2.0;
+        let style = self.stroke_style;
+        let bounds = (
+            Point::new(from.x.min(to.x) - half, from.y.min(to.y) - half),
+            Point::new(from.x.max(to.x) + half, from.y.max(to.y) + half),
+        );
+        self.fill(bounds, |point| inside_line(point, from, to, half, style));
+    }
+}
+
+fn ellipse_bounds(ellipse: Ellipse, grow: f32) -> (Point, Point) {
+    let Ellipse {
+        center,
+        radius_x,
+        radius_y,
+    } = ellipse;
+    (
+        Point::new(center.x - radius_x - grow, center.y - radius_y - grow),
+        Point::new(center.x + radius_x + grow, center.y + radius_y + grow),
+    )
+}
+
+fn inside_ellipse(
+    point: Point,
+    center: Point,
+    radius_x: f32,
+    radius_y: f32,
+) -> bool {
+    if radius_x <= 0.0 || radius_y <= 0.0 {
+        return false;
+    }
+    let x = (point.x - center.x) / radius_x;
+    let y = (point.y - center.y) / radius_y;
+    x * x + y * y <= 1.0
+}
+
+fn inside_line(
+    point: Point,
+    from: Point,
+    to: Point,
+    half: f32,
+    style: StrokeStyle,
+) -> bool {
+    let length = from.distance(to);
+    // A line without length has no direction; its caps face left and right.
+    let (direction_x, direction_y) = if length > 0.0 {
+        ((to.x - from.x) / length, (to.y - from.y) / length)
+    } else {
+        (1.0, 0.0)
+    };
+
+    let x = point.x - from.x;
+    let y = point.y - from.y;
+    // How far along the line the point is, and how far beside it.
+    let along = x * direction_x + y * direction_y;
+    let across = (y * direction_x - x * direction_y).abs();
+
+    if along < 0.0 {
+        inside_cap(style.start_cap, -along, across, half)
+    } else if along > length {
+        inside_cap(style.end_cap, along - length, across, half)
+    } else {
+        across <= half
+    }
+}
+
+// `beyond` is how far the point is past the end of the line.
+fn inside_cap(cap: CapStyle, beyond: f32, across: f32, half: f32) -> bool {
+    match cap {
+        CapStyle::Flat => false,
+        CapStyle::Square => beyond <= half && across <= half,
+        CapStyle::Round => beyond * beyond + across * across <= half * half,
+        CapStyle::Triangle => beyond + across <= half,
+    }
+}
+
+// The pixels whose area overlaps `start..end`.
+fn pixel_range(start: f32, end: f32, limit: u32) -> std::ops::Range<u32> {
+    if !(start.is_finite() && end.is_finite()) {
+        return 0..0;
+    }
+    let start = start.floor().clamp(0.0, limit as f32) as u32;
+    let end = end.ceil().clamp(0.0, limit as f32) as u32;
+    start..end.max(start)
+}
+
+fn premultiply(color: Color, coverage: f32) -> [f32; 4] {
+    let alpha = color.a.clamp(0.0, 1.0) * coverage;
+    [
+        color.r.clamp(0.0, 1.0) * alpha,
+        color.g.clamp(0.0, 1.0) * alpha,
+        color.b.clamp(0.0, 1.0) * alpha,
+        alpha,
+    ]
+}
+
+fn blend(pixel: &mut [f32; 4], color: Color, coverage: f32) {
+    let source = premultiply(color, coverage);
+    let remaining = 1.0 - source[3];
+    for (destination, source) in pixel.iter_mut().zip(source) {
+        *destination = source + *destination * remaining;
+    }
+}
+
+fn to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Color = Color::rgb(1.0, 0.0, 0.0);
+
+    fn renderer(width: u32, height: u32) -> SoftwareRenderer {
+        let mut renderer = SoftwareRenderer::new(width, height);
+        renderer.clear(Color::WHITE);
+        renderer.set_brush_color(RED);
+        renderer
+    }
+
+    #[test]
+    fn new_target_is_transparent() {
+        let image = SoftwareRenderer::new(2, 3).image();
+        assert_eq!((image.width(), image.height()), (2, 3));
+        assert!(image.as_rgba().iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn clear_fills_everything_and_ignores_the_transform() {
+        let mut renderer = SoftwareRenderer::new(4, 4);
+        renderer.set_transform(Matrix::scale(0.0, 0.0));
+        renderer.clear(Color::rgba(0.0, 0.0, 1.0, 0.5));
+
+        let image = renderer.image();
+        for y in 0..4 {
+            for x in 0..4 {
+                assert_eq!(image.pixel(x, y), [0, 0, 255, 128]);
+            }
+        }
+    }
+
+    #[test]
+    fn filled_circle_covers_its_inside_only() {
+        let mut renderer = renderer(20, 20);
+        renderer.fill_ellipse(Ellipse::circle(Point::new(10.0, 10.0), 5.0));
+        let image = renderer.image();
+
+        assert_eq!(image.pixel(10, 10), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(6, 10), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(3, 10), [255, 255, 255, 255]);
+        assert_eq!(image.pixel(0, 0), [255, 255, 255, 255]);
+
+        // The rim is anti-aliased.
+        let [_, green, _, _] = image.pixel(13, 6);
+        assert!(green > 0 && green < 255, "{}", green);
+    }
+
+    #[test]
+    fn filled_area_matches_the_circle() {
+        let mut renderer = SoftwareRenderer::new(64, 64);
+        renderer.fill_ellipse(Ellipse::circle(Point::new(32.0, 32.0), 20.0));
+
+        let image = renderer.image();
+        let area: f32 = image
+            .as_rgba()
+            .chunks_exact(4)
+            .map(|pixel| pixel[3] as f32 / 255.0)
+            .sum();
+        let expected = std::f32::consts::PI * 20.0 * 20.0;
+        assert!((area - expected).abs() / expected < 0.01, "{}", area);
+    }
+
+    #[test]
+    fn stroked_circle_is_a_ring() {
+        let mut renderer = renderer(40, 40);
+        renderer
+            .draw_ellipse(Ellipse::circle(Point::new(20.0, 20.0), 15.0), 4.0);
+        let image = renderer.image();
+
+        assert_eq!(image.pixel(20, 20), [255, 255, 255, 255]);
+        assert_eq!(image.pixel(20, 5), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(34, 20), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(20, 1), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn line_is_as_wide_as_its_stroke() {
+        let mut renderer = renderer(20, 20);
+        renderer.draw_line(Point::new(2.0, 10.0), Point::new(18.0, 10.0), 4.0);
+        let image = renderer.image();
+
+        for y in 8..12 {
+            assert_eq!(image.pixel(10, y), [255, 0, 0, 255], "row {}", y);
+        }
+        assert_eq!(image.pixel(10, 7), [255, 255, 255, 255]);
+        assert_eq!(image.pixel(10, 12), [255, 255, 255, 255]);
+        // Flat caps end exactly at the end points.
+        assert_eq!(image.pixel(1, 10), [255, 255, 255, 255]);
+        assert_eq!(image.pixel(18, 10), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn caps_extend_the_line() {
+        let line = |cap| {
+            let mut renderer = renderer(20, 20);
+            renderer.set_stroke_style(StrokeStyle {
+                start_cap: CapStyle::Flat,
+                end_cap: cap,
+            });
+            renderer.draw_line(
+                Point::new(2.0, 10.0),
+                Point::new(12.0, 10.0),
+                8.0,
+            );
+            renderer.image()
+        };
+        let red = |image: &Image, x, y| image.pixel(x, y) == [255, 0, 0, 255];
+
+        let flat = line(CapStyle::Flat);
+        assert!(!red(&flat, 12, 10));
+
+        let square = line(CapStyle::Square);
+        assert!(red(&square, 15, 7) && red(&square, 15, 12));
+        assert!(!red(&square, 16, 10));
+
+        let round = line(CapStyle::Round);
+        assert!(red(&round, 15, 10));
+        assert!(!red(&round, 15, 6) && !red(&round, 15, 13));
+
+        let triangle = line(CapStyle::Triangle);
+        assert!(red(&triangle, 13, 10));
+        assert!(!red(&triangle, 15, 10) && !red(&triangle, 13, 7));
+    }
+
+    #[test]
+    fn transform_moves_and_scales_shapes_and_strokes() {
+        let mut renderer = renderer(40, 40);
+        renderer.set_transform(
+            Matrix::scale(2.0, 2.0) * Matrix::translation(10.0, 10.0),
+        );
+        renderer.draw_line(Point::new(0.0, 0.0), Point::new(10.0, 0.0), 2.0);
+        let image = renderer.image();
+
+        // From (10, 10) to (30, 10) and 4 pixels wide.
+        assert_eq!(image.pixel(20, 8), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(20, 11), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(20, 13), [255, 255, 255, 255]);
+        assert_eq!(image.pixel(31, 10), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn rotated_line_points_the_right_way() {
+        let mut renderer = renderer(40, 40);
+        let center = Point::new(20.0, 20.0);
+        renderer.set_transform(Matrix::rotation(90.0, center));
+        renderer.draw_line(center, Point::new(20.0, 5.0), 2.0);
+        let image = renderer.image();
+
+        // Twelve o'clock turned a quarter clockwise is three o'clock.
+        assert_eq!(image.pixel(30, 19), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(20, 10), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn translucent_colors_blend_over_the_background() {
+        let mut renderer = renderer(4, 4);
+        renderer.set_brush_color(Color::rgba(0.0, 0.0, 0.0, 0.5));
+        renderer.fill_ellipse(Ellipse::circle(Point::new(2.0, 2.0), 10.0));
+
+        assert_eq!(renderer.image().pixel(1, 1), [128, 128, 128, 255]);
+    }
+
+    #[test]
+    fn shapes_outside_the_target_are_clipped() {
+        let mut renderer = renderer(4, 4);
+        renderer.fill_ellipse(Ellipse::circle(Point::new(-50.0, 2.0), 10.0));
+        renderer.draw_line(Point::new(0.0, 100.0), Point::new(4.0, 100.0), 2.0);
+        renderer.set_transform(Matrix::scale(0.0, 1.0));
+        renderer.fill_ellipse(Ellipse::circle(Point::new(2.0, 2.0), 10.0));
+
+        assert!(renderer
+            .image()
+            .as_rgba()
+            .chunks_exact(4)
+            .all(|pixel| pixel == [255, 255, 255, 255]));
+    }
+}
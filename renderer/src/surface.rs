@@ -0,0 +1,117 @@
+use crate::{
+    error::Error,
+    primitives::Size,
+    recording::{Command, RecordingRenderer},
+    renderer::Renderer,
+};
+
+/// What a window draws its frames to, like a Direct2D render target for its
+/// client area.
+pub trait Surface {
+    /// The client area is now `width` by `height` pixels. Fails with
+    /// [`Error::DeviceLost`] if the device went away while resizing.
+    fn resize(&mut self, width: u32, height: u32) -> Result<(), Error>;
+
+    /// Draws a frame with `draw` and presents it, creating whatever the
+    /// surface needs for that first.
+    fn draw(
+        &mut self,
+        draw: &mut dyn FnMut(&mut dyn Renderer),
+    ) -> Result<(), Error>;
+
+    /// Whether other windows cover the surface completely, so the frames
+    /// drawn to it aren't seen. Every call checks again.
+    fn is_occluded(&mut self) -> bool {
+        false
+    }
+}
+
+/// A surface that keeps the commands of every frame drawn to it, for
+/// running windows without a GPU.
+#[derive(Clone, Debug, Default)]
+pub struct RecordingSurface {
+    size: Size,
+    frames: Vec<Vec<Command>>,
+    occluded: bool,
+}
+
+impl RecordingSurface {
+    /// A surface for a client area of `width` by `height` pixels, which
+    /// are also its device independent pixels.
+    pub fn new(width: u32, height: u32) -> Self {
+        RecordingSurface {
+            size: Size::new(width as f32, height as f32),
+            frames: Vec::new(),
+            occluded: false,
+        }
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// The frames drawn so far, oldest first.
+    pub fn frames(&self) -> &[Vec<Command>] {
+        &self.frames
+    }
+
+    pub fn last_frame(&self) -> Option<&[Command]> {
+        self.frames.last().map(Vec::as_slice)
+    }
+
+    /// Has the surface report that it is covered, or no longer is. Frames
+    /// drawn while it is covered are still kept.
+    pub fn set_occluded(&mut self, occluded: bool) {
+        self.occluded = occluded;
+    }
+}
+
+impl Surface for RecordingSurface {
+    fn resize(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        self.size = Size::new(width as f32, height as f32);
+        Ok(())
+    }
+
+    fn draw(
+        &mut self,
+        draw: &mut dyn FnMut(&mut dyn Renderer),
+    ) -> Result<(), Error> {
+        let mut renderer = RecordingRenderer::new(self.size);
+        draw(&mut renderer);
+        self.frames.push(renderer.take_commands());
+        Ok(())
+    }
+
+    fn is_occluded(&mut self) -> bool {
+        self.occluded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+
+    #[test]
+    fn keeps_every_frame_at_its_size() {
+        let mut surface = RecordingSurface::new(640, 480);
+        let mut clear = |renderer: &mut dyn Renderer| {
+            let size = renderer.size();
+            renderer.clear(Color::rgb(size.width / 1000.0, 0.0, 0.0));
+        };
+
+        surface.draw(&mut clear).unwrap();
+        surface.resize(320, 200).unwrap();
+        surface.draw(&mut clear).unwrap();
+
+        assert_eq!(surface.size(), Size::new(320.0, 200.0));
+        assert_eq!(
+            surface.frames(),
+            [
+                vec![Command::Clear(Color::rgb(0.64, 0.0, 0.0))],
+                vec![Command::Clear(Color::rgb(0.32, 0.0, 0.0))],
+            ]
+        );
+        assert_eq!(surface.last_frame().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,413 @@
+use std::fmt::{self, Write};
+
+use clock_core::{Matrix, Point};
+
+use crate::{
+    primitives::{CapStyle, Color, Ellipse, Size, StrokeStyle},
+    recording::{Command, RecordingRenderer},
+    renderer::Renderer,
+};
+
+impl RecordingRenderer {
+    /// The commands recorded so far as a standalone SVG document.
+    pub fn to_svg(&self) -> String {
+        to_svg(self.size(), self.commands())
+    }
+}
+
+/// Draws `commands` into a standalone SVG document of the given size.
+///
+/// Shapes drawn under the same transform are grouped in a `<g>` with that
+/// transform, so strokes scale with it as they do in Direct2D. SVG has no
+/// triangle caps and can't give a line different caps at either end, so
+/// those are drawn as separate shapes on a line with flat ends.
+pub fn to_svg(size: Size, commands: &[Command]) -> String {
+    let mut writer = SvgWriter {
+        svg: String::new(),
+        color: Color::BLACK,
+        stroke_style: StrokeStyle::default(),
+        transform: Matrix::identity(),
+    };
+    // Writing to a String can't fail.
+    writer.write(size, commands).unwrap();
+    writer.svg
+}
+
+struct SvgWriter {
+    svg: String,
+    color: Color,
+    stroke_style: StrokeStyle,
+    transform: Matrix,
+}
+
+impl SvgWriter {
+    fn write(&mut self, size: Size, commands: &[Command]) -> fmt::Result {
+        writeln!(
+            self.svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = size.width,
+            h = size.height
+        )?;
+
+        for command in commands {
+            match *command {
+                Command::Clear(color) => {
+                    // Clearing covers everything drawn before, and isn't
+                    // transformed.
+                    self.svg.truncate(self.svg.find('\n').unwrap() + 1);
+                    write!(
+                        self.svg,
+                        r#"<rect width="{}" height="{}"{}/>"#,
+                        size.width,
+                        size.height,
+                        Paint::Fill(color)
+                    )?;
+                    self.svg.push('\n');
+                    self.open_group()?;
+                }
+                Command::SetBrushColor(color) => self.color = color,
+                Command::SetStrokeStyle(style) => self.stroke_style = style,
+                Command::SetTransform(transform) => {
+                    self.close_group();
+                    self.transform = transform;
+                    self.open_group()?;
+                }
+                Command::FillEllipse(ellipse) => {
+                    self.ellipse(ellipse, Paint::Fill(self.color))?
+                }
+                Command::DrawEllipse {
+                    ellipse,
+                    stroke_width,
+                } => self.ellipse(
+                    ellipse,
+                    Paint::Stroke(self.color, stroke_width, CapStyle::Flat),
+                )?,
+                Command::DrawLine {
+                    from,
+                    to,
+                    stroke_width,
+                } => self.line(from, to, stroke_width)?,
+            }
+        }
+
+        self.close_group();
+        self.svg.push_str("</svg>\n");
+        Ok(())
+    }
+
+    fn open_group(&mut self) -> fmt::Result {
+        let m = self.transform;
+        if m != Matrix::identity() {
+            writeln!(
+                self.svg,
+                r#"<g transform="matrix({} {} {} {} {} {})">"#,
+                m.m11, m.m12, m.m21, m.m22, m.m31, m.m32
+            )?;
+        }
+        Ok(())
+    }
+
+    fn close_group(&mut self) {
+        if self.transform != Matrix::identity() {
+            self.svg.push_str("</g>\n");
+        }
+    }
+
+    fn ellipse(&mut self, ellipse: Ellipse, paint: Paint) -> fmt::Result {
+        writeln!(
+            self.svg,
+            r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}"{}/>"#,
+            ellipse.center.x,
+            ellipse.center.y,
+            ellipse.radius_x,
+            ellipse.radius_y,
+            paint
+        )
+    }
+
+    fn line(&mut self, from: Point, to: Point, width: f32) -> fmt::Result {
+        let StrokeStyle { start_cap, end_cap } = self.stroke_style;
+        let cap = if start_cap == end_cap && start_cap != CapStyle::Triangle {
+            start_cap
+        } else {
+            CapStyle::Flat
+        };
+
+        // Overlapping translucent shapes would show where they overlap, so
+        // a line drawn as several is made translucent as a whole.
+        let shapes = cap == CapStyle::Flat
+            && (start_cap != CapStyle::Flat || end_cap != CapStyle::Flat);
+        let color = if shapes && self.color.a < 1.0 {
+            writeln!(self.svg, r#"<g opacity="{}">"#, self.color.a)?;
+            Color {
+                a: 1.0,
+                ..self.color
+            }
+        } else {
+            self.color
+        };
+
+        writeln!(
+            self.svg,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}"{}/>"#,
+            from.x,
+            from.y,
+            to.x,
+            to.y,
+            Paint::Stroke(color, width, cap)
+        )?;
+
+        if shapes {
+            self.cap(to, from, start_cap, width, color)?;
+            self.cap(from, to, end_cap, width, color)?;
+            if color != self.color {
+                self.svg.push_str("</g>\n");
+            }
+        }
+        Ok(())
+    }
+
+    // Draws the cap at `end` of a line coming from `start`.
+    fn cap(
+        &mut self,
+        start: Point,
+        end: Point,
+        cap: CapStyle,
+        width: f32,
+        color: Color,
+    ) -> fmt::Result {
+        let length = start.distance(end);
+        if length == 0.0 {
+            return Ok(());
+        }
+
+        let half = width / 2.0;
+        let (dx, dy) = (
+            (end.x - start.x) / length * half,
+            (end.y - start.y) / length * half,
+        );
+        let left = Point::new(end.x - dy, end.y + dx);
+        let right = Point::new(end.x + dy, end.y - dx);
+        let fill = Paint::Fill(color);
+
+        match cap {
+            CapStyle::Flat => Ok(()),
+            CapStyle::Round => writeln!(
+                self.svg,
+                r#"<circle cx="{}" cy="{}" r="{}"{}/>"#,
+                end.x, end.y, half, fill
+            ),
+            CapStyle::Square => writeln!(
+                self.svg,
+                r#"<polygon points="{} {} {} {} {} {} {} {}"{}/>"#,
+                left.x,
+                left.y,
+                left.x + dx,
+                left.y + dy,
+                right.x + dx,
+                right.y + dy,
+                right.x,
+                right.y,
+                fill
+            ),
+            CapStyle::Triangle => writeln!(
+                self.svg,
+                r#"<polygon points="{} {} {} {} {} {}"{}/>"#,
+                left.x,
+                left.y,
+                end.x + dx,
+                end.y + dy,
+                right.x,
+                right.y,
+                fill
+            ),
+        }
+    }
+}
+
+// The attributes that paint a shape.
+enum Paint {
+    Fill(Color),
+    Stroke(Color, f32, CapStyle),
+}
+
+impl fmt::Display for Paint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Paint::Fill(color) => {
+                write!(f, r#" fill="{}""#, Hex(color))?;
+                if color.a < 1.0 {
+                    write!(f, r#" fill-opacity="{}""#, color.a)?;
+                }
+            }
+            Paint::Stroke(color, width, cap) => {
+                write!(
+                    f,
+                    r#" fill="none" stroke="{}" stroke-width="{}""#,
+                    Hex(color),
+                    width
+                )?;
+                if color.a < 1.0 {
+                    write!(f, r#" stroke-opacity="{}""#, color.a)?;
+                }
+                match cap {
+                    CapStyle::Square => {
+                        f.write_str(r#" stroke-linecap="square""#)?
+                    }
+                    CapStyle::Round => {
+                        f.write_str(r#" stroke-linecap="round""#)?
+                    }
+                    CapStyle::Flat | CapStyle::Triangle => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Hex(Color);
+
+impl fmt::Display for Hex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let byte =
+            |component: f32| (component.clamp(0.0, 1.0) * 255.0).round() as u8;
+        write!(
+            f,
+            "#{:02x}{:02x}{:02x}",
+            byte(self.0.r),
+            byte(self.0.g),
+            byte(self.0.b)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn svg(draw: impl FnOnce(&mut RecordingRenderer)) -> String {
+        let mut renderer = RecordingRenderer::new(Size::new(100.0, 50.0));
+        draw(&mut renderer);
+        renderer.to_svg()
+    }
+
+    #[test]
+    fn empty_document() {
+        assert_eq!(
+            svg(|_| {}),
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"100\" \
+             height=\"50\" viewBox=\"0 0 100 50\">\n</svg>\n"
+        );
+    }
+
+    #[test]
+    fn ellipses_and_colors() {
+        let svg = svg(|renderer| {
+            renderer.set_brush_color(Color::rgba(1.0, 0.5, 0.0, 0.25));
+            renderer.fill_ellipse(Ellipse::circle(Point::new(10.0, 20.0), 5.0));
+            renderer.set_brush_color(Color::BLACK);
+            renderer.draw_ellipse(
+                Ellipse {
+                    center: Point::new(1.5, 2.0),
+                    radius_x: 3.0,
+                    radius_y: 4.0,
+                },
+                2.0,
+            );
+        });
+
+        assert!(svg.contains(
+            r##"<ellipse cx="10" cy="20" rx="5" ry="5" fill="#ff8000" fill-opacity="0.25"/>"##
+        ));
+        assert!(svg.contains(
+            r##"<ellipse cx="1.5" cy="2" rx="3" ry="4" fill="none" stroke="#000000" stroke-width="2"/>"##
+        ));
+    }
+
+    #[test]
+    fn clear_replaces_what_was_drawn() {
+        let svg = svg(|renderer| {
+            renderer.fill_ellipse(Ellipse::circle(Point::default(), 5.0));
+            renderer.clear(Color::WHITE);
+        });
+
+        assert!(!svg.contains("<ellipse"));
+        assert!(
+            svg.contains(r##"<rect width="100" height="50" fill="#ffffff"/>"##)
+        );
+    }
+
+    #[test]
+    fn shapes_are_grouped_by_transform() {
+        let svg = svg(|renderer| {
+            renderer.set_transform(Matrix::translation(10.0, 20.0));
+            renderer.draw_line(Point::default(), Point::new(0.0, -5.0), 1.0);
+            renderer.draw_line(Point::default(), Point::new(5.0, 0.0), 1.0);
+            renderer.set_transform(Matrix::identity());
+            renderer.fill_ellipse(Ellipse::circle(Point::default(), 5.0));
+        });
+
+        let group = svg.find(r#"<g transform="matrix(1 0 0 1 10 20)">"#);
+        let end = svg.find("</g>");
+        let ellipse = svg.find("<ellipse");
+        assert!(group < end && end < ellipse, "{}", svg);
+        assert_eq!(svg.matches("<line").count(), 2);
+        assert_eq!(svg.matches("<g ").count(), 1);
+    }
+
+    #[test]
+    fn matching_round_or_square_caps_use_linecap() {
+        let svg = svg(|renderer| {
+            renderer.set_stroke_style(StrokeStyle {
+                start_cap: CapStyle::Round,
+                end_cap: CapStyle::Round,
+            });
+            renderer.draw_line(Point::default(), Point::new(10.0, 0.0), 4.0);
+        });
+
+        assert!(svg.contains(r#"stroke-linecap="round""#));
+        assert!(!svg.contains("<circle"));
+    }
+
+    #[test]
+    fn other_caps_are_drawn_as_shapes() {
+        let svg = svg(|renderer| {
+            renderer.set_stroke_style(StrokeStyle {
+                start_cap: CapStyle::Round,
+                end_cap: CapStyle::Triangle,
+            });
+            renderer.draw_line(Point::default(), Point::new(10.0, 0.0), 4.0);
+        });
+
+        assert!(!svg.contains("stroke-linecap"));
+        assert!(
+            svg.contains(r##"<circle cx="0" cy="0" r="2" fill="#000000"/>"##)
+        );
+        assert!(svg.contains(
+            r##"<polygon points="10 2 12 0 10 -2" fill="#000000"/>"##
+        ));
+    }
+
+    #[test]
+    fn translucent_lines_with_cap_shapes_are_grouped() {
+        let svg = svg(|renderer| {
+            renderer.set_brush_color(Color::rgba(1.0, 0.0, 0.0, 0.5));
+            renderer.set_stroke_style(StrokeStyle {
+                start_cap: CapStyle::Flat,
+                end_cap: CapStyle::Triangle,
+            });
+            renderer.draw_line(Point::default(), Point::new(10.0, 0.0), 4.0);
+        });
+
+        assert!(svg.contains("<g opacity=\"0.5\">\n<line"), "{}", svg);
+        assert!(!svg.contains("-opacity"), "{}", svg);
+        assert!(
+            svg.contains(
+                r##"fill="#ff0000"/>
+</g>"##
+            ),
+            "{}",
+            svg
+        );
+    }
+}
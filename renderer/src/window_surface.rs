@@ -0,0 +1,235 @@
+use win32_common::WindowHost;
+
+use crate::{Error, Surface};
+
+/// How many seconds a window that failed to draw waits before trying again,
+/// unless it is painted before.
+pub const RETRY_DELAY: f64 = 1.0;
+
+/// Creates the surface of a window once it is needed.
+pub type CreateSurface<S> = Box<dyn FnMut(&dyn WindowHost) -> Result<S, Error>>;
+
+/// The surface of a window that draws continuously, created when it is first
+/// needed, and the error that kept the window from drawing.
+///
+/// A window that fails to create its surface or to draw keeps the error
+/// rather than giving up, and tries again after [`RETRY_DELAY`]. Losing the
+/// device isn't kept, since the surface recovers from it with the next frame.
+pub struct WindowSurface<S> {
+    create: CreateSurface<S>,
+    surface: Option<S>,
+    error: Option<Error>,
+    retry_at: f64,
+}
+
+impl<S> WindowSurface<S> {
+    /// A surface that `create` creates once it is needed.
+    pub fn new<F>(create: F) -> Self
+    where
+        F: FnMut(&dyn WindowHost) -> Result<S, Error> + 'static,
+    {
+        WindowSurface {
+            create: Box::new(create),
+            surface: None,
+            error: None,
+            retry_at: 0.0,
+        }
+    }
+
+    pub fn get(&self) -> Option<&S> {
+        self.surface.as_ref()
+    }
+
+    pub fn get_mut(&mut self) -> Option<&mut S> {
+        self.surface.as_mut()
+    }
+
+    /// Releases the surface, which is created again once it is needed.
+    pub fn release(&mut self) {
+        self.surface = None;
+    }
+
+    /// Why the last frame wasn't drawn, unless the device was lost.
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+
+    /// How many seconds are left until a window that failed tries again, or
+    /// `None` if it may draw.
+    pub fn retry_in(&self, host: &dyn WindowHost) -> Option<f64> {
+        let now = host.now();
+        match self.error {
+            Some(_) if now < self.retry_at => Some(self.retry_at - now),
+            _ => None,
+        }
+    }
+
+    /// Creates the surface for `host` unless there is one already, and
+    /// returns it. Failing to create it is kept like [`fail`] does.
+    ///
+    /// [`fail`]: WindowSurface::fail
+    pub fn create(&mut self, host: &dyn WindowHost) -> Option<&mut S> {
+        if self.surface.is_none() {
+            match (self.create)(host) {
+                Ok(surface) => self.surface = Some(surface),
+                Err(error) => self.fail(host, error),
+            }
+        }
+        self.surface.as_mut()
+    }
+
+    /// Keeps how drawing a frame went. When the device was lost the window
+    /// is invalidated, since the surface is created again for the next frame.
+    pub fn presented(
+        &mut self,
+        host: &mut dyn WindowHost,
+        presented: Result<(), Error>,
+    ) {
+        match presented {
+            Ok(()) => self.error = None,
+            Err(Error::DeviceLost) => host.invalidate(),
+            Err(error) => self.fail(host, error),
+        }
+    }
+
+    /// Keeps `error`, for the window to show through [`error`], and waits
+    /// [`RETRY_DELAY`] before trying again.
+    ///
+    /// [`error`]: WindowSurface::error
+    pub fn fail(&mut self, host: &dyn WindowHost, error: Error) {
+        self.error = Some(error);
+        self.retry_at = host.now() + RETRY_DELAY;
+    }
+}
+
+impl<S: Surface> WindowSurface<S> {
+    /// Resizes the surface, if there is one, to the client area of `host`.
+    /// Failing to is kept like a frame that failed, see [`presented`].
+    ///
+    /// [`presented`]: WindowSurface::presented
+    pub fn resize(&mut self, host: &mut dyn WindowHost) {
+        let (width, height) = host.client_size();
+        if let Some(surface) = &mut self.surface {
+            if let Err(error) = surface.resize(width, height) {
+                self.presented(host, Err(error));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use win32_common::replay::VirtualWindow;
+
+    use super::*;
+    use crate::{FaultInjectingSurface, Renderer, ResourceKind, Surface};
+
+    fn draw(surface: &mut FaultInjectingSurface) -> Result<(), Error> {
+        surface.draw(&mut |_: &mut dyn Renderer| {})
+    }
+
+    #[test]
+    fn failing_waits_before_trying_again() {
+        let mut surface =
+            WindowSurface::new(|_: &dyn WindowHost| {
+                Ok(FaultInjectingSurface::new(10, 10)
+                    .with_creation_failing_at([0]))
+            });
+        let mut host = VirtualWindow {
+            time: 2.0,
+            ..VirtualWindow::default()
+        };
+        assert_eq!(surface.retry_in(&host), None);
+
+        let presented = draw(surface.create(&host).unwrap());
+        surface.presented(&mut host, presented);
+        assert!(matches!(
+            surface.error(),
+            Some(Error::ResourceCreation(ResourceKind::Target, _))
+        ));
+        host.time = 2.25;
+        assert_eq!(surface.retry_in(&host), Some(RETRY_DELAY - 0.25));
+        host.time = 2.0 + RETRY_DELAY;
+        assert_eq!(surface.retry_in(&host), None);
+
+        let presented = draw(surface.get_mut().unwrap());
+        surface.presented(&mut host, presented);
+        assert_eq!(surface.error(), None);
+        assert!(!host.invalidated);
+    }
+
+    #[test]
+    fn losing_the_device_asks_for_another_frame() {
+        let mut surface = WindowSurface::new(|_: &dyn WindowHost| {
+            Ok(FaultInjectingSurface::new(10, 10).with_device_lost_at([0]))
+        });
+        let mut host = VirtualWindow::default();
+
+        let presented = draw(surface.create(&host).unwrap());
+        surface.presented(&mut host, presented);
+        assert!(host.invalidated);
+        assert_eq!(surface.error(), None);
+        assert_eq!(surface.retry_in(&host), None);
+    }
+
+    // Fails to resize with `error`.
+    struct Stuck(Error);
+
+    impl Surface for Stuck {
+        fn resize(&mut self, _: u32, _: u32) -> Result<(), Error> {
+            Err(self.0.clone())
+        }
+
+        fn draw(
+            &mut self,
+            _: &mut dyn FnMut(&mut dyn Renderer),
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failing_to_resize_is_kept_unless_the_device_was_lost() {
+        let mut surface = WindowSurface::new(|_: &dyn WindowHost| {
+            Ok(Stuck(Error::Draw("out of memory".to_string())))
+        });
+        let mut host = VirtualWindow::default();
+        surface.create(&host);
+        surface.resize(&mut host);
+        assert_eq!(
+            surface.error(),
+            Some(&Error::Draw("out of memory".to_string()))
+        );
+        assert_eq!(surface.retry_in(&host), Some(RETRY_DELAY));
+
+        let mut surface = WindowSurface::new(|_: &dyn WindowHost| {
+            Ok(Stuck(Error::DeviceLost))
+        });
+        surface.create(&host);
+        surface.resize(&mut host);
+        assert_eq!(surface.error(), None);
+        assert!(host.invalidated);
+    }
+
+    #[test]
+    fn surfaces_are_created_again_once_released() {
+        let mut created = 0;
+        let mut surface = WindowSurface::new(move |_: &dyn WindowHost| {
+            created += 1;
+            match created {
+                1 => Err(Error::Draw("no adapter".to_string())),
+                _ => Ok(created),
+            }
+        });
+        let mut host = VirtualWindow::default();
+
+        assert_eq!(surface.create(&host), None);
+        host.time = 0.5;
+        assert_eq!(surface.retry_in(&host), Some(0.5));
+        assert_eq!(surface.create(&host).copied(), Some(2));
+        assert_eq!(surface.create(&host).copied(), Some(2));
+        surface.release();
+        assert_eq!(surface.get(), None);
+        assert_eq!(surface.create(&host).copied(), Some(3));
+    }
+}
@@ -0,0 +1,1071 @@
+//! Letting the user pick files or folders with the common item dialog.
+//!
+//! A [`FileDialog`] for opening and a [`SaveDialog`] for saving are
+//! builders like [`WindowBuilder`]: validating them and turning their
+//! options into dialog flags doesn't touch the operating system, so that
+//! part can be tested on Linux.
+//!
+//! The dialogs themselves, and message boxes, are shown by a [`Dialogs`]
+//! implementation: the common item dialog on Windows, [`TerminalDialogs`]
+//! anywhere else, or [`ScriptedDialogs`] with the answers a test prepared.
+//!
+//! [`WindowBuilder`]: crate::WindowBuilder
+//! [`TerminalDialogs`]: crate::TerminalDialogs
+//! [`ScriptedDialogs`]: crate::ScriptedDialogs
+
+use std::{
+    fmt, io,
+    ops::BitOr,
+    path::{Path, PathBuf},
+};
+
+#[cfg(windows)]
+use windows::{
+    core::{Interface, HRESULT},
+    Win32::{
+        Foundation::{ERROR_CANCELLED, E_INVALIDARG, HWND, PWSTR},
+        System::Com::{CoCreateInstance, CoTaskMemFree, CLSCTX_ALL},
+        UI::Shell::{
+            Common::COMDLG_FILTERSPEC, FileOpenDialog, FileSaveDialog,
+            IFileDialog, IFileOpenDialog, IFileSaveDialog, IShellItem,
+            SHCreateItemFromParsingName, SIGDN_FILESYSPATH,
+        },
+        UI::WindowsAndMessaging::{MessageBoxW, MESSAGEBOX_STYLE},
+    },
+};
+
+use crate::message_box::{DialogResult, MessageBoxOptions};
+#[cfg(windows)]
+use crate::wide::WideString;
+#[cfg(not(windows))]
+use crate::TerminalDialogs;
+
+/// Options of the common item dialog, mirroring the `FOS_*` constants.
+// https://docs.microsoft.com/en-us/windows/win32/api/shobjidl_core/ne-shobjidl_core-_fileopendialogoptions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DialogFlags(u32);
+
+impl DialogFlags {
+    /// Asks before overwriting an existing file when saving.
+    pub const OVERWRITEPROMPT: DialogFlags = DialogFlags(0x0000_0002);
+    /// Leaves the working directory of the process alone.
+    pub const NOCHANGEDIR: DialogFlags = DialogFlags(0x0000_0008);
+    /// Picks folders instead of files.
+    pub const PICKFOLDERS: DialogFlags = DialogFlags(0x0000_0020);
+    /// Only returns items that are files or folders on disk.
+    pub const FORCEFILESYSTEM: DialogFlags = DialogFlags(0x0000_0040);
+    /// Lets the user pick more than one item.
+    pub const ALLOWMULTISELECT: DialogFlags = DialogFlags(0x0000_0200);
+    /// The folder of the picked item has to exist.
+    pub const PATHMUSTEXIST: DialogFlags = DialogFlags(0x0000_0800);
+    /// The picked item has to exist.
+    pub const FILEMUSTEXIST: DialogFlags = DialogFlags(0x0000_1000);
+    /// The saved file can't be read-only or in a write-protected folder.
+    pub const NOREADONLYRETURN: DialogFlags = DialogFlags(0x0000_8000);
+
+    pub const fn empty() -> Self {
+        DialogFlags(0)
+    }
+
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    pub const fn contains(self, other: DialogFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for DialogFlags {
+    type Output = DialogFlags;
+
+    fn bitor(self, rhs: DialogFlags) -> DialogFlags {
+        DialogFlags(self.0 | rhs.0)
+    }
+}
+
+/// A named group of file name patterns the user can restrict the dialog
+/// to, like "Images" for `*.png` and `*.bmp`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Filter {
+    name: String,
+    patterns: Vec<String>,
+}
+
+impl Filter {
+    pub fn new(name: &str, patterns: &[&str]) -> Self {
+        Filter {
+            name: name.to_owned(),
+            patterns: patterns
+                .iter()
+                .map(|&pattern| pattern.to_owned())
+                .collect(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// The patterns separated by semicolons, the way the dialog takes them.
+    pub fn spec(&self) -> String {
+        self.patterns.join(";")
+    }
+
+    /// Whether the file name matches one of the patterns, where `*` stands
+    /// for any run of characters and `?` for any one. Case is ignored, like
+    /// Windows does.
+    pub fn matches(&self, file_name: &str) -> bool {
+        let name: Vec<char> = file_name.to_lowercase().chars().collect();
+        self.patterns.iter().any(|pattern| {
+            let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+            wildcard_match(&pattern, &name)
+        })
+    }
+
+    /// The extension of the first pattern that names one, like `png` for
+    /// `*.png`. Patterns like `*.*` don't.
+    pub fn extension(&self) -> Option<&str> {
+        self.patterns.iter().find_map(|pattern| {
+            let extension = pattern.strip_prefix("*.")?;
+            let is_plain =
+                !extension.is_empty() && !extension.contains(['*', '?', '.']);
+            is_plain.then_some(extension)
+        })
+    }
+
+    /// The path with the extension of this file type appended, unless its
+    /// file name already matches the filter. That is what "Save as type"
+    /// does to a name typed without the extension.
+    pub fn append_extension(&self, path: &Path) -> PathBuf {
+        let file_name = match path.file_name() {
+            Some(file_name) => file_name.to_string_lossy(),
+            None => return path.to_owned(),
+        };
+        match self.extension() {
+            Some(extension) if !self.matches(&file_name) => {
+                let mut appended = path.as_os_str().to_owned();
+                appended.push(".");
+                appended.push(extension);
+                PathBuf::from(appended)
+            }
+            _ => path.to_owned(),
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        !self.name.is_empty()
+            && !self.name.contains('\0')
+            && !self.patterns.is_empty()
+            && self.patterns.iter().all(|pattern| {
+                !pattern.is_empty()
+                    && !pattern.contains('\0')
+                    && !pattern.contains(';')
+            })
+    }
+}
+
+/// Reasons a [`FileDialog`] or [`SaveDialog`] didn't return any paths, or
+/// a message box didn't return a result.
+#[derive(Debug)]
+pub enum DialogError {
+    /// The user closed the dialog without picking anything.
+    Cancelled,
+    /// The title contains a NUL character, which would end it early.
+    TitleContainsNul,
+    /// The filter has no name or no patterns, or a pattern is empty or
+    /// contains a NUL character or a semicolon.
+    InvalidFilter(String),
+    /// The default extension is empty or contains a NUL character.
+    InvalidExtension(String),
+    /// The initial folder isn't an absolute path.
+    RelativeFolder(PathBuf),
+    /// The suggested file name is empty, or contains a NUL character or a
+    /// path separator.
+    InvalidFileName(String),
+    /// The file type to select isn't one of the filters.
+    InvalidFileType(usize),
+    /// The text or caption of a message box contains a NUL character.
+    MessageContainsNul,
+    /// The message box doesn't have as many buttons.
+    InvalidDefaultButton(usize),
+    /// Reading from or writing to the terminal failed.
+    Terminal(io::Error),
+    /// Creating or showing the dialog failed.
+    #[cfg(windows)]
+    Dialog(windows::core::Error),
+}
+
+impl DialogError {
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, DialogError::Cancelled)
+    }
+}
+
+impl fmt::Display for DialogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DialogError::Cancelled => write!(f, "the dialog was cancelled"),
+            DialogError::TitleContainsNul => {
+                write!(f, "dialog title contains a NUL character")
+            }
+            DialogError::InvalidFilter(name) => {
+                write!(f, "invalid file type filter {:?}", name)
+            }
+            DialogError::InvalidExtension(extension) => {
+                write!(f, "invalid default extension {:?}", extension)
+            }
+            DialogError::RelativeFolder(folder) => write!(
+                f,
+                "initial folder {} isn't an absolute path",
+                folder.display()
+            ),
+            DialogError::InvalidFileName(file_name) => {
+                write!(f, "invalid file name {:?}", file_name)
+            }
+            DialogError::InvalidFileType(index) => {
+                write!(f, "there is no file type filter {}", index)
+            }
+            DialogError::MessageContainsNul => {
+                write!(f, "message box text contains a NUL character")
+            }
+            DialogError::InvalidDefaultButton(index) => {
+                write!(f, "the message box has no button {}", index)
+            }
+            DialogError::Terminal(error) => {
+                write!(f, "failed to use the terminal: {}", error)
+            }
+            #[cfg(windows)]
+            DialogError::Dialog(error) => {
+                write!(f, "failed to show the dialog: {}", error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DialogError {}
+
+impl From<io::Error> for DialogError {
+    fn from(error: io::Error) -> Self {
+        DialogError::Terminal(error)
+    }
+}
+
+/// Shows file dialogs and message boxes and waits for the user's answer.
+pub trait Dialogs {
+    /// Lets the user pick files, or folders if the options have
+    /// [`DialogFlags::PICKFOLDERS`].
+    fn open(
+        &mut self,
+        options: &DialogOptions,
+    ) -> Result<Vec<PathBuf>, DialogError>;
+
+    /// Lets the user pick where to save a file and as which type.
+    fn save(
+        &mut self,
+        options: &DialogOptions,
+    ) -> Result<SaveChoice, DialogError>;
+
+    /// Shows a message and returns the button the user pressed, or
+    /// [`DialogError::Cancelled`] if they closed a box that can't be closed
+    /// without pressing one.
+    fn message_box(
+        &mut self,
+        options: &MessageBoxOptions,
+    ) -> Result<DialogResult, DialogError>;
+}
+
+/// The dialogs of the platform: the common item dialog on Windows, which
+/// needs COM to be initialized on the calling thread, and a
+/// [`TerminalDialogs`] on stdin and stdout anywhere else.
+///
+/// [`TerminalDialogs`]: crate::TerminalDialogs
+#[cfg(windows)]
+pub fn native_dialogs() -> Win32Dialogs {
+    Win32Dialogs
+}
+
+#[cfg(not(windows))]
+pub fn native_dialogs() -> TerminalDialogs<io::StdinLock<'static>, io::Stdout> {
+    TerminalDialogs::stdio()
+}
+
+#[cfg(windows)]
+impl From<DialogError> for windows::core::Error {
+    fn from(error: DialogError) -> Self {
+        match error {
+            DialogError::Cancelled => HRESULT::from(ERROR_CANCELLED).into(),
+            DialogError::Dialog(error) => error,
+            _ => E_INVALIDARG.into(),
+        }
+    }
+}
+
+/// The validated options a dialog is shown with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DialogOptions {
+    pub title: Option<String>,
+    pub filters: Vec<Filter>,
+    /// Without a leading dot.
+    pub default_extension: Option<String>,
+    pub folder: Option<PathBuf>,
+    /// The file name suggested when saving.
+    pub file_name: Option<String>,
+    /// The index of the filter that is selected first, if there are any.
+    pub file_type: Option<usize>,
+    pub flags: DialogFlags,
+}
+
+/// Shows the common item dialog for opening files, replacing the
+/// `IFileOpenDialog` calls module_2 used to spell out.
+#[derive(Clone, Debug, Default)]
+pub struct FileDialog {
+    title: Option<String>,
+    filters: Vec<Filter>,
+    default_extension: Option<String>,
+    folder: Option<PathBuf>,
+    multi_select: bool,
+    pick_folders: bool,
+}
+
+impl FileDialog {
+    pub fn new() -> Self {
+        FileDialog::default()
+    }
+
+    /// Replaces the title of the dialog, "Open" by default.
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_owned());
+        self
+    }
+
+    /// Adds a file type to the list the user picks from. The first one is
+    /// selected when the dialog opens; without any every file is shown.
+    pub fn filter(mut self, name: &str, patterns: &[&str]) -> Self {
+        self.filters.push(Filter::new(name, patterns));
+        self
+    }
+
+    /// The extension added to a file name the user typed without one, with
+    /// or without the leading dot.
+    pub fn default_extension(mut self, extension: &str) -> Self {
+        self.default_extension = Some(extension.to_owned());
+        self
+    }
+
+    /// The folder the dialog opens in, instead of the one it was last used
+    /// in.
+    pub fn folder(mut self, folder: impl AsRef<Path>) -> Self {
+        self.folder = Some(folder.as_ref().to_owned());
+        self
+    }
+
+    /// Lets the user pick several items at once.
+    pub fn multi_select(mut self, multi_select: bool) -> Self {
+        self.multi_select = multi_select;
+        self
+    }
+
+    /// Picks folders instead of files. Filters and the default extension
+    /// don't apply to folders and are left out.
+    pub fn pick_folders(mut self, pick_folders: bool) -> Self {
+        self.pick_folders = pick_folders;
+        self
+    }
+
+    /// Validates the builder and translates it into dialog flags.
+    pub fn options(&self) -> Result<DialogOptions, DialogError> {
+        // What the dialog does by default when opening, but only returning
+        // paths on disk.
+        let mut flags = DialogFlags::NOCHANGEDIR
+            | DialogFlags::FORCEFILESYSTEM
+            | DialogFlags::PATHMUSTEXIST
+            | DialogFlags::FILEMUSTEXIST;
+        if self.multi_select {
+            flags = flags | DialogFlags::ALLOWMULTISELECT;
+        }
+        if self.pick_folders {
+            flags = flags | DialogFlags::PICKFOLDERS;
+        }
+
+        let mut options = self.shared_options(flags)?;
+        if self.pick_folders {
+            options.filters.clear();
+            options.default_extension = None;
+            options.file_type = None;
+        }
+        Ok(options)
+    }
+
+    // Validates the options open and save dialogs have in common.
+    fn shared_options(
+        &self,
+        flags: DialogFlags,
+    ) -> Result<DialogOptions, DialogError> {
+        if self
+            .title
+            .as_ref()
+            .is_some_and(|title| title.contains('\0'))
+        {
+            return Err(DialogError::TitleContainsNul);
+        }
+
+        if let Some(filter) = self.filters.iter().find(|f| !f.is_valid()) {
+            return Err(DialogError::InvalidFilter(filter.name.clone()));
+        }
+
+        let default_extension = match &self.default_extension {
+            Some(extension) => {
+                let trimmed = extension.strip_prefix('.').unwrap_or(extension);
+                if trimmed.is_empty() || trimmed.contains('\0') {
+                    return Err(DialogError::InvalidExtension(
+                        extension.clone(),
+                    ));
+                }
+                Some(trimmed.to_owned())
+            }
+            None => None,
+        };
+
+        if let Some(folder) = &self.folder {
+            if !folder.is_absolute() {
+                return Err(DialogError::RelativeFolder(folder.clone()));
+            }
+        }
+
+        Ok(DialogOptions {
+            title: self.title.clone(),
+            filters: self.filters.clone(),
+            default_extension,
+            folder: self.folder.clone(),
+            file_name: None,
+            file_type: (!self.filters.is_empty()).then_some(0),
+            flags,
+        })
+    }
+}
+
+/// What the user picked in a [`SaveDialog`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SaveChoice {
+    /// Where to save, with the extension of the chosen file type.
+    pub path: PathBuf,
+    /// The index of the filter picked as "Save as type", if there are any.
+    pub file_type: Option<usize>,
+}
+
+impl SaveChoice {
+    /// The choice for `path` typed with `file_type` selected, with the
+    /// extension of that file type appended if `path` doesn't match it.
+    pub fn new(
+        options: &DialogOptions,
+        path: PathBuf,
+        file_type: Option<usize>,
+    ) -> Self {
+        let filter = file_type.and_then(|index| options.filters.get(index));
+        SaveChoice {
+            path: match filter {
+                Some(filter) => filter.append_extension(&path),
+                None => path,
+            },
+            file_type,
+        }
+    }
+}
+
+/// Shows the common item dialog for saving a file. It takes the title,
+/// filters, default extension and folder like a [`FileDialog`] does.
+#[derive(Clone, Debug)]
+pub struct SaveDialog {
+    dialog: FileDialog,
+    file_name: Option<String>,
+    file_type: Option<usize>,
+    overwrite_prompt: bool,
+}
+
+impl Default for SaveDialog {
+    fn default() -> Self {
+        SaveDialog {
+            dialog: FileDialog::default(),
+            file_name: None,
+            file_type: None,
+            overwrite_prompt: true,
+        }
+    }
+}
+
+impl SaveDialog {
+    pub fn new() -> Self {
+        SaveDialog::default()
+    }
+
+    /// Replaces the title of the dialog, "Save As" by default.
+    pub fn title(mut self, title: &str) -> Self {
+        self.dialog = self.dialog.title(title);
+        self
+    }
+
+    /// Adds a file type to the "Save as type" list. Its extension is
+    /// appended to a file name that doesn't match it.
+    pub fn filter(mut self, name: &str, patterns: &[&str]) -> Self {
+        self.dialog = self.dialog.filter(name, patterns);
+        self
+    }
+
+    /// The extension added to a file name typed without one while no file
+    /// type is selected, with or without the leading dot.
+    pub fn default_extension(mut self, extension: &str) -> Self {
+        self.dialog = self.dialog.default_extension(extension);
+        self
+    }
+
+    /// The folder the dialog opens in, instead of the one it was last used
+    /// in.
+    pub fn folder(mut self, folder: impl AsRef<Path>) -> Self {
+        self.dialog = self.dialog.folder(folder);
+        self
+    }
+
+    /// The file name filled in when the dialog opens.
+    pub fn file_name(mut self, file_name: &str) -> Self {
+        self.file_name = Some(file_name.to_owned());
+        self
+    }
+
+    /// The index of the filter selected as "Save as type" when the dialog
+    /// opens, the first one by default.
+    pub fn file_type(mut self, index: usize) -> Self {
+        self.file_type = Some(index);
+        self
+    }
+
+    /// Whether to ask before overwriting an existing file, which is on by
+    /// default.
+    pub fn overwrite_prompt(mut self, overwrite_prompt: bool) -> Self {
+        self.overwrite_prompt = overwrite_prompt;
+        self
+    }
+
+    /// Validates the builder and translates it into dialog flags.
+    pub fn options(&self) -> Result<DialogOptions, DialogError> {
+        // What the dialog does by default when saving, but only returning
+        // paths on disk.
+        let mut flags = DialogFlags::NOCHANGEDIR
+            | DialogFlags::FORCEFILESYSTEM
+            | DialogFlags::PATHMUSTEXIST
+            | DialogFlags::NOREADONLYRETURN;
+        if self.overwrite_prompt {
+            flags = flags | DialogFlags::OVERWRITEPROMPT;
+        }
+
+        let mut options = self.dialog.shared_options(flags)?;
+
+        if let Some(file_name) = &self.file_name {
+            let is_valid =
+                !file_name.is_empty() && !file_name.contains(['\0', '/', '\\']);
+            if !is_valid {
+                return Err(DialogError::InvalidFileName(file_name.clone()));
+            }
+            options.file_name = Some(file_name.clone());
+        }
+
+        if let Some(index) = self.file_type {
+            if index >= options.filters.len() {
+                return Err(DialogError::InvalidFileType(index));
+            }
+            options.file_type = Some(index);
+        }
+
+        Ok(options)
+    }
+}
+
+// Whether `name` matches `pattern` as a whole, with `*` matching any run of
+// characters and `?` any one. Backtracks to the last `*` on a mismatch.
+fn wildcard_match(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // The position after the last `*` and the name position it matched up
+    // to.
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((after_star, matched)) => {
+                    p = after_star;
+                    n = matched + 1;
+                    star = Some((after_star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+impl FileDialog {
+    /// Shows the dialog of the platform, see [`native_dialogs`], and waits
+    /// until the user closes it, returning the paths that were picked: one
+    /// unless [`FileDialog::multi_select`] is on.
+    pub fn show(&self) -> Result<Vec<PathBuf>, DialogError> {
+        self.show_with(&mut native_dialogs())
+    }
+
+    pub fn show_with(
+        &self,
+        dialogs: &mut dyn Dialogs,
+    ) -> Result<Vec<PathBuf>, DialogError> {
+        dialogs.open(&self.options()?)
+    }
+}
+
+impl SaveDialog {
+    /// Shows the dialog of the platform, see [`native_dialogs`], and waits
+    /// until the user closes it, returning where to save and as which
+    /// type.
+    pub fn show(&self) -> Result<SaveChoice, DialogError> {
+        self.show_with(&mut native_dialogs())
+    }
+
+    pub fn show_with(
+        &self,
+        dialogs: &mut dyn Dialogs,
+    ) -> Result<SaveChoice, DialogError> {
+        dialogs.save(&self.options()?)
+    }
+}
+
+/// The common item dialog.
+#[cfg(windows)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Win32Dialogs;
+
+#[cfg(windows)]
+impl Dialogs for Win32Dialogs {
+    fn open(
+        &mut self,
+        options: &DialogOptions,
+    ) -> Result<Vec<PathBuf>, DialogError> {
+        unsafe { show_open_dialog(options) }.map_err(dialog_error)
+    }
+
+    fn save(
+        &mut self,
+        options: &DialogOptions,
+    ) -> Result<SaveChoice, DialogError> {
+        let (path, file_type) =
+            unsafe { show_save_dialog(options) }.map_err(dialog_error)?;
+        Ok(SaveChoice::new(options, path, file_type))
+    }
+
+    fn message_box(
+        &mut self,
+        options: &MessageBoxOptions,
+    ) -> Result<DialogResult, DialogError> {
+        // Both strings were validated by `options`.
+        let text = WideString::new(&options.text).unwrap();
+        let caption = options
+            .caption
+            .as_deref()
+            .map(|caption| WideString::new(caption).unwrap());
+        let caption = match &caption {
+            Some(caption) => caption.as_pwstr(),
+            None => PWSTR(std::ptr::null_mut()),
+        };
+
+        // https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-messageboxw
+        let id = unsafe {
+            MessageBoxW(
+                HWND(options.owner),
+                text.as_pwstr(),
+                caption,
+                MESSAGEBOX_STYLE(options.style()),
+            )
+        };
+        // Zero if the box couldn't be shown.
+        DialogResult::from_id(id.0).ok_or_else(|| {
+            DialogError::Dialog(windows::core::Error::from_win32())
+        })
+    }
+}
+
+#[cfg(windows)]
+fn dialog_error(error: windows::core::Error) -> DialogError {
+    if error.code() == HRESULT::from(ERROR_CANCELLED) {
+        return DialogError::Cancelled;
+    }
+    DialogError::Dialog(error)
+}
+
+#[cfg(windows)]
+unsafe fn show_open_dialog(
+    options: &DialogOptions,
+) -> windows::core::Result<Vec<PathBuf>> {
+    // https://docs.microsoft.com/en-us/windows/win32/api/shobjidl_core/nn-shobjidl_core-ifileopendialog
+    let dialog: IFileOpenDialog =
+        CoCreateInstance(&FileOpenDialog, None, CLSCTX_ALL)?;
+    configure(&IFileDialog::from(&dialog), options)?;
+
+    // Launches the modal window.
+    dialog.Show(None)?;
+
+    let results = dialog.GetResults()?;
+    (0..results.GetCount()?)
+        .map(|index| item_path(&results.GetItemAt(index)?))
+        .collect()
+}
+
+#[cfg(windows)]
+unsafe fn show_save_dialog(
+    options: &DialogOptions,
+) -> windows::core::Result<(PathBuf, Option<usize>)> {
+    // https://docs.microsoft.com/en-us/windows/win32/api/shobjidl_core/nn-shobjidl_core-ifilesavedialog
+    let dialog: IFileSaveDialog =
+        CoCreateInstance(&FileSaveDialog, None, CLSCTX_ALL)?;
+    configure(&IFileDialog::from(&dialog), options)?;
+
+    // Launches the modal window.
+    dialog.Show(None)?;
+
+    let path = item_path(&dialog.GetResult()?)?;
+    // The file type indices of the dialog start at one.
+    let file_type = match options.file_type {
+        Some(_) => Some(dialog.GetFileTypeIndex()? as usize - 1),
+        None => None,
+    };
+    Ok((path, file_type))
+}
+
+// Applies the options both kinds of dialogs have in common.
+#[cfg(windows)]
+unsafe fn configure(
+    dialog: &IFileDialog,
+    options: &DialogOptions,
+) -> windows::core::Result<()> {
+    // Every string was validated by `options`.
+    let wide = |string: &str| WideString::new(string).unwrap();
+
+    dialog.SetOptions(options.flags.bits())?;
+
+    if let Some(title) = &options.title {
+        dialog.SetTitle(wide(title).as_pwstr())?;
+    }
+
+    // The dialog copies the filters, so the strings only have to outlive
+    // the call.
+    let filters: Vec<_> = options
+        .filters
+        .iter()
+        .map(|filter| (wide(filter.name()), wide(&filter.spec())))
+        .collect();
+    let specs: Vec<_> = filters
+        .iter()
+        .map(|(name, spec)| COMDLG_FILTERSPEC {
+            pszName: name.as_pwstr(),
+            pszSpec: spec.as_pwstr(),
+        })
+        .collect();
+    if !specs.is_empty() {
+        dialog.SetFileTypes(specs.len() as u32, specs.as_ptr())?;
+    }
+    if let Some(index) = options.file_type {
+        dialog.SetFileTypeIndex(index as u32 + 1)?;
+    }
+
+    if let Some(extension) = &options.default_extension {
+        dialog.SetDefaultExtension(wide(extension).as_pwstr())?;
+    }
+
+    if let Some(folder) = &options.folder {
+        dialog.SetFolder(shell_item(folder)?)?;
+    }
+
+    if let Some(file_name) = &options.file_name {
+        dialog.SetFileName(wide(file_name).as_pwstr())?;
+    }
+    Ok(())
+}
+
+// The shell item for a path on disk.
+#[cfg(windows)]
+unsafe fn shell_item(path: &Path) -> windows::core::Result<IShellItem> {
+    let path = WideString::from_os_str(path.as_os_str())
+        .map_err(|_| windows::core::Error::from(E_INVALIDARG))?;
+
+    // https://docs.microsoft.com/en-us/windows/win32/api/shobjidl_core/nf-shobjidl_core-shcreateitemfromparsingname
+    let mut item: Option<IShellItem> = None;
+    SHCreateItemFromParsingName(
+        path.as_pwstr(),
+        None,
+        &IShellItem::IID,
+        &mut item as *mut Option<IShellItem> as *mut _,
+    )?;
+    Ok(item.unwrap())
+}
+
+#[cfg(windows)]
+unsafe fn item_path(item: &IShellItem) -> windows::core::Result<PathBuf> {
+    // The name is allocated by the shell and has to be freed by the caller.
+    let name = item.GetDisplayName(SIGDN_FILESYSPATH)?;
+    let path = WideString::from_pwstr(name);
+    CoTaskMemFree(name.0 as *const _);
+    Ok(PathBuf::from(path.to_os_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn absolute_folder() -> PathBuf {
+        std::env::temp_dir()
+    }
+
+    #[test]
+    fn unset_options_open_a_single_existing_file() {
+        let options = FileDialog::new().options().unwrap();
+
+        assert_eq!(options.title, None);
+        assert!(options.filters.is_empty());
+        assert_eq!(options.default_extension, None);
+        assert_eq!(options.folder, None);
+        assert_eq!(
+            options.flags,
+            DialogFlags::NOCHANGEDIR
+                | DialogFlags::FORCEFILESYSTEM
+                | DialogFlags::PATHMUSTEXIST
+                | DialogFlags::FILEMUSTEXIST
+        );
+        assert_eq!(options.flags.bits(), 0x1848);
+    }
+
+    #[test]
+    fn explicit_options_are_kept() {
+        let options = FileDialog::new()
+            .title("Open a trace")
+            .filter("Traces", &["*.trace", "*.log"])
+            .filter("All files", &["*.*"])
+            .default_extension(".trace")
+            .folder(absolute_folder())
+            .options()
+            .unwrap();
+
+        assert_eq!(options.title.as_deref(), Some("Open a trace"));
+        let specs: Vec<_> = options
+            .filters
+            .iter()
+            .map(|filter| (filter.name(), filter.spec()))
+            .collect();
+        assert_eq!(
+            specs,
+            [
+                ("Traces", "*.trace;*.log".to_owned()),
+                ("All files", "*.*".to_owned())
+            ]
+        );
+        assert_eq!(options.default_extension.as_deref(), Some("trace"));
+        assert_eq!(options.folder, Some(absolute_folder()));
+    }
+
+    #[test]
+    fn multi_select_and_folders_translate_to_flags() {
+        let flags = |dialog: FileDialog| dialog.options().unwrap().flags;
+
+        let multi = flags(FileDialog::new().multi_select(true));
+        assert!(multi.contains(DialogFlags::ALLOWMULTISELECT));
+        assert!(!multi.contains(DialogFlags::PICKFOLDERS));
+
+        let folders = flags(FileDialog::new().pick_folders(true));
+        assert!(folders.contains(DialogFlags::PICKFOLDERS));
+        assert!(!folders.contains(DialogFlags::ALLOWMULTISELECT));
+
+        let both =
+            flags(FileDialog::new().multi_select(true).pick_folders(true));
+        assert!(both.contains(
+            DialogFlags::ALLOWMULTISELECT | DialogFlags::PICKFOLDERS
+        ));
+        assert_eq!(DialogFlags::empty().bits(), 0);
+    }
+
+    #[test]
+    fn folder_pickers_leave_out_file_types() {
+        let options = FileDialog::new()
+            .filter("Images", &["*.png"])
+            .default_extension("png")
+            .pick_folders(true)
+            .options()
+            .unwrap();
+
+        assert!(options.filters.is_empty());
+        assert_eq!(options.default_extension, None);
+    }
+
+    #[test]
+    fn invalid_options_are_rejected() {
+        let error = FileDialog::new().title("O\0pen").options().unwrap_err();
+        assert!(matches!(error, DialogError::TitleContainsNul));
+
+        for filter in [
+            Filter::new("", &["*.png"]),
+            Filter::new("Images", &[]),
+            Filter::new("Images", &[""]),
+            Filter::new("Images", &["*.png;*.bmp"]),
+            Filter::new("Images", &["*.png\0"]),
+        ] {
+            let patterns: Vec<_> =
+                filter.patterns().iter().map(String::as_str).collect();
+            let error = FileDialog::new()
+                .filter(filter.name(), &patterns)
+                .options()
+                .unwrap_err();
+            assert!(matches!(
+                error,
+                DialogError::InvalidFilter(name) if name == filter.name()
+            ));
+        }
+
+        for extension in ["", ".", "p\0ng"] {
+            let error = FileDialog::new()
+                .default_extension(extension)
+                .options()
+                .unwrap_err();
+            assert!(matches!(
+                error,
+                DialogError::InvalidExtension(e) if e == extension
+            ));
+        }
+
+        let error = FileDialog::new().folder("traces").options().unwrap_err();
+        assert!(matches!(error, DialogError::RelativeFolder(_)));
+    }
+
+    #[test]
+    fn cancelling_is_told_apart_from_failing() {
+        assert!(DialogError::Cancelled.is_cancelled());
+        assert!(!DialogError::TitleContainsNul.is_cancelled());
+        assert_eq!(
+            DialogError::RelativeFolder(PathBuf::from("traces")).to_string(),
+            "initial folder traces isn't an absolute path"
+        );
+    }
+
+    #[test]
+    fn save_dialogs_prompt_before_overwriting() {
+        let options = SaveDialog::new().options().unwrap();
+        assert_eq!(
+            options.flags,
+            DialogFlags::NOCHANGEDIR
+                | DialogFlags::FORCEFILESYSTEM
+                | DialogFlags::PATHMUSTEXIST
+                | DialogFlags::NOREADONLYRETURN
+                | DialogFlags::OVERWRITEPROMPT
+        );
+        assert_eq!(options.file_type, None);
+
+        let quiet = SaveDialog::new().overwrite_prompt(false).options();
+        assert!(!quiet.unwrap().flags.contains(DialogFlags::OVERWRITEPROMPT));
+    }
+
+    #[test]
+    fn save_dialogs_share_the_open_dialog_options() {
+        let options = SaveDialog::new()
+            .title("Export the clock")
+            .filter("PNG images", &["*.png"])
+            .filter("Traces", &["*.trace"])
+            .default_extension("png")
+            .folder(absolute_folder())
+            .file_name("clock.png")
+            .file_type(1)
+            .options()
+            .unwrap();
+
+        assert_eq!(options.title.as_deref(), Some("Export the clock"));
+        assert_eq!(options.filters.len(), 2);
+        assert_eq!(options.default_extension.as_deref(), Some("png"));
+        assert_eq!(options.folder, Some(absolute_folder()));
+        assert_eq!(options.file_name.as_deref(), Some("clock.png"));
+        assert_eq!(options.file_type, Some(1));
+
+        let error = SaveDialog::new().title("\0").options().unwrap_err();
+        assert!(matches!(error, DialogError::TitleContainsNul));
+    }
+
+    #[test]
+    fn invalid_save_options_are_rejected() {
+        for file_name in ["", "traces/clock.png", "clock\\.png", "a\0b"] {
+            let error = SaveDialog::new()
+                .file_name(file_name)
+                .options()
+                .unwrap_err();
+            assert!(matches!(
+                error,
+                DialogError::InvalidFileName(name) if name == file_name
+            ));
+        }
+
+        let error = SaveDialog::new()
+            .filter("PNG images", &["*.png"])
+            .file_type(1)
+            .options()
+            .unwrap_err();
+        assert!(matches!(error, DialogError::InvalidFileType(1)));
+        let error = SaveDialog::new().file_type(0).options().unwrap_err();
+        assert!(matches!(error, DialogError::InvalidFileType(0)));
+    }
+
+    #[test]
+    fn filters_match_file_names_like_windows() {
+        let images = Filter::new("Images", &["*.png", "clock_??.bmp"]);
+
+        assert!(images.matches("clock.png"));
+        assert!(images.matches("CLOCK.PNG"));
+        assert!(images.matches("clock_01.bmp"));
+        assert!(images.matches(".png"));
+        assert!(!images.matches("clock.png.bak"));
+        assert!(!images.matches("clock_1.bmp"));
+        assert!(!images.matches("clock"));
+        assert!(Filter::new("All files", &["*"]).matches("anything"));
+        assert!(Filter::new("Logs", &["*a*b*.log"]).matches("xaxxbyb.log"));
+    }
+
+    #[test]
+    fn file_types_name_their_extension() {
+        let extension = |patterns: &[&str]| {
+            Filter::new("f", patterns).extension().map(str::to_owned)
+        };
+
+        assert_eq!(extension(&["*.png", "*.bmp"]).as_deref(), Some("png"));
+        assert_eq!(extension(&["*.*", "*.bmp"]).as_deref(), Some("bmp"));
+        assert_eq!(extension(&["*.tar.gz"]), None);
+        assert_eq!(extension(&["*"]), None);
+        assert_eq!(extension(&["clock.png"]), None);
+    }
+
+    #[test]
+    fn save_as_type_appends_its_extension() {
+        let options = SaveDialog::new()
+            .filter("PNG images", &["*.png"])
+            .filter("All files", &["*.*"])
+            .options()
+            .unwrap();
+        let choice = |path: &str, file_type| {
+            SaveChoice::new(&options, PathBuf::from(path), file_type).path
+        };
+
+        assert_eq!(choice("out/clock", Some(0)), Path::new("out/clock.png"));
+        assert_eq!(choice("clock.png", Some(0)), Path::new("clock.png"));
+        assert_eq!(choice("clock.v2", Some(0)), Path::new("clock.v2.png"));
+        assert_eq!(choice("clock", Some(1)), Path::new("clock"));
+        assert_eq!(choice("clock", None), Path::new("clock"));
+    }
+}
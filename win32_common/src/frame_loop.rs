@@ -0,0 +1,71 @@
+//! Message loops of windows that draw frames of their own between messages,
+//! rather than only when they are painted.
+//!
+//! Once the pending messages are handled such a loop gives the window a
+//! turn through [`FrameHandler::frame`], which draws the next frame if it is
+//! due and says how long the loop may [`Wait`] for messages. On Windows
+//! [`run_frame_loop`] runs the loop of a real window, and
+//! [`HeadlessHost::run_for`] takes the same turns without Win32.
+//!
+//! [`HeadlessHost::run_for`]: crate::HeadlessHost::run_for
+
+use crate::handler::{WindowHandler, WindowHost};
+
+/// How long a message loop may sleep after its window had its turn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Wait {
+    /// Until a message arrives or this many seconds have passed, whichever
+    /// comes first. Zero doesn't sleep at all.
+    For(f64),
+    /// Until a message arrives, for a window that has nothing to draw.
+    ForMessage,
+}
+
+/// A [`WindowHandler`] whose window draws frames between messages.
+pub trait FrameHandler: WindowHandler {
+    /// Draws the next frame if it is due, and says how long the loop may
+    /// wait for messages before the window has its next turn.
+    fn frame(&mut self, host: &mut dyn WindowHost) -> Wait;
+}
+
+/// Runs the message loop of `window`, whose state is a `H` created with
+/// [`WindowState::<H>::create_param`], until the window is destroyed or
+/// `WM_QUIT` is posted.
+///
+/// [`WindowState::<H>::create_param`]: crate::WindowState::create_param
+#[cfg(windows)]
+pub fn run_frame_loop<H: FrameHandler>(
+    window: windows::Win32::Foundation::HWND,
+) -> windows::core::Result<()> {
+    use std::time::Duration;
+
+    use crate::{
+        handler::Win32Host,
+        message_loop::{pump_messages, wait_for_messages, wait_message},
+        state::{Win32UserData, WindowState},
+    };
+
+    loop {
+        // Once the window has been destroyed its handler is gone as well.
+        let handler =
+            match unsafe { WindowState::<H>::get(&Win32UserData, window) } {
+                Some(handler) => handler,
+                None => return Ok(()),
+            };
+
+        let running = match handler.frame(&mut Win32Host(window)) {
+            Wait::For(seconds) => {
+                if seconds > 0.0 {
+                    wait_for_messages(Duration::from_secs_f64(seconds))?;
+                }
+                pump_messages()
+            }
+            // `wait_message` blocks until the next message arrives, which
+            // avoids spinning while there is nothing to draw.
+            Wait::ForMessage => wait_message(),
+        };
+        if !running {
+            return Ok(());
+        }
+    }
+}
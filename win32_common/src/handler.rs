@@ -0,0 +1,192 @@
+//! Window procedures as plain objects that can be driven without Win32.
+//!
+//! A [`WindowHandler`] gets decoded messages and talks to its window only
+//! through a [`WindowHost`]. On Windows [`handler_wndproc`] is the window
+//! procedure that feeds it the messages of a real window; elsewhere the
+//! messages can come from a recording, see [`crate::replay`].
+
+use crate::message::Message;
+
+/// What a [`WindowHandler`] can ask of the window it handles messages for.
+pub trait WindowHost {
+    /// The handle of the window, `0` for windows that only exist in memory.
+    fn handle(&self) -> isize;
+
+    /// The width and height of the client area, in pixels.
+    fn client_size(&self) -> (u32, u32);
+
+    /// The dots per inch of the window, 96 at 100% scaling.
+    fn dpi(&self) -> u32;
+
+    /// Seconds on a monotonic clock.
+    fn now(&self) -> f64;
+
+    /// Has the whole client area painted again, like `InvalidateRect`.
+    fn invalidate(&mut self);
+
+    /// Marks the whole client area as painted, like `ValidateRect`.
+    fn validate(&mut self);
+
+    /// Ends the message loop with `exit_code`, like `PostQuitMessage`.
+    fn quit(&mut self, exit_code: i32);
+}
+
+/// Handles the messages of a window.
+pub trait WindowHandler {
+    /// Handles `message` and returns the result of the window procedure, or
+    /// `None` to leave the message to the default window procedure.
+    fn handle(
+        &mut self,
+        host: &mut dyn WindowHost,
+        message: Message,
+    ) -> Option<isize>;
+}
+
+/// Hands `message` to `handler` like a window does.
+///
+/// The client area is validated before `WM_PAINT` is handled rather than
+/// after, so a handler that can't draw the frame can invalidate it again
+/// to be painted once more.
+pub fn dispatch(
+    handler: &mut (impl WindowHandler + ?Sized),
+    host: &mut dyn WindowHost,
+    message: Message,
+) -> Option<isize> {
+    if message == Message::Paint {
+        host.validate();
+    }
+    handler.handle(host, message)
+}
+
+#[cfg(windows)]
+pub use win32::{handler_wndproc, Win32Host};
+
+#[cfg(windows)]
+mod win32 {
+    use std::{sync::OnceLock, time::Instant};
+
+    use windows::Win32::{
+        Foundation::{BOOL, HWND, LPARAM, LRESULT, RECT, WPARAM},
+        Graphics::Gdi::{InvalidateRect, ValidateRect},
+        UI::{
+            HiDpi::GetDpiForWindow,
+            WindowsAndMessaging::{
+                DefWindowProcW, GetClientRect, PostQuitMessage,
+            },
+        },
+    };
+
+    use super::{dispatch, WindowHandler, WindowHost};
+    use crate::{decode, trace, WindowState};
+
+    /// A real window.
+    pub struct Win32Host(pub HWND);
+
+    impl WindowHost for Win32Host {
+        fn handle(&self) -> isize {
+            self.0 .0
+        }
+
+        fn client_size(&self) -> (u32, u32) {
+            let mut rect = RECT::default();
+            unsafe { GetClientRect(self.0, &mut rect) };
+            (
+                (rect.right - rect.left).max(0) as u32,
+                (rect.bottom - rect.top).max(0) as u32,
+            )
+        }
+
+        fn dpi(&self) -> u32 {
+            match unsafe { GetDpiForWindow(self.0) } {
+                // Not a valid window.
+                0 => 96,
+                dpi => dpi,
+            }
+        }
+
+        fn now(&self) -> f64 {
+            // Every window shares the same clock.
+            static START: OnceLock<Instant> = OnceLock::new();
+            START.get_or_init(Instant::now).elapsed().as_secs_f64()
+        }
+
+        fn invalidate(&mut self) {
+            unsafe { InvalidateRect(self.0, std::ptr::null(), BOOL(0)) };
+        }
+
+        fn validate(&mut self) {
+            unsafe { ValidateRect(self.0, std::ptr::null()) };
+        }
+
+        fn quit(&mut self, exit_code: i32) {
+            unsafe { PostQuitMessage(exit_code) };
+        }
+    }
+
+    /// The window procedure of windows whose state is a `H`, created with
+    /// [`WindowState::<H>::create_param`].
+    ///
+    /// Messages are traced, see [`crate::trace`], and handed to the handler
+    /// with [`dispatch`], which validates the client area on `WM_PAINT`
+    /// since drawing with Direct2D doesn't call `BeginPaint` and `EndPaint`.
+    pub extern "system" fn handler_wndproc<H: WindowHandler>(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        trace::traced(window.0, message, wparam.0, lparam.0, || unsafe {
+            let handler =
+                match WindowState::<H>::lookup(window, message, lparam) {
+                    Some(handler) => handler,
+                    None => {
+                        return DefWindowProcW(window, message, wparam, lparam)
+                    }
+                };
+
+            let decoded = decode(message, wparam.0, lparam.0);
+            match dispatch(handler, &mut Win32Host(window), decoded) {
+                Some(result) => LRESULT(result),
+                None => DefWindowProcW(window, message, wparam, lparam),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::VirtualWindow;
+
+    // Asks for another frame whenever it is painted, like a window that
+    // lost its device.
+    struct Retrying;
+
+    impl WindowHandler for Retrying {
+        fn handle(
+            &mut self,
+            host: &mut dyn WindowHost,
+            message: Message,
+        ) -> Option<isize> {
+            if message == Message::Paint {
+                host.invalidate();
+            }
+            Some(0)
+        }
+    }
+
+    #[test]
+    fn painting_keeps_what_the_handler_invalidated() {
+        let mut window = VirtualWindow {
+            invalidated: true,
+            ..VirtualWindow::default()
+        };
+
+        dispatch(&mut Retrying, &mut window, Message::Paint);
+        assert!(window.invalidated);
+
+        window.invalidated = true;
+        dispatch(&mut Retrying, &mut window, Message::Create);
+        assert!(window.invalidated);
+    }
+}
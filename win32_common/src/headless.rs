@@ -0,0 +1,415 @@
+//! Running a [`WindowHandler`] through the life of a window without Win32.
+//!
+//! A [`HeadlessHost`] owns a handler and a [`VirtualWindow`] and sends the
+//! handler the messages Windows sends a window when it is created, resized,
+//! painted, activated, minimized and destroyed, in the order Windows sends
+//! them. Tests drive a window's whole lifecycle with it on any platform.
+//!
+//! Occlusion comes in two kinds: minimizing the window hides it from the
+//! handler's point of view, while DXGI only reports that other windows
+//! cover it when a frame is presented to a surface. The latter belongs to
+//! the surface, see `renderer::RecordingSurface::set_occluded`; the window
+//! learns that it is uncovered again from a message it registered for, which
+//! [`HeadlessHost::send`] can deliver.
+
+use crate::{
+    frame_loop::{FrameHandler, Wait},
+    handler::WindowHandler,
+    message::{Message, SizeKind},
+    replay::{replay, Recording, VirtualWindow},
+};
+
+/// The client size of a headless window unless it is given another one.
+pub const DEFAULT_CLIENT_SIZE: (u32, u32) = (640, 480);
+
+/// The shortest time [`HeadlessHost::run_for`] moves the clock on by between
+/// frames, so it can't spin forever on a window that never waits.
+pub const MIN_WAIT: f64 = 1e-3;
+
+/// A window without an operating system, sending the messages of typical
+/// sequences to the handler it owns.
+///
+/// Like Windows does once the message queue is empty, every sequence ends
+/// with painting the window if the handler invalidated it.
+pub struct HeadlessHost<H> {
+    handler: H,
+    window: VirtualWindow,
+    // The client size before minimizing.
+    restored_size: (u32, u32),
+    minimized: bool,
+    destroyed: bool,
+    messages: Vec<(Message, Option<isize>)>,
+}
+
+impl<H: WindowHandler> HeadlessHost<H> {
+    /// A window with a client area of [`DEFAULT_CLIENT_SIZE`] at 96 DPI that
+    /// is yet to be created.
+    pub fn new(handler: H) -> Self {
+        HeadlessHost {
+            handler,
+            window: VirtualWindow {
+                client_size: DEFAULT_CLIENT_SIZE,
+                ..VirtualWindow::default()
+            },
+            restored_size: DEFAULT_CLIENT_SIZE,
+            minimized: false,
+            destroyed: false,
+            messages: Vec::new(),
+        }
+    }
+
+    pub fn with_client_size(mut self, width: u32, height: u32) -> Self {
+        self.window.client_size = (width, height);
+        self.restored_size = (width, height);
+        self
+    }
+
+    pub fn with_dpi(mut self, dpi: u32) -> Self {
+        self.window.dpi = dpi;
+        self
+    }
+
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    pub fn handler_mut(&mut self) -> &mut H {
+        &mut self.handler
+    }
+
+    pub fn into_handler(self) -> H {
+        self.handler
+    }
+
+    pub fn window(&self) -> &VirtualWindow {
+        &self.window
+    }
+
+    /// Lends the handler together with its window, for calling handler
+    /// methods that take the window as their host.
+    pub fn with_handler<R>(
+        &mut self,
+        f: impl FnOnce(&mut H, &mut VirtualWindow) -> R,
+    ) -> R {
+        f(&mut self.handler, &mut self.window)
+    }
+
+    /// Every message sent so far, with what the handler returned for it.
+    pub fn messages(&self) -> &[(Message, Option<isize>)] {
+        &self.messages
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    pub fn is_destroyed(&self) -> bool {
+        self.destroyed
+    }
+
+    /// What the handler passed to `quit`, if it did.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.window.exit_code
+    }
+
+    /// Moves the clock of the window on by `seconds`.
+    pub fn advance(&mut self, seconds: f64) {
+        self.window.time += seconds;
+    }
+
+    /// Sends `message` to the handler, see [`VirtualWindow::deliver`].
+    ///
+    /// # Panics
+    ///
+    /// If the window was destroyed, since Windows drops the handler of a
+    /// window with the window.
+    pub fn send(&mut self, message: Message) -> Option<isize> {
+        assert!(!self.destroyed, "The window was destroyed");
+        let result = self.window.deliver(&mut self.handler, message);
+        self.messages.push((message, result));
+        result
+    }
+
+    /// Creates and shows the window: `WM_CREATE`, then `WM_SIZE`,
+    /// `WM_ACTIVATE` and `WM_PAINT` like `ShowWindow` and `UpdateWindow` do.
+    ///
+    /// Returns `false` without showing the window if the handler failed its
+    /// creation by returning `-1` for `WM_CREATE`, which destroys it.
+    pub fn create(&mut self) -> bool {
+        if self.send(Message::Create) == Some(-1) {
+            self.destroyed = true;
+            return false;
+        }
+
+        let (width, height) = self.window.client_size;
+        self.send(size(SizeKind::Restored, width, height));
+        self.send(Message::Activate {
+            active: true,
+            minimized: false,
+        });
+        self.paint();
+        true
+    }
+
+    /// Resizes the client area to `width` by `height` pixels.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.restored_size = (width, height);
+        self.send(size(SizeKind::Restored, width, height));
+        self.paint_if_invalidated();
+    }
+
+    pub fn paint(&mut self) -> Option<isize> {
+        self.send(Message::Paint)
+    }
+
+    /// Paints the window if it was invalidated since it was last painted.
+    pub fn paint_if_invalidated(&mut self) -> bool {
+        if !self.window.invalidated {
+            return false;
+        }
+        self.paint();
+        true
+    }
+
+    pub fn activate(&mut self) {
+        self.send(Message::Activate {
+            active: true,
+            minimized: false,
+        });
+        self.paint_if_invalidated();
+    }
+
+    pub fn deactivate(&mut self) {
+        self.send(Message::Activate {
+            active: false,
+            minimized: false,
+        });
+        self.paint_if_invalidated();
+    }
+
+    /// Minimizes the window, which deactivates it and shrinks its client
+    /// area to nothing. Windows doesn't paint minimized windows.
+    pub fn minimize(&mut self) {
+        self.minimized = true;
+        self.send(Message::Activate {
+            active: false,
+            minimized: true,
+        });
+        self.send(size(SizeKind::Minimized, 0, 0));
+    }
+
+    /// Restores a minimized window to its previous size and activates it.
+    /// All of it shows again, so all of it is painted.
+    pub fn restore(&mut self) {
+        self.minimized = false;
+        let (width, height) = self.restored_size;
+        self.send(size(SizeKind::Restored, width, height));
+        self.window.invalidated = true;
+        self.activate();
+    }
+
+    /// Asks the window to close. Left to the default window procedure,
+    /// closing destroys the window.
+    pub fn close(&mut self) {
+        if self.send(Message::Close).is_none() {
+            self.destroy();
+        }
+    }
+
+    /// Destroys the window. The handler is kept for inspection, but no
+    /// more messages can be sent to it.
+    pub fn destroy(&mut self) {
+        self.deactivate();
+        self.send(Message::Destroy);
+        self.destroyed = true;
+    }
+
+    /// Replays `recording` against the handler, see [`replay`].
+    pub fn replay(&mut self, recording: &Recording) -> Vec<Option<isize>> {
+        assert!(!self.destroyed, "The window was destroyed");
+        replay(recording, &mut self.handler, &mut self.window)
+    }
+}
+
+impl<H: FrameHandler> HeadlessHost<H> {
+    /// Runs the message loop of `run_frame_loop` for `seconds` of the
+    /// window's clock: the window has its turn, is painted if it was
+    /// invalidated, and the clock moves on by how long it may wait.
+    ///
+    /// Nothing sends the window messages while the loop runs, so waiting
+    /// for one lasts until the end.
+    pub fn run_for(&mut self, seconds: f64) {
+        let end = self.window.time + seconds;
+        while self.window.time < end && !self.destroyed {
+            let wait = self.handler.frame(&mut self.window);
+            self.paint_if_invalidated();
+            match wait {
+                Wait::For(seconds) => self.advance(seconds.max(MIN_WAIT)),
+                Wait::ForMessage => self.window.time = end,
+            }
+        }
+    }
+}
+
+fn size(kind: SizeKind, width: u32, height: u32) -> Message {
+    Message::Size {
+        kind,
+        width: width as u16,
+        height: height as u16,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler::WindowHost;
+
+    // Handles what a simple window handles, invalidating it on resizing.
+    #[derive(Default)]
+    struct Spy {
+        fail_creation: bool,
+        seen: Vec<Message>,
+        painted_sizes: Vec<(u32, u32)>,
+    }
+
+    impl WindowHandler for Spy {
+        fn handle(
+            &mut self,
+            host: &mut dyn WindowHost,
+            message: Message,
+        ) -> Option<isize> {
+            self.seen.push(message);
+            match message {
+                Message::Create if self.fail_creation => Some(-1),
+                Message::Size { .. } => {
+                    host.invalidate();
+                    Some(0)
+                }
+                Message::Paint => {
+                    self.painted_sizes.push(host.client_size());
+                    Some(0)
+                }
+                Message::Destroy => {
+                    host.quit(0);
+                    Some(0)
+                }
+                _ => None,
+            }
+        }
+    }
+
+    fn kinds(messages: &[Message]) -> Vec<&'static str> {
+        messages
+            .iter()
+            .map(|message| message.kind().name())
+            .collect()
+    }
+
+    #[test]
+    fn creating_shows_and_paints_the_window() {
+        let mut host = HeadlessHost::new(Spy::default())
+            .with_client_size(300, 200)
+            .with_dpi(144);
+        assert!(host.create());
+
+        assert_eq!(
+            kinds(&host.handler().seen),
+            ["create", "size", "activate", "paint"]
+        );
+        assert_eq!(host.handler().painted_sizes, [(300, 200)]);
+        assert_eq!(host.window().dpi, 144);
+        assert!(!host.window().invalidated);
+    }
+
+    #[test]
+    fn failing_creation_destroys_the_window() {
+        let mut host = HeadlessHost::new(Spy {
+            fail_creation: true,
+            ..Spy::default()
+        });
+
+        assert!(!host.create());
+        assert!(host.is_destroyed());
+        assert_eq!(kinds(&host.handler().seen), ["create"]);
+    }
+
+    #[test]
+    fn resizing_paints_what_was_invalidated() {
+        let mut host = HeadlessHost::new(Spy::default());
+        host.create();
+        host.resize(100, 50);
+        host.minimize();
+        host.restore();
+
+        assert_eq!(
+            host.handler().painted_sizes,
+            [DEFAULT_CLIENT_SIZE, (100, 50), (100, 50)]
+        );
+        let minimized = host.messages()[6..8].to_vec();
+        assert_eq!(
+            minimized,
+            [
+                (
+                    Message::Activate {
+                        active: false,
+                        minimized: true
+                    },
+                    None
+                ),
+                (size(SizeKind::Minimized, 0, 0), Some(0)),
+            ]
+        );
+        assert!(!host.is_minimized());
+    }
+
+    #[test]
+    fn closing_destroys_the_window() {
+        let mut host = HeadlessHost::new(Spy::default());
+        host.create();
+        host.advance(0.5);
+        host.close();
+
+        assert!(host.is_destroyed());
+        assert_eq!(host.exit_code(), Some(0));
+        assert_eq!(host.window().time, 0.5);
+        let seen = &host.handler().seen;
+        assert_eq!(
+            kinds(&seen[seen.len() - 3..]),
+            ["close", "activate", "destroy"]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The window was destroyed")]
+    fn destroyed_windows_get_no_messages() {
+        let mut host = HeadlessHost::new(Spy::default());
+        host.create();
+        host.destroy();
+        host.paint();
+    }
+
+    // Asks to be painted every quarter of a second, and then waits for
+    // messages once it was painted four times.
+    impl FrameHandler for Spy {
+        fn frame(&mut self, host: &mut dyn WindowHost) -> Wait {
+            if self.painted_sizes.len() > 4 {
+                return Wait::ForMessage;
+            }
+            host.invalidate();
+            Wait::For(0.25)
+        }
+    }
+
+    #[test]
+    fn running_paints_every_frame_the_window_asks_for() {
+        let mut host = HeadlessHost::new(Spy::default());
+        host.create();
+        host.run_for(0.5);
+        assert_eq!(host.window().time, 0.5);
+        assert_eq!(host.handler().painted_sizes.len(), 3);
+
+        host.run_for(10.0);
+        assert_eq!(host.window().time, 10.5);
+        assert_eq!(host.handler().painted_sizes.len(), 5);
+    }
+}
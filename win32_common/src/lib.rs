@@ -0,0 +1,54 @@
+//! Win32 plumbing shared by the learn-win32 modules.
+//!
+//! Everything that doesn't touch the operating system, like validating and
+//! resolving window options, is available on every platform so it can be
+//! tested on Linux. The parts that call into Win32 only exist on Windows.
+
+pub mod file_dialog;
+pub mod frame_loop;
+pub mod handler;
+pub mod headless;
+pub mod message;
+pub mod message_box;
+#[cfg(windows)]
+mod message_loop;
+pub mod replay;
+pub mod scripted;
+pub mod state;
+pub mod terminal;
+pub mod trace;
+pub mod wide;
+pub mod window;
+
+#[cfg(windows)]
+pub use file_dialog::Win32Dialogs;
+pub use file_dialog::{
+    native_dialogs, DialogError, DialogFlags, DialogOptions, Dialogs,
+    FileDialog, Filter, SaveChoice, SaveDialog,
+};
+#[cfg(windows)]
+pub use frame_loop::run_frame_loop;
+pub use frame_loop::{FrameHandler, Wait};
+pub use handler::{dispatch, WindowHandler, WindowHost};
+#[cfg(windows)]
+pub use handler::{handler_wndproc, Win32Host};
+pub use headless::HeadlessHost;
+pub use message::{
+    decode, encode, Message, MessageKind, MouseButton, SizeKind,
+};
+pub use message_box::{
+    Buttons, DialogResult, Icon, MessageBox, MessageBoxOptions,
+};
+#[cfg(windows)]
+pub use message_loop::{
+    pump_messages, run_message_loop, wait_for_messages, wait_message,
+};
+pub use scripted::{Answer, ScriptedDialogs};
+#[cfg(windows)]
+pub use state::Win32UserData;
+pub use state::{UserDataSlots, WindowState};
+pub use terminal::TerminalDialogs;
+pub use wide::{WideStr, WideString};
+pub use window::{
+    BuildError, ClassStyle, Cursor, WindowBuilder, WindowOptions, USE_DEFAULT,
+};
@@ -0,0 +1,741 @@
+// Message identifiers, copied from WinUser.h so they can be decoded on every
+// platform.
+// https://docs.microsoft.com/en-us/windows/win32/winmsg/about-messages-and-message-queues#system-defined-messages
+const WM_CREATE: u32 = 0x0001;
+const WM_DESTROY: u32 = 0x0002;
+const WM_SIZE: u32 = 0x0005;
+const WM_ACTIVATE: u32 = 0x0006;
+const WM_PAINT: u32 = 0x000F;
+const WM_CLOSE: u32 = 0x0010;
+const WM_DISPLAYCHANGE: u32 = 0x007E;
+const WM_KEYDOWN: u32 = 0x0100;
+const WM_KEYUP: u32 = 0x0101;
+const WM_SYSKEYDOWN: u32 = 0x0104;
+const WM_SYSKEYUP: u32 = 0x0105;
+const WM_MOUSEMOVE: u32 = 0x0200;
+const WM_LBUTTONDOWN: u32 = 0x0201;
+const WM_LBUTTONUP: u32 = 0x0202;
+const WM_RBUTTONDOWN: u32 = 0x0204;
+const WM_RBUTTONUP: u32 = 0x0205;
+const WM_MBUTTONDOWN: u32 = 0x0207;
+const WM_MBUTTONUP: u32 = 0x0208;
+const WM_MOUSEWHEEL: u32 = 0x020A;
+const WM_XBUTTONDOWN: u32 = 0x020B;
+const WM_XBUTTONUP: u32 = 0x020C;
+const WM_USER: u32 = 0x0400;
+const WM_APP: u32 = 0x8000;
+
+/// The low-order word of `value`, like the `LOWORD` macro.
+pub fn loword(value: usize) -> u16 {
+    (value & 0xFFFF) as u16
+}
+
+/// The high-order word of the low 32 bits of `value`, like the `HIWORD`
+/// macro.
+pub fn hiword(value: usize) -> u16 {
+    ((value >> 16) & 0xFFFF) as u16
+}
+
+/// The signed x-coordinate packed into an `LPARAM`, like the
+/// `GET_X_LPARAM` macro. Coordinates can be negative on multi-monitor
+/// setups, so `LOWORD` would be wrong here.
+pub fn get_x_lparam(lparam: isize) -> i32 {
+    loword(lparam as usize) as i16 as i32
+}
+
+/// The signed y-coordinate packed into an `LPARAM`, like the
+/// `GET_Y_LPARAM` macro.
+pub fn get_y_lparam(lparam: isize) -> i32 {
+    hiword(lparam as usize) as i16 as i32
+}
+
+/// The type of resizing requested by `WM_SIZE`.
+// https://docs.microsoft.com/en-us/windows/win32/winmsg/wm-size
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SizeKind {
+    Restored,
+    Minimized,
+    Maximized,
+    /// Another window was restored to its former size.
+    MaxShow,
+    /// Another window was maximized.
+    MaxHide,
+    Unknown(usize),
+}
+
+impl SizeKind {
+    fn from_wparam(wparam: usize) -> Self {
+        match wparam {
+            0 => SizeKind::Restored,
+            1 => SizeKind::Minimized,
+            2 => SizeKind::Maximized,
+            3 => SizeKind::MaxShow,
+            4 => SizeKind::MaxHide,
+            other => SizeKind::Unknown(other),
+        }
+    }
+
+    fn to_wparam(self) -> usize {
+        match self {
+            SizeKind::Restored => 0,
+            SizeKind::Minimized => 1,
+            SizeKind::Maximized => 2,
+            SizeKind::MaxShow => 3,
+            SizeKind::MaxHide => 4,
+            SizeKind::Unknown(other) => other,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    X1,
+    X2,
+}
+
+/// A window message with its `WPARAM` and `LPARAM` unpacked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Message {
+    Create,
+    Close,
+    Destroy,
+    Paint,
+    Size {
+        kind: SizeKind,
+        width: u16,
+        height: u16,
+    },
+    Activate {
+        active: bool,
+        minimized: bool,
+    },
+    DisplayChange {
+        bpp: u32,
+        width: u16,
+        height: u16,
+    },
+    /// The cursor moved, in client coordinates.
+    MouseMove {
+        x: i32,
+        y: i32,
+    },
+    MouseDown {
+        button: MouseButton,
+        x: i32,
+        y: i32,
+    },
+    MouseUp {
+        button: MouseButton,
+        x: i32,
+        y: i32,
+    },
+    /// The wheel rotated by `delta`, in multiples of `WHEEL_DELTA` (120).
+    /// Unlike the other mouse messages the position is in screen
+    /// coordinates.
+    MouseWheel {
+        delta: i16,
+        x: i32,
+        y: i32,
+    },
+    KeyDown {
+        key: u32,
+        repeat_count: u16,
+        /// The key was already down, so this is an auto-repeat.
+        was_down: bool,
+        system: bool,
+    },
+    KeyUp {
+        key: u32,
+        system: bool,
+    },
+    /// An application-defined message `WM_USER + n`, below `WM_APP`.
+    User(u32),
+    /// A message that isn't decoded, with its raw identifier, which is none
+    /// of those decoded into the other variants.
+    Other(u32),
+}
+
+impl Message {
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            Message::Create => MessageKind::Create,
+            Message::Close => MessageKind::Close,
+            Message::Destroy => MessageKind::Destroy,
+            Message::Paint => MessageKind::Paint,
+            Message::Size { .. } => MessageKind::Size,
+            Message::Activate { .. } => MessageKind::Activate,
+            Message::DisplayChange { .. } => MessageKind::DisplayChange,
+            Message::MouseMove { .. } => MessageKind::MouseMove,
+            Message::MouseDown { .. } => MessageKind::MouseDown,
+            Message::MouseUp { .. } => MessageKind::MouseUp,
+            Message::MouseWheel { .. } => MessageKind::MouseWheel,
+            Message::KeyDown { .. } => MessageKind::KeyDown,
+            Message::KeyUp { .. } => MessageKind::KeyUp,
+            Message::User(_) => MessageKind::User,
+            Message::Other(_) => MessageKind::Other,
+        }
+    }
+}
+
+/// The variant of a [`Message`] without its parameters, for filtering.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MessageKind {
+    Create,
+    Close,
+    Destroy,
+    Paint,
+    Size,
+    Activate,
+    DisplayChange,
+    MouseMove,
+    MouseDown,
+    MouseUp,
+    MouseWheel,
+    KeyDown,
+    KeyUp,
+    User,
+    Other,
+}
+
+impl MessageKind {
+    pub const ALL: [MessageKind; 15] = [
+        MessageKind::Create,
+        MessageKind::Close,
+        MessageKind::Destroy,
+        MessageKind::Paint,
+        MessageKind::Size,
+        MessageKind::Activate,
+        MessageKind::DisplayChange,
+        MessageKind::MouseMove,
+        MessageKind::MouseDown,
+        MessageKind::MouseUp,
+        MessageKind::MouseWheel,
+        MessageKind::KeyDown,
+        MessageKind::KeyUp,
+        MessageKind::User,
+        MessageKind::Other,
+    ];
+
+    /// The name in snake case, like `mouse_move`, as used in trace files
+    /// and filters.
+    pub fn name(self) -> &'static str {
+        match self {
+            MessageKind::Create => "create",
+            MessageKind::Close => "close",
+            MessageKind::Destroy => "destroy",
+            MessageKind::Paint => "paint",
+            MessageKind::Size => "size",
+            MessageKind::Activate => "activate",
+            MessageKind::DisplayChange => "display_change",
+            MessageKind::MouseMove => "mouse_move",
+            MessageKind::MouseDown => "mouse_down",
+            MessageKind::MouseUp => "mouse_up",
+            MessageKind::MouseWheel => "mouse_wheel",
+            MessageKind::KeyDown => "key_down",
+            MessageKind::KeyUp => "key_up",
+            MessageKind::User => "user",
+            MessageKind::Other => "other",
+        }
+    }
+
+    /// The kind called `name`, see [`MessageKind::name`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        MessageKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name)
+    }
+}
+
+/// Decodes a message and its parameters the way the window procedure
+/// receives them.
+pub fn decode(message: u32, wparam: usize, lparam: isize) -> Message {
+    match message {
+        WM_CREATE => Message::Create,
+        WM_CLOSE => Message::Close,
+        WM_DESTROY => Message::Destroy,
+        WM_PAINT => Message::Paint,
+        WM_SIZE => Message::Size {
+            kind: SizeKind::from_wparam(wparam),
+            width: loword(lparam as usize),
+            height: hiword(lparam as usize),
+        },
+        // The low-order word is WA_INACTIVE, WA_ACTIVE or WA_CLICKACTIVE, the
+        // high-order word is nonzero if the window is minimized.
+        // https://docs.microsoft.com/en-us/windows/win32/inputdev/wm-activate
+        WM_ACTIVATE => Message::Activate {
+            active: loword(wparam) != 0,
+            minimized: hiword(wparam) != 0,
+        },
+        // https://docs.microsoft.com/en-us/windows/win32/gdi/wm-displaychange
+        WM_DISPLAYCHANGE => Message::DisplayChange {
+            bpp: wparam as u32,
+            width: loword(lparam as usize),
+            height: hiword(lparam as usize),
+        },
+        WM_MOUSEMOVE => Message::MouseMove {
+            x: get_x_lparam(lparam),
+            y: get_y_lparam(lparam),
+        },
+        WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN | WM_XBUTTONDOWN => {
+            Message::MouseDown {
+                button: mouse_button(message, wparam),
+                x: get_x_lparam(lparam),
+                y: get_y_lparam(lparam),
+            }
+        }
+        WM_LBUTTONUP | WM_RBUTTONUP | WM_MBUTTONUP | WM_XBUTTONUP => {
+            Message::MouseUp {
+                button: mouse_button(message, wparam),
+                x: get_x_lparam(lparam),
+                y: get_y_lparam(lparam),
+            }
+        }
+        // https://docs.microsoft.com/en-us/windows/win32/inputdev/wm-mousewheel
+        WM_MOUSEWHEEL => Message::MouseWheel {
+            delta: hiword(wparam) as i16,
+            x: get_x_lparam(lparam),
+            y: get_y_lparam(lparam),
+        },
+        // Bits 0-15 of the LPARAM hold the repeat count, bit 30 the previous
+        // key state.
+        // https://docs.microsoft.com/en-us/windows/win32/inputdev/wm-keydown
+        WM_KEYDOWN | WM_SYSKEYDOWN => Message::KeyDown {
+            key: wparam as u32,
+            repeat_count: loword(lparam as usize),
+            was_down: (lparam >> 30) & 1 == 1,
+            system: message == WM_SYSKEYDOWN,
+        },
+        WM_KEYUP | WM_SYSKEYUP => Message::KeyUp {
+            key: wparam as u32,
+            system: message == WM_SYSKEYUP,
+        },
+        WM_USER..WM_APP => Message::User(message - WM_USER),
+        _ => Message::Other(message),
+    }
+}
+
+/// Packs a message back into the identifier and parameters [`decode`]
+/// reads it from, for simulating and recording messages.
+///
+/// Messages that are decoded from several identifiers, like key presses of
+/// system keys, are encoded into the one that decodes to them again. What
+/// `decode` drops, like the modifier keys held during a mouse message, is
+/// left zero.
+///
+/// Only messages `decode` can return round-trip. [`Message::Other`] with an
+/// identifier that is decoded, and [`Message::User`] at `WM_APP - WM_USER`
+/// or beyond, are encoded as the identifier they name and decode as
+/// whatever message that is.
+pub fn encode(message: Message) -> (u32, usize, isize) {
+    let words = |low: u16, high: u16| {
+        ((high as u32) << 16 | low as u32) as i32 as isize
+    };
+    let point = |x: i32, y: i32| words(x as u16, y as u16);
+
+    match message {
+        Message::Create => (WM_CREATE, 0, 0),
+        Message::Close => (WM_CLOSE, 0, 0),
+        Message::Destroy => (WM_DESTROY, 0, 0),
+        Message::Paint => (WM_PAINT, 0, 0),
+        Message::Size {
+            kind,
+            width,
+            height,
+        } => (WM_SIZE, kind.to_wparam(), words(width, height)),
+        Message::Activate { active, minimized } => {
+            (WM_ACTIVATE, (minimized as usize) << 16 | active as usize, 0)
+        }
+        Message::DisplayChange { bpp, width, height } => {
+            (WM_DISPLAYCHANGE, bpp as usize, words(width, height))
+        }
+        Message::MouseMove { x, y } => (WM_MOUSEMOVE, 0, point(x, y)),
+        Message::MouseDown { button, x, y } => {
+            let (message, wparam) = encode_button(button, true);
+            (message, wparam, point(x, y))
+        }
+        Message::MouseUp { button, x, y } => {
+            let (message, wparam) = encode_button(button, false);
+            (message, wparam, point(x, y))
+        }
+        Message::MouseWheel { delta, x, y } => {
+            (WM_MOUSEWHEEL, (delta as u16 as usize) << 16, point(x, y))
+        }
+        Message::KeyDown {
+            key,
+            repeat_count,
+            was_down,
+            system,
+        } => (
+            if system { WM_SYSKEYDOWN } else { WM_KEYDOWN },
+            key as usize,
+            (was_down as isize) << 30 | repeat_count as isize,
+        ),
+        // The previous key state and the transition state are always set.
+        Message::KeyUp { key, system } => (
+            if system { WM_SYSKEYUP } else { WM_KEYUP },
+            key as usize,
+            0xC000_0001_u32 as i32 as isize,
+        ),
+        Message::User(n) => (WM_USER.wrapping_add(n), 0, 0),
+        Message::Other(message) => (message, 0, 0),
+    }
+}
+
+fn encode_button(button: MouseButton, down: bool) -> (u32, usize) {
+    let pick = |down_message, up_message| match down {
+        true => down_message,
+        false => up_message,
+    };
+    match button {
+        MouseButton::Left => (pick(WM_LBUTTONDOWN, WM_LBUTTONUP), 0),
+        MouseButton::Right => (pick(WM_RBUTTONDOWN, WM_RBUTTONUP), 0),
+        MouseButton::Middle => (pick(WM_MBUTTONDOWN, WM_MBUTTONUP), 0),
+        MouseButton::X1 => (pick(WM_XBUTTONDOWN, WM_XBUTTONUP), 0x0001_0000),
+        MouseButton::X2 => (pick(WM_XBUTTONDOWN, WM_XBUTTONUP), 0x0002_0000),
+    }
+}
+
+fn mouse_button(message: u32, wparam: usize) -> MouseButton {
+    match message {
+        WM_LBUTTONDOWN | WM_LBUTTONUP => MouseButton::Left,
+        WM_RBUTTONDOWN | WM_RBUTTONUP => MouseButton::Right,
+        WM_MBUTTONDOWN | WM_MBUTTONUP => MouseButton::Middle,
+        // The high-order word of the WPARAM is XBUTTON1 (1) or XBUTTON2 (2).
+        _ if hiword(wparam) == 2 => MouseButton::X2,
+        _ => MouseButton::X1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_lparam(low: u16, high: u16) -> isize {
+        ((high as u32) << 16 | low as u32) as i32 as isize
+    }
+
+    #[test]
+    fn words_are_split() {
+        assert_eq!(loword(0x1234_5678), 0x5678);
+        assert_eq!(hiword(0x1234_5678), 0x1234);
+        assert_eq!(loword(0), 0);
+        assert_eq!(hiword(0xFFFF), 0);
+        // Only the low 32 bits count, like the Win32 macros.
+        assert_eq!(hiword(0xABCD_0000_FFFF_0000), 0xFFFF);
+    }
+
+    #[test]
+    fn coordinates_are_sign_extended() {
+        let lparam = make_lparam(-5_i16 as u16, -300_i16 as u16);
+        assert_eq!(get_x_lparam(lparam), -5);
+        assert_eq!(get_y_lparam(lparam), -300);
+
+        let lparam = make_lparam(640, 480);
+        assert_eq!(get_x_lparam(lparam), 640);
+        assert_eq!(get_y_lparam(lparam), 480);
+    }
+
+    #[test]
+    fn parameterless_messages() {
+        assert_eq!(decode(WM_CREATE, 0, 0), Message::Create);
+        assert_eq!(decode(WM_CLOSE, 0, 0), Message::Close);
+        assert_eq!(decode(WM_DESTROY, 0, 0), Message::Destroy);
+        assert_eq!(decode(WM_PAINT, 0, 0), Message::Paint);
+    }
+
+    #[test]
+    fn size() {
+        assert_eq!(
+            decode(WM_SIZE, 0, make_lparam(800, 600)),
+            Message::Size {
+                kind: SizeKind::Restored,
+                width: 800,
+                height: 600
+            }
+        );
+
+        let kinds = [
+            SizeKind::Restored,
+            SizeKind::Minimized,
+            SizeKind::Maximized,
+            SizeKind::MaxShow,
+            SizeKind::MaxHide,
+            SizeKind::Unknown(5),
+        ];
+        for (wparam, kind) in kinds.into_iter().enumerate() {
+            match decode(WM_SIZE, wparam, 0) {
+                Message::Size { kind: decoded, .. } => {
+                    assert_eq!(decoded, kind)
+                }
+                other => panic!("decoded {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn size_uses_full_unsigned_words() {
+        assert_eq!(
+            decode(WM_SIZE, 2, make_lparam(0xFFFF, 0x8000)),
+            Message::Size {
+                kind: SizeKind::Maximized,
+                width: 0xFFFF,
+                height: 0x8000
+            }
+        );
+    }
+
+    #[test]
+    fn activate() {
+        let cases = [
+            // WA_INACTIVE
+            (0x0000_0000, false, false),
+            // WA_ACTIVE
+            (0x0000_0001, true, false),
+            // WA_CLICKACTIVE
+            (0x0000_0002, true, false),
+            // WA_ACTIVE while minimized
+            (0x0001_0001, true, true),
+            // WA_INACTIVE while minimized
+            (0x0001_0000, false, true),
+        ];
+        for (wparam, active, minimized) in cases {
+            assert_eq!(
+                decode(WM_ACTIVATE, wparam, 0),
+                Message::Activate { active, minimized },
+                "wparam {:#x}",
+                wparam
+            );
+        }
+    }
+
+    #[test]
+    fn display_change() {
+        assert_eq!(
+            decode(WM_DISPLAYCHANGE, 32, make_lparam(2560, 1440)),
+            Message::DisplayChange {
+                bpp: 32,
+                width: 2560,
+                height: 1440
+            }
+        );
+    }
+
+    #[test]
+    fn mouse_move() {
+        assert_eq!(
+            decode(WM_MOUSEMOVE, 0, make_lparam(10, 20)),
+            Message::MouseMove { x: 10, y: 20 }
+        );
+    }
+
+    #[test]
+    fn mouse_buttons() {
+        let lparam = make_lparam(-1_i16 as u16, 7);
+        let cases = [
+            (WM_LBUTTONDOWN, WM_LBUTTONUP, 0, MouseButton::Left),
+            (WM_RBUTTONDOWN, WM_RBUTTONUP, 0, MouseButton::Right),
+            (WM_MBUTTONDOWN, WM_MBUTTONUP, 0, MouseButton::Middle),
+            (WM_XBUTTONDOWN, WM_XBUTTONUP, 0x0001_0000, MouseButton::X1),
+            (WM_XBUTTONDOWN, WM_XBUTTONUP, 0x0002_0000, MouseButton::X2),
+        ];
+        for (down, up, wparam, button) in cases {
+            assert_eq!(
+                decode(down, wparam, lparam),
+                Message::MouseDown {
+                    button,
+                    x: -1,
+                    y: 7
+                }
+            );
+            assert_eq!(
+                decode(up, wparam, lparam),
+                Message::MouseUp {
+                    button,
+                    x: -1,
+                    y: 7
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn mouse_wheel() {
+        // One notch towards the user is -WHEEL_DELTA.
+        let wparam = ((-120_i16 as u16 as usize) << 16) | 0x0008;
+        assert_eq!(
+            decode(WM_MOUSEWHEEL, wparam, make_lparam(100, 200)),
+            Message::MouseWheel {
+                delta: -120,
+                x: 100,
+                y: 200
+            }
+        );
+    }
+
+    #[test]
+    fn keys() {
+        // 'A' pressed for the first time, repeat count 1.
+        assert_eq!(
+            decode(WM_KEYDOWN, 0x41, 0x001E_0001),
+            Message::KeyDown {
+                key: 0x41,
+                repeat_count: 1,
+                was_down: false,
+                system: false
+            }
+        );
+        // Auto-repeat: bit 30 is set.
+        assert_eq!(
+            decode(WM_KEYDOWN, 0x41, 0x401E_0003),
+            Message::KeyDown {
+                key: 0x41,
+                repeat_count: 3,
+                was_down: true,
+                system: false
+            }
+        );
+        // Alt+F4 arrives as a system key.
+        assert_eq!(
+            decode(WM_SYSKEYDOWN, 0x73, 0x2000_0001),
+            Message::KeyDown {
+                key: 0x73,
+                repeat_count: 1,
+                was_down: false,
+                system: true
+            }
+        );
+        // Key up has bits 30 and 31 set, so the LPARAM is negative when it
+        // is sign extended to 64 bits.
+        let lparam = 0xC01E_0001_u32 as i32 as isize;
+        assert_eq!(
+            decode(WM_KEYUP, 0x41, lparam),
+            Message::KeyUp {
+                key: 0x41,
+                system: false
+            }
+        );
+        assert_eq!(
+            decode(WM_SYSKEYUP, 0x12, lparam),
+            Message::KeyUp {
+                key: 0x12,
+                system: true
+            }
+        );
+    }
+
+    #[test]
+    fn user_messages_are_relative_to_wm_user() {
+        assert_eq!(decode(WM_USER, 0, 0), Message::User(0));
+        assert_eq!(decode(WM_USER + 42, 0, 0), Message::User(42));
+        assert_eq!(
+            decode(WM_APP - 1, 0, 0),
+            Message::User(WM_APP - 1 - WM_USER)
+        );
+    }
+
+    #[test]
+    fn encoding_round_trips() {
+        let messages = [
+            Message::Create,
+            Message::Close,
+            Message::Destroy,
+            Message::Paint,
+            Message::Size {
+                kind: SizeKind::Maximized,
+                width: 0xFFFF,
+                height: 600,
+            },
+            Message::Activate {
+                active: true,
+                minimized: true,
+            },
+            Message::DisplayChange {
+                bpp: 32,
+                width: 2560,
+                height: 1440,
+            },
+            Message::MouseMove { x: -5, y: 300 },
+            Message::MouseDown {
+                button: MouseButton::Middle,
+                x: 1,
+                y: 2,
+            },
+            Message::MouseUp {
+                button: MouseButton::X2,
+                x: -1,
+                y: -2,
+            },
+            Message::MouseWheel {
+                delta: -240,
+                x: 10,
+                y: 20,
+            },
+            Message::KeyDown {
+                key: 0x73,
+                repeat_count: 3,
+                was_down: true,
+                system: true,
+            },
+            Message::KeyUp {
+                key: 0x41,
+                system: false,
+            },
+            Message::User(7),
+            Message::Other(0x0081),
+        ];
+        for message in messages {
+            let (id, wparam, lparam) = encode(message);
+            assert_eq!(decode(id, wparam, lparam), message);
+        }
+        assert_eq!(encode(Message::Paint), (WM_PAINT, 0, 0));
+    }
+
+    #[test]
+    fn only_decoded_messages_round_trip() {
+        let round_trip = |message| {
+            let (id, wparam, lparam) = encode(message);
+            decode(id, wparam, lparam)
+        };
+
+        let last_user = WM_APP - WM_USER - 1;
+        for message in [
+            Message::User(0),
+            Message::User(last_user),
+            Message::Other(WM_USER - 1),
+            Message::Other(WM_APP),
+        ] {
+            assert_eq!(round_trip(message), message);
+        }
+
+        assert_eq!(round_trip(Message::Other(WM_PAINT)), Message::Paint);
+        assert_eq!(round_trip(Message::Other(WM_USER)), Message::User(0));
+        assert_eq!(
+            round_trip(Message::User(last_user + 1)),
+            Message::Other(WM_APP)
+        );
+        assert_eq!(encode(Message::User(u32::MAX)).0, WM_USER - 1);
+    }
+
+    #[test]
+    fn kinds_have_unique_names() {
+        for kind in MessageKind::ALL {
+            assert_eq!(MessageKind::from_name(kind.name()), Some(kind));
+        }
+        assert_eq!(
+            decode(WM_SIZE, 0, make_lparam(1, 2)).kind(),
+            MessageKind::Size
+        );
+        assert_eq!(MessageKind::from_name("Paint"), None);
+    }
+
+    #[test]
+    fn everything_else_is_other() {
+        assert_eq!(decode(WM_APP, 0, 0), Message::Other(WM_APP));
+        assert_eq!(decode(0x0000, 0, 0), Message::Other(0x0000));
+        // WM_NCCREATE is handled by `WindowState`, not decoded.
+        assert_eq!(decode(0x0081, 0, 0), Message::Other(0x0081));
+    }
+}
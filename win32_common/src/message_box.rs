@@ -0,0 +1,359 @@
+//! Asking the user a question with a message box.
+//!
+//! A [`MessageBox`] is a builder like the file dialogs: its options are
+//! validated and turned into a `MB_*` style without Windows, and it is
+//! shown by a [`Dialogs`] implementation, so it asks in the terminal off
+//! Windows and gets preset answers in tests.
+//!
+//! [`Dialogs`]: crate::Dialogs
+
+use std::fmt;
+
+#[cfg(windows)]
+use windows::Win32::Foundation::HWND;
+
+use crate::file_dialog::{native_dialogs, DialogError, Dialogs};
+
+/// The buttons of a message box, mirroring the `MB_*` constants for them.
+// https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-messageboxw
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Buttons {
+    #[default]
+    Ok,
+    OkCancel,
+    AbortRetryIgnore,
+    YesNoCancel,
+    YesNo,
+    RetryCancel,
+}
+
+impl Buttons {
+    /// What the buttons answer, from left to right.
+    pub fn results(self) -> &'static [DialogResult] {
+        use DialogResult::*;
+        match self {
+            Buttons::Ok => &[Ok],
+            Buttons::OkCancel => &[Ok, Cancel],
+            Buttons::AbortRetryIgnore => &[Abort, Retry, Ignore],
+            Buttons::YesNoCancel => &[Yes, No, Cancel],
+            Buttons::YesNo => &[Yes, No],
+            Buttons::RetryCancel => &[Retry, Cancel],
+        }
+    }
+
+    /// What closing the box without pressing a button answers, like
+    /// pressing Escape does. Boxes without a Cancel button other than a
+    /// lone OK button can't be closed that way.
+    pub fn close_result(self) -> Option<DialogResult> {
+        match self {
+            Buttons::Ok => Some(DialogResult::Ok),
+            Buttons::AbortRetryIgnore | Buttons::YesNo => None,
+            _ => Some(DialogResult::Cancel),
+        }
+    }
+
+    fn style(self) -> u32 {
+        match self {
+            Buttons::Ok => 0x0,
+            Buttons::OkCancel => 0x1,
+            Buttons::AbortRetryIgnore => 0x2,
+            Buttons::YesNoCancel => 0x3,
+            Buttons::YesNo => 0x4,
+            Buttons::RetryCancel => 0x5,
+        }
+    }
+}
+
+/// The icon shown next to the text, also setting the sound played.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Icon {
+    #[default]
+    None,
+    Error,
+    Question,
+    Warning,
+    Information,
+}
+
+impl Icon {
+    fn style(self) -> u32 {
+        match self {
+            Icon::None => 0x00,
+            Icon::Error => 0x10,
+            Icon::Question => 0x20,
+            Icon::Warning => 0x30,
+            Icon::Information => 0x40,
+        }
+    }
+
+    /// How the terminal shows the icon, in front of the text.
+    pub fn label(self) -> Option<&'static str> {
+        match self {
+            Icon::None => None,
+            Icon::Error => Some("Error"),
+            Icon::Question => Some("Question"),
+            Icon::Warning => Some("Warning"),
+            Icon::Information => Some("Information"),
+        }
+    }
+}
+
+/// The button that closed a message box, mirroring the `ID*` values
+/// `MessageBoxW` returns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DialogResult {
+    Ok = 1,
+    Cancel = 2,
+    Abort = 3,
+    Retry = 4,
+    Ignore = 5,
+    Yes = 6,
+    No = 7,
+}
+
+impl DialogResult {
+    /// The result for an `ID*` value, or `None` for one without a button
+    /// here, like zero for failure.
+    pub fn from_id(id: i32) -> Option<DialogResult> {
+        use DialogResult::*;
+        [Ok, Cancel, Abort, Retry, Ignore, Yes, No]
+            .into_iter()
+            .find(|result| *result as i32 == id)
+    }
+
+    /// The text on the button.
+    pub fn label(self) -> &'static str {
+        match self {
+            DialogResult::Ok => "OK",
+            DialogResult::Cancel => "Cancel",
+            DialogResult::Abort => "Abort",
+            DialogResult::Retry => "Retry",
+            DialogResult::Ignore => "Ignore",
+            DialogResult::Yes => "Yes",
+            DialogResult::No => "No",
+        }
+    }
+}
+
+impl fmt::Display for DialogResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// The validated options a message box is shown with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageBoxOptions {
+    pub text: String,
+    pub caption: Option<String>,
+    pub buttons: Buttons,
+    pub icon: Icon,
+    /// The index of the button that has the focus, from the left.
+    pub default_button: usize,
+    /// The handle of the owner window, zero for none.
+    pub owner: isize,
+}
+
+impl MessageBoxOptions {
+    /// The `uType` of `MessageBoxW` for the options.
+    pub fn style(&self) -> u32 {
+        // `MB_DEFBUTTON1` and up.
+        let default_button = (self.default_button as u32) << 8;
+        self.buttons.style() | self.icon.style() | default_button
+    }
+
+    /// The result of the button that has the focus.
+    pub fn default_result(&self) -> DialogResult {
+        self.buttons.results()[self.default_button]
+    }
+}
+
+/// Shows a message and waits for the user to press one of its buttons,
+/// replacing direct `MessageBoxW` calls.
+#[derive(Clone, Debug)]
+pub struct MessageBox {
+    text: String,
+    caption: Option<String>,
+    buttons: Buttons,
+    icon: Icon,
+    default_button: usize,
+    owner: isize,
+}
+
+impl MessageBox {
+    pub fn new(text: &str) -> Self {
+        MessageBox {
+            text: text.to_owned(),
+            caption: None,
+            buttons: Buttons::default(),
+            icon: Icon::default(),
+            default_button: 0,
+            owner: 0,
+        }
+    }
+
+    /// Replaces the title of the box, "Error" by default on Windows.
+    pub fn caption(mut self, caption: &str) -> Self {
+        self.caption = Some(caption.to_owned());
+        self
+    }
+
+    pub fn buttons(mut self, buttons: Buttons) -> Self {
+        self.buttons = buttons;
+        self
+    }
+
+    pub fn icon(mut self, icon: Icon) -> Self {
+        self.icon = icon;
+        self
+    }
+
+    /// Gives the focus to the button at `index` from the left instead of
+    /// the first one.
+    pub fn default_button(mut self, index: usize) -> Self {
+        self.default_button = index;
+        self
+    }
+
+    /// The window the box belongs to, which is disabled while it is shown.
+    #[cfg(windows)]
+    pub fn owner(mut self, owner: HWND) -> Self {
+        self.owner = owner.0;
+        self
+    }
+
+    /// Validates the builder and resolves every option that was left unset.
+    pub fn options(&self) -> Result<MessageBoxOptions, DialogError> {
+        let caption_has_nul = self
+            .caption
+            .as_ref()
+            .is_some_and(|caption| caption.contains('\0'));
+        if self.text.contains('\0') || caption_has_nul {
+            return Err(DialogError::MessageContainsNul);
+        }
+        if self.default_button >= self.buttons.results().len() {
+            return Err(DialogError::InvalidDefaultButton(self.default_button));
+        }
+
+        Ok(MessageBoxOptions {
+            text: self.text.clone(),
+            caption: self.caption.clone(),
+            buttons: self.buttons,
+            icon: self.icon,
+            default_button: self.default_button,
+            owner: self.owner,
+        })
+    }
+
+    /// Shows the box of the platform, see [`native_dialogs`], and waits
+    /// until the user presses a button.
+    ///
+    /// [`native_dialogs`]: crate::native_dialogs
+    pub fn show(&self) -> Result<DialogResult, DialogError> {
+        self.show_with(&mut native_dialogs())
+    }
+
+    pub fn show_with(
+        &self,
+        dialogs: &mut dyn Dialogs,
+    ) -> Result<DialogResult, DialogError> {
+        dialogs.message_box(&self.options()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unset_options_show_an_ok_button() {
+        let options = MessageBox::new("Done").options().unwrap();
+
+        assert_eq!(options.caption, None);
+        assert_eq!(options.buttons, Buttons::Ok);
+        assert_eq!(options.icon, Icon::None);
+        assert_eq!(options.owner, 0);
+        assert_eq!(options.style(), 0);
+        assert_eq!(options.default_result(), DialogResult::Ok);
+    }
+
+    #[test]
+    fn options_translate_to_a_style() {
+        let options = MessageBox::new("Save the trace?")
+            .caption("Clock")
+            .buttons(Buttons::YesNoCancel)
+            .icon(Icon::Warning)
+            .default_button(2)
+            .options()
+            .unwrap();
+
+        // MB_YESNOCANCEL | MB_ICONWARNING | MB_DEFBUTTON3
+        assert_eq!(options.style(), 0x3 | 0x30 | 0x200);
+        assert_eq!(options.default_result(), DialogResult::Cancel);
+
+        let styles: Vec<_> = [
+            (Buttons::OkCancel, Icon::Information, 1),
+            (Buttons::AbortRetryIgnore, Icon::Error, 0),
+            (Buttons::RetryCancel, Icon::Question, 1),
+            (Buttons::YesNo, Icon::None, 0),
+        ]
+        .into_iter()
+        .map(|(buttons, icon, default_button)| {
+            let message = MessageBox::new("m")
+                .buttons(buttons)
+                .icon(icon)
+                .default_button(default_button);
+            message.options().unwrap().style()
+        })
+        .collect();
+        assert_eq!(styles, [0x141, 0x12, 0x125, 0x4]);
+    }
+
+    #[test]
+    fn invalid_options_are_rejected() {
+        for message in [
+            MessageBox::new("a\0b"),
+            MessageBox::new("text").caption("a\0b"),
+        ] {
+            let error = message.options().unwrap_err();
+            assert!(matches!(error, DialogError::MessageContainsNul));
+        }
+
+        let error = MessageBox::new("text")
+            .buttons(Buttons::YesNo)
+            .default_button(2)
+            .options()
+            .unwrap_err();
+        assert!(matches!(error, DialogError::InvalidDefaultButton(2)));
+    }
+
+    #[test]
+    fn results_match_the_ids_of_their_buttons() {
+        for buttons in [
+            Buttons::Ok,
+            Buttons::OkCancel,
+            Buttons::AbortRetryIgnore,
+            Buttons::YesNoCancel,
+            Buttons::YesNo,
+            Buttons::RetryCancel,
+        ] {
+            for &result in buttons.results() {
+                assert_eq!(DialogResult::from_id(result as i32), Some(result));
+            }
+        }
+        assert_eq!(DialogResult::from_id(0), None);
+        assert_eq!(DialogResult::from_id(6), Some(DialogResult::Yes));
+        assert_eq!(DialogResult::Ok.to_string(), "OK");
+    }
+
+    #[test]
+    fn only_some_boxes_can_be_closed() {
+        assert_eq!(Buttons::Ok.close_result(), Some(DialogResult::Ok));
+        assert_eq!(
+            Buttons::YesNoCancel.close_result(),
+            Some(DialogResult::Cancel)
+        );
+        assert_eq!(Buttons::YesNo.close_result(), None);
+        assert_eq!(Buttons::AbortRetryIgnore.close_result(), None);
+    }
+}
@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use windows::{
+    core::{Error, Result},
+    Win32::{
+        Foundation::{BOOL, HWND, WAIT_FAILED},
+        UI::WindowsAndMessaging::{
+            DispatchMessageW, GetMessageW, MsgWaitForMultipleObjects,
+            PeekMessageW, TranslateMessage, MSG, PM_REMOVE, QS_ALLINPUT,
+            WM_QUIT,
+        },
+    },
+};
+
+/// Retrieves and dispatches messages until `WM_QUIT` is posted, returning
+/// the exit code passed to `PostQuitMessage`.
+pub fn run_message_loop() -> i32 {
+    let mut message = MSG::default();
+
+    unsafe {
+        // Retrieves a message from the calling thread's message queue. If the
+        // hwnd parameter is `0`, both window messages and thread messages are
+        // processed. `GetMessageW` returns zero once it retrieves `WM_QUIT`.
+        // https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-getmessagew
+        while GetMessageW(&mut message, HWND(0), 0, 0).into() {
+            // Translates virtual-key messages into character messages.
+            TranslateMessage(&message);
+            // Dispatches a message to a window procedure.
+            DispatchMessageW(&message);
+        }
+    }
+
+    message.wParam.0 as i32
+}
+
+/// Blocks until a single message arrives and dispatches it. Returns `false`
+/// once `WM_QUIT` has been retrieved.
+pub fn wait_message() -> bool {
+    let mut message = MSG::default();
+
+    unsafe {
+        if !GetMessageW(&mut message, HWND(0), 0, 0).as_bool() {
+            return false;
+        }
+        TranslateMessage(&message);
+        DispatchMessageW(&message);
+    }
+
+    true
+}
+
+/// Blocks until a message arrives or `timeout` elapses, without retrieving
+/// the message, so a loop can sleep until its next frame is due and still
+/// react to input right away.
+pub fn wait_for_messages(timeout: Duration) -> Result<()> {
+    // Rounded up, so the loop doesn't wake just before the frame is due.
+    let milliseconds = timeout.as_micros().div_ceil(1000).min(u32::MAX as u128);
+    let woken = unsafe {
+        MsgWaitForMultipleObjects(
+            0,
+            std::ptr::null(),
+            BOOL(0),
+            milliseconds as u32,
+            QS_ALLINPUT,
+        )
+    };
+    if woken == WAIT_FAILED.0 {
+        return Err(Error::from_win32());
+    }
+    Ok(())
+}
+
+/// Dispatches every message that is already queued without waiting for new
+/// ones, for loops that have other work to do between messages. Returns
+/// `false` once `WM_QUIT` has been retrieved.
+pub fn pump_messages() -> bool {
+    let mut message = MSG::default();
+
+    unsafe {
+        // Unlike GetMessage, the PeekMessage function does not wait for a
+        // message to be posted before returning.
+        // https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-peekmessagew
+        while PeekMessageW(&mut message, HWND(0), 0, 0, PM_REMOVE).into() {
+            if message.message == WM_QUIT {
+                return false;
+            }
+            TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+    }
+
+    true
+}
@@ -0,0 +1,333 @@
+//! Recording the messages a window receives and replaying them against a
+//! [`WindowHandler`] without a window, to reproduce bugs on any platform.
+//!
+//! A recording is a binary message trace of every message, see
+//! [`crate::trace`]: run a module with `WIN32_TRACE=binary:session.trace`
+//! and without `WIN32_TRACE_FILTER`, then [`Recording::load`] the file.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{
+    handler::{dispatch, WindowHandler, WindowHost},
+    message::{encode, Message},
+    trace::{
+        read_binary, write_binary_header, write_binary_record, TraceEvent,
+    },
+};
+
+/// The messages windows received, in the order they arrived.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recording {
+    events: Vec<TraceEvent>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Recording::default()
+    }
+
+    pub fn from_events(events: Vec<TraceEvent>) -> Self {
+        Recording { events }
+    }
+
+    /// Reads a recording from a binary message trace.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Recording::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn read(reader: impl Read) -> io::Result<Self> {
+        read_binary(reader).map(Recording::from_events)
+    }
+
+    /// Writes the recording as a binary message trace.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        write_binary_header(&mut writer)?;
+        for event in &self.events {
+            write_binary_record(&mut writer, event)?;
+        }
+        Ok(())
+    }
+
+    /// Appends `message` sent to window `0` at `time`, for writing
+    /// recordings by hand.
+    pub fn push(&mut self, time: f64, message: Message) {
+        let (message, wparam, lparam) = encode(message);
+        self.events.push(TraceEvent {
+            time,
+            window: 0,
+            message,
+            wparam,
+            lparam,
+            latency: 0.0,
+        });
+    }
+
+    pub fn events(&self) -> &[TraceEvent] {
+        &self.events
+    }
+
+    /// The handles of the recorded windows, in the order they first
+    /// received a message.
+    pub fn windows(&self) -> Vec<isize> {
+        let mut windows = Vec::new();
+        for event in &self.events {
+            if !windows.contains(&event.window) {
+                windows.push(event.window);
+            }
+        }
+        windows
+    }
+
+    /// Only the messages of `window`.
+    pub fn for_window(&self, window: isize) -> Recording {
+        Recording::from_events(
+            self.events
+                .iter()
+                .filter(|event| event.window == window)
+                .copied()
+                .collect(),
+        )
+    }
+}
+
+/// A window that only exists in memory, standing in for the real one while
+/// a recording is replayed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VirtualWindow {
+    pub client_size: (u32, u32),
+    pub dpi: u32,
+    /// The time of the message being replayed.
+    pub time: f64,
+    /// The client area was invalidated and not painted since.
+    pub invalidated: bool,
+    /// What the handler passed to [`WindowHost::quit`], if it did.
+    pub exit_code: Option<i32>,
+}
+
+impl Default for VirtualWindow {
+    fn default() -> Self {
+        VirtualWindow {
+            client_size: (0, 0),
+            dpi: 96,
+            time: 0.0,
+            invalidated: false,
+            exit_code: None,
+        }
+    }
+}
+
+impl VirtualWindow {
+    /// Hands `message` to `handler` after updating the window for it, like
+    /// Windows does: the client size follows every `WM_SIZE` before the
+    /// handler sees it, and painting validates the window, see
+    /// [`dispatch`].
+    pub fn deliver(
+        &mut self,
+        handler: &mut (impl WindowHandler + ?Sized),
+        message: Message,
+    ) -> Option<isize> {
+        if let Message::Size { width, height, .. } = message {
+            self.client_size = (width as u32, height as u32);
+        }
+        dispatch(handler, self, message)
+    }
+}
+
+impl WindowHost for VirtualWindow {
+    fn handle(&self) -> isize {
+        0
+    }
+
+    fn client_size(&self) -> (u32, u32) {
+        self.client_size
+    }
+
+    fn dpi(&self) -> u32 {
+        self.dpi
+    }
+
+    fn now(&self) -> f64 {
+        self.time
+    }
+
+    fn invalidate(&mut self) {
+        self.invalidated = true;
+    }
+
+    fn validate(&mut self) {
+        self.invalidated = false;
+    }
+
+    fn quit(&mut self, exit_code: i32) {
+        self.exit_code = Some(exit_code);
+    }
+}
+
+/// Hands every message of `recording` to `handler` at the time it was
+/// recorded, and returns what the handler returned for each of them. See
+/// [`VirtualWindow::deliver`].
+pub fn replay(
+    recording: &Recording,
+    handler: &mut impl WindowHandler,
+    window: &mut VirtualWindow,
+) -> Vec<Option<isize>> {
+    recording
+        .events()
+        .iter()
+        .map(|event| {
+            window.time = event.time;
+            window.deliver(handler, event.decoded())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::SizeKind;
+
+    // Remembers what it saw of the window with every message.
+    #[derive(Default)]
+    struct Spy {
+        seen: Vec<(f64, Message, (u32, u32))>,
+    }
+
+    impl WindowHandler for Spy {
+        fn handle(
+            &mut self,
+            host: &mut dyn WindowHost,
+            message: Message,
+        ) -> Option<isize> {
+            self.seen.push((host.now(), message, host.client_size()));
+            match message {
+                Message::Size { .. } => host.invalidate(),
+                Message::Destroy => host.quit(3),
+                Message::Close => return None,
+                _ => {}
+            }
+            Some(0)
+        }
+    }
+
+    fn size(width: u16, height: u16) -> Message {
+        Message::Size {
+            kind: SizeKind::Restored,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn replays_messages_with_their_time_and_size() {
+        let mut recording = Recording::new();
+        recording.push(0.0, Message::Create);
+        recording.push(0.1, size(640, 480));
+        recording.push(0.2, Message::Close);
+        recording.push(0.3, Message::Destroy);
+
+        let mut spy = Spy::default();
+        let mut window = VirtualWindow::default();
+        let results = replay(&recording, &mut spy, &mut window);
+
+        assert_eq!(results, [Some(0), Some(0), None, Some(0)]);
+        assert_eq!(spy.seen[0], (0.0, Message::Create, (0, 0)));
+        assert_eq!(spy.seen[1], (0.1, size(640, 480), (640, 480)));
+        assert_eq!(spy.seen[3].0, 0.3);
+        assert_eq!(window.exit_code, Some(3));
+    }
+
+    #[test]
+    fn painting_validates_the_window() {
+        let mut recording = Recording::new();
+        recording.push(0.0, size(100, 100));
+        let mut window = VirtualWindow::default();
+        replay(&recording, &mut Spy::default(), &mut window);
+        assert!(window.invalidated);
+
+        recording.push(0.1, Message::Paint);
+        replay(&recording, &mut Spy::default(), &mut window);
+        assert!(!window.invalidated);
+    }
+
+    #[test]
+    fn recordings_round_trip() {
+        let mut recording = Recording::new();
+        recording.push(0.0, Message::Create);
+        recording.push(0.5, size(800, 600));
+        recording.push(1.0, Message::MouseMove { x: -3, y: 4 });
+
+        let mut bytes = Vec::new();
+        recording.write(&mut bytes).unwrap();
+        assert_eq!(Recording::read(&bytes[..]).unwrap(), recording);
+    }
+
+    #[test]
+    fn recordings_are_split_by_window() {
+        let event = |window, message| TraceEvent {
+            time: 0.0,
+            window,
+            message,
+            wparam: 0,
+            lparam: 0,
+            latency: 0.0,
+        };
+        let recording = Recording::from_events(vec![
+            event(2, 0x0001),
+            event(1, 0x0001),
+            event(2, 0x000F),
+        ]);
+
+        assert_eq!(recording.windows(), [2, 1]);
+        let second = recording.for_window(2);
+        assert_eq!(second.events().len(), 2);
+        assert_eq!(second.events()[1].decoded(), Message::Paint);
+    }
+
+    #[test]
+    fn traced_sessions_replay_in_the_order_messages_arrived() {
+        use std::{cell::RefCell, rc::Rc};
+
+        use crate::trace::{install, traced, TraceFormat, TraceWriter, Tracer};
+
+        #[derive(Clone, Default)]
+        struct Shared(Rc<RefCell<Vec<u8>>>);
+
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.borrow_mut().write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        // Like `DefWindowProc` destroying the window while `WM_CLOSE` is
+        // handled.
+        let output = Shared::default();
+        let guard = install(Tracer::new(
+            TraceWriter::new(TraceFormat::Binary, output.clone()).unwrap(),
+        ));
+        traced(1, 0x0010, 0, 0, || traced(1, 0x0002, 0, 0, || ()));
+        drop(guard);
+
+        let recording = Recording::read(&output.0.borrow()[..]).unwrap();
+        let mut spy = Spy::default();
+        let mut window = VirtualWindow::default();
+        replay(&recording, &mut spy, &mut window);
+        let messages: Vec<_> =
+            spy.seen.iter().map(|&(_, message, _)| message).collect();
+        assert_eq!(messages, [Message::Close, Message::Destroy]);
+        assert_eq!(window.exit_code, Some(3));
+    }
+}
@@ -0,0 +1,267 @@
+//! Dialogs that answer with what a test prepared instead of asking anyone.
+
+use std::{collections::VecDeque, path::PathBuf};
+
+use crate::{
+    file_dialog::{
+        DialogError, DialogFlags, DialogOptions, Dialogs, SaveChoice,
+    },
+    message_box::{DialogResult, MessageBoxOptions},
+};
+
+/// What the user does in the next dialog shown by [`ScriptedDialogs`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Answer {
+    /// Picks these files or folders in an open dialog.
+    Open(Vec<PathBuf>),
+    /// Types this path in a save dialog with the filter at the index picked
+    /// as "Save as type". The extension of that type is appended like the
+    /// real dialog does.
+    Save(PathBuf, Option<usize>),
+    /// Presses this button of a message box.
+    Press(DialogResult),
+    /// Closes the dialog without picking anything, or the message box
+    /// without pressing a button.
+    Cancel,
+}
+
+/// Answers dialogs and message boxes in order with preset [`Answer`]s and
+/// keeps the options of every dialog it was asked to show, and the options
+/// of every message box.
+///
+/// It panics when a dialog doesn't get the kind of answer it asks for, or
+/// there is no answer left, since that is a mistake in the test.
+#[derive(Clone, Debug, Default)]
+pub struct ScriptedDialogs {
+    answers: VecDeque<Answer>,
+    shown: Vec<DialogOptions>,
+    messages: Vec<MessageBoxOptions>,
+}
+
+impl ScriptedDialogs {
+    pub fn new(answers: impl IntoIterator<Item = Answer>) -> Self {
+        ScriptedDialogs {
+            answers: answers.into_iter().collect(),
+            shown: Vec::new(),
+            messages: Vec::new(),
+        }
+    }
+
+    /// The options of the dialogs shown so far, oldest first.
+    pub fn shown(&self) -> &[DialogOptions] {
+        &self.shown
+    }
+
+    /// The options of the message boxes shown so far, oldest first.
+    pub fn messages(&self) -> &[MessageBoxOptions] {
+        &self.messages
+    }
+
+    /// How many answers weren't given yet.
+    pub fn remaining(&self) -> usize {
+        self.answers.len()
+    }
+
+    fn next(&mut self, options: &DialogOptions) -> Answer {
+        self.shown.push(options.clone());
+        self.answers.pop_front().unwrap_or_else(|| {
+            panic!("no answer left for dialog {}", self.shown.len())
+        })
+    }
+
+    fn next_message(&mut self, options: &MessageBoxOptions) -> Answer {
+        self.messages.push(options.clone());
+        self.answers.pop_front().unwrap_or_else(|| {
+            panic!("no answer left for message box {}", self.messages.len())
+        })
+    }
+}
+
+impl Dialogs for ScriptedDialogs {
+    fn open(
+        &mut self,
+        options: &DialogOptions,
+    ) -> Result<Vec<PathBuf>, DialogError> {
+        match self.next(options) {
+            Answer::Open(paths) => {
+                let multi_select =
+                    options.flags.contains(DialogFlags::ALLOWMULTISELECT);
+                assert!(
+                    paths.len() == 1 || multi_select && !paths.is_empty(),
+                    "can't pick {} paths in dialog {}",
+                    paths.len(),
+                    self.shown.len()
+                );
+                Ok(paths)
+            }
+            Answer::Cancel => Err(DialogError::Cancelled),
+            answer => {
+                panic!(
+                    "{:?} doesn't answer open dialog {}",
+                    answer,
+                    self.shown.len()
+                )
+            }
+        }
+    }
+
+    fn save(
+        &mut self,
+        options: &DialogOptions,
+    ) -> Result<SaveChoice, DialogError> {
+        match self.next(options) {
+            Answer::Save(path, file_type) => {
+                assert!(
+                    file_type.is_none_or(|index| index < options.filters.len()),
+                    "there is no file type {:?} in dialog {}",
+                    file_type,
+                    self.shown.len()
+                );
+                Ok(SaveChoice::new(options, path, file_type))
+            }
+            Answer::Cancel => Err(DialogError::Cancelled),
+            answer => {
+                panic!(
+                    "{:?} doesn't answer save dialog {}",
+                    answer,
+                    self.shown.len()
+                )
+            }
+        }
+    }
+
+    fn message_box(
+        &mut self,
+        options: &MessageBoxOptions,
+    ) -> Result<DialogResult, DialogError> {
+        match self.next_message(options) {
+            Answer::Press(result) => {
+                assert!(
+                    options.buttons.results().contains(&result),
+                    "message box {} has no {} button",
+                    self.messages.len(),
+                    result
+                );
+                Ok(result)
+            }
+            Answer::Cancel => {
+                options.buttons.close_result().ok_or(DialogError::Cancelled)
+            }
+            answer => panic!(
+                "{:?} doesn't answer message box {}",
+                answer,
+                self.messages.len()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        file_dialog::{FileDialog, SaveDialog},
+        message_box::{Buttons, MessageBox},
+    };
+
+    #[test]
+    fn answers_are_given_in_order() {
+        let mut dialogs = ScriptedDialogs::new([
+            Answer::Open(vec![PathBuf::from("a.trace")]),
+            Answer::Cancel,
+            Answer::Save(PathBuf::from("out/clock"), Some(0)),
+        ]);
+        let open = FileDialog::new().title("Open a trace");
+        let save = SaveDialog::new().filter("PNG images", &["*.png"]);
+
+        assert_eq!(
+            open.show_with(&mut dialogs).unwrap(),
+            [PathBuf::from("a.trace")]
+        );
+        assert!(open.show_with(&mut dialogs).unwrap_err().is_cancelled());
+        assert_eq!(
+            save.show_with(&mut dialogs).unwrap(),
+            SaveChoice {
+                path: PathBuf::from("out/clock.png"),
+                file_type: Some(0),
+            }
+        );
+
+        assert_eq!(dialogs.remaining(), 0);
+        let titles: Vec<_> = dialogs
+            .shown()
+            .iter()
+            .map(|options| options.title.as_deref())
+            .collect();
+        assert_eq!(titles, [Some("Open a trace"), Some("Open a trace"), None]);
+    }
+
+    #[test]
+    fn invalid_options_never_reach_the_dialogs() {
+        let mut dialogs = ScriptedDialogs::new([Answer::Cancel]);
+        let result = FileDialog::new().folder("traces").show_with(&mut dialogs);
+
+        assert!(matches!(result, Err(DialogError::RelativeFolder(_))));
+        assert!(dialogs.shown().is_empty());
+        assert_eq!(dialogs.remaining(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "can't pick 2 paths in dialog 1")]
+    fn several_paths_need_multi_select() {
+        let paths = vec![PathBuf::from("a"), PathBuf::from("b")];
+        let mut dialogs = ScriptedDialogs::new([Answer::Open(paths)]);
+        FileDialog::new().show_with(&mut dialogs).ok();
+    }
+
+    #[test]
+    #[should_panic(expected = "no answer left for dialog 1")]
+    fn running_out_of_answers_panics() {
+        SaveDialog::new()
+            .show_with(&mut ScriptedDialogs::default())
+            .ok();
+    }
+
+    #[test]
+    fn message_boxes_get_their_answers_in_turn() {
+        let mut dialogs = ScriptedDialogs::new([
+            Answer::Open(vec![PathBuf::from("clock.trace")]),
+            Answer::Press(DialogResult::No),
+            Answer::Cancel,
+            Answer::Cancel,
+        ]);
+        let question = MessageBox::new("Replay clock.trace?")
+            .buttons(Buttons::YesNo)
+            .default_button(1);
+
+        FileDialog::new().show_with(&mut dialogs).unwrap();
+        assert_eq!(question.show_with(&mut dialogs).unwrap(), DialogResult::No);
+        assert!(question.show_with(&mut dialogs).unwrap_err().is_cancelled());
+        assert_eq!(
+            MessageBox::new("Replayed")
+                .buttons(Buttons::OkCancel)
+                .show_with(&mut dialogs)
+                .unwrap(),
+            DialogResult::Cancel
+        );
+
+        assert_eq!(dialogs.shown().len(), 1);
+        let texts: Vec<_> = dialogs
+            .messages()
+            .iter()
+            .map(|options| options.text.as_str())
+            .collect();
+        assert_eq!(
+            texts,
+            ["Replay clock.trace?", "Replay clock.trace?", "Replayed"]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "message box 1 has no Retry button")]
+    fn pressed_buttons_have_to_exist() {
+        let mut dialogs =
+            ScriptedDialogs::new([Answer::Press(DialogResult::Retry)]);
+        MessageBox::new("Done").show_with(&mut dialogs).ok();
+    }
+}
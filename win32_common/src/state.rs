@@ -0,0 +1,338 @@
+use std::{ffi::c_void, marker::PhantomData};
+
+#[cfg(windows)]
+use windows::Win32::{
+    Foundation::{HWND, LPARAM},
+    UI::WindowsAndMessaging::{
+        CREATESTRUCTW, GWLP_USERDATA, WM_NCCREATE, WM_NCDESTROY,
+    },
+};
+
+/// Per-window storage for a single pointer-sized value.
+///
+/// On Windows this is the `GWLP_USERDATA` slot of a window; tests use a table
+/// of fake handles instead.
+pub trait UserDataSlots {
+    type Handle: Copy;
+
+    /// The stored value, `0` if nothing was stored yet.
+    fn user_data(&self, handle: Self::Handle) -> isize;
+
+    fn set_user_data(&mut self, handle: Self::Handle, value: isize);
+}
+
+/// Typed state owned by a window.
+///
+/// The state is boxed by [`WindowState::create_param`] and handed to
+/// `CreateWindowEx` as its `lpParam`. On `WM_NCCREATE` the window takes
+/// ownership of the box with [`WindowState::attach`], every later message can
+/// borrow it with [`WindowState::get`], and on `WM_NCDESTROY`
+/// [`WindowState::detach`] gives it back so it is dropped together with the
+/// window.
+pub struct WindowState<T> {
+    _state: PhantomData<T>,
+}
+
+impl<T> WindowState<T> {
+    /// Boxes `state` and returns the pointer to pass to `CreateWindowEx`.
+    ///
+    /// If no window ever attaches the state, it is leaked.
+    pub fn create_param(state: T) -> *const c_void {
+        Box::into_raw(Box::new(state)) as *const c_void
+    }
+
+    /// Stores the state created by [`WindowState::create_param`] in the
+    /// window. Returns `false` if `create_param` is null. State that was
+    /// already attached to the window is dropped.
+    ///
+    /// # Safety
+    ///
+    /// `create_param` must be null or come from
+    /// [`WindowState::<T>::create_param`], and must not be attached to any
+    /// other window.
+    pub unsafe fn attach<S: UserDataSlots>(
+        slots: &mut S,
+        handle: S::Handle,
+        create_param: *const c_void,
+    ) -> bool {
+        if create_param.is_null() {
+            return false;
+        }
+
+        let previous = slots.user_data(handle) as *mut T;
+        slots.set_user_data(handle, create_param as isize);
+
+        if !previous.is_null() {
+            drop(Box::from_raw(previous));
+        }
+        true
+    }
+
+    /// The state attached to the window, or `None` if the window doesn't
+    /// have any (yet).
+    ///
+    /// # Safety
+    ///
+    /// Only state of type `T` may be attached to the window, and the returned
+    /// reference must be dropped before the state is detached or borrowed
+    /// again.
+    pub unsafe fn get<'a, S: UserDataSlots>(
+        slots: &S,
+        handle: S::Handle,
+    ) -> Option<&'a mut T> {
+        (slots.user_data(handle) as *mut T).as_mut()
+    }
+
+    /// Takes the state back from the window, leaving it without state.
+    ///
+    /// # Safety
+    ///
+    /// Only state of type `T` may be attached to the window, and no reference
+    /// returned by [`WindowState::get`] may still be alive.
+    pub unsafe fn detach<S: UserDataSlots>(
+        slots: &mut S,
+        handle: S::Handle,
+    ) -> Option<T> {
+        let state = slots.user_data(handle) as *mut T;
+        if state.is_null() {
+            return None;
+        }
+
+        slots.set_user_data(handle, 0);
+        Some(*Box::from_raw(state))
+    }
+}
+
+/// The `GWLP_USERDATA` slots of real windows.
+#[cfg(windows)]
+pub struct Win32UserData;
+
+#[cfg(windows)]
+impl UserDataSlots for Win32UserData {
+    type Handle = HWND;
+
+    fn user_data(&self, handle: HWND) -> isize {
+        unsafe { GetWindowLong(handle) }
+    }
+
+    fn set_user_data(&mut self, handle: HWND, value: isize) {
+        unsafe { SetWindowLong(handle, value) };
+    }
+}
+
+#[cfg(windows)]
+impl<T> WindowState<T> {
+    /// Does the bookkeeping for a message received by a window procedure:
+    /// attaches the state on `WM_NCCREATE`, drops it on `WM_NCDESTROY` and
+    /// returns it for every message in between.
+    ///
+    /// # Safety
+    ///
+    /// The window must have been created with a create parameter from
+    /// [`WindowState::<T>::create_param`], and the returned reference must not
+    /// outlive the handling of `message`.
+    pub unsafe fn lookup<'a>(
+        window: HWND,
+        message: u32,
+        lparam: LPARAM,
+    ) -> Option<&'a mut T> {
+        match message {
+            WM_NCCREATE => {
+                let create_struct = &*(lparam.0 as *const CREATESTRUCTW);
+                Self::attach(
+                    &mut Win32UserData,
+                    window,
+                    create_struct.lpCreateParams,
+                );
+                Self::get(&Win32UserData, window)
+            }
+            WM_NCDESTROY => {
+                drop(Self::detach(&mut Win32UserData, window));
+                None
+            }
+            _ => Self::get(&Win32UserData, window),
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(all(windows, target_pointer_width = "32"))]
+unsafe fn SetWindowLong(window: HWND, value: isize) -> isize {
+    use windows::Win32::UI::WindowsAndMessaging::SetWindowLongW;
+    SetWindowLongW(window, GWLP_USERDATA, value as _) as _
+}
+
+#[allow(non_snake_case)]
+#[cfg(all(windows, target_pointer_width = "64"))]
+unsafe fn SetWindowLong(window: HWND, value: isize) -> isize {
+    use windows::Win32::UI::WindowsAndMessaging::SetWindowLongPtrW;
+    SetWindowLongPtrW(window, GWLP_USERDATA, value)
+}
+
+#[allow(non_snake_case)]
+#[cfg(all(windows, target_pointer_width = "32"))]
+unsafe fn GetWindowLong(window: HWND) -> isize {
+    use windows::Win32::UI::WindowsAndMessaging::GetWindowLongW;
+    GetWindowLongW(window, GWLP_USERDATA) as _
+}
+
+#[allow(non_snake_case)]
+#[cfg(all(windows, target_pointer_width = "64"))]
+unsafe fn GetWindowLong(window: HWND) -> isize {
+    use windows::Win32::UI::WindowsAndMessaging::GetWindowLongPtrW;
+    GetWindowLongPtrW(window, GWLP_USERDATA)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::Cell, collections::HashMap, rc::Rc};
+
+    #[derive(Default)]
+    struct FakeHandleTable {
+        slots: HashMap<u32, isize>,
+    }
+
+    impl UserDataSlots for FakeHandleTable {
+        type Handle = u32;
+
+        fn user_data(&self, handle: u32) -> isize {
+            self.slots.get(&handle).copied().unwrap_or(0)
+        }
+
+        fn set_user_data(&mut self, handle: u32, value: isize) {
+            self.slots.insert(handle, value);
+        }
+    }
+
+    // Counts how often it has been dropped.
+    struct Tracked {
+        value: u8,
+        drops: Rc<Cell<u32>>,
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.drops.set(self.drops.get() + 1);
+        }
+    }
+
+    fn tracked(value: u8) -> (Tracked, Rc<Cell<u32>>) {
+        let drops = Rc::new(Cell::new(0));
+        let state = Tracked {
+            value,
+            drops: drops.clone(),
+        };
+        (state, drops)
+    }
+
+    #[test]
+    fn nothing_is_handed_out_before_attach() {
+        let table = FakeHandleTable::default();
+        let state = unsafe { WindowState::<u8>::get(&table, 1) };
+        assert!(state.is_none());
+    }
+
+    #[test]
+    fn attached_state_can_be_mutated() {
+        let mut table = FakeHandleTable::default();
+        let param = WindowState::create_param(100_u8);
+
+        unsafe {
+            assert!(WindowState::<u8>::attach(&mut table, 1, param));
+            *WindowState::<u8>::get(&table, 1).unwrap() += 1;
+            assert_eq!(*WindowState::<u8>::get(&table, 1).unwrap(), 101);
+            assert_eq!(WindowState::<u8>::detach(&mut table, 1), Some(101));
+        }
+    }
+
+    #[test]
+    fn null_create_param_is_not_attached() {
+        let mut table = FakeHandleTable::default();
+
+        unsafe {
+            assert!(!WindowState::<u8>::attach(
+                &mut table,
+                1,
+                std::ptr::null()
+            ));
+            assert!(WindowState::<u8>::get(&table, 1).is_none());
+        }
+    }
+
+    #[test]
+    fn state_is_per_window() {
+        let mut table = FakeHandleTable::default();
+
+        unsafe {
+            WindowState::<u8>::attach(
+                &mut table,
+                1,
+                WindowState::create_param(1_u8),
+            );
+            WindowState::<u8>::attach(
+                &mut table,
+                2,
+                WindowState::create_param(2_u8),
+            );
+
+            assert_eq!(*WindowState::<u8>::get(&table, 1).unwrap(), 1);
+            assert_eq!(*WindowState::<u8>::get(&table, 2).unwrap(), 2);
+            assert_eq!(WindowState::<u8>::detach(&mut table, 1), Some(1));
+            assert_eq!(*WindowState::<u8>::get(&table, 2).unwrap(), 2);
+            assert_eq!(WindowState::<u8>::detach(&mut table, 2), Some(2));
+        }
+    }
+
+    #[test]
+    fn detach_frees_the_state_exactly_once() {
+        let mut table = FakeHandleTable::default();
+        let (state, drops) = tracked(7);
+
+        unsafe {
+            WindowState::<Tracked>::attach(
+                &mut table,
+                1,
+                WindowState::create_param(state),
+            );
+            assert_eq!(drops.get(), 0);
+
+            let detached = WindowState::<Tracked>::detach(&mut table, 1);
+            assert_eq!(detached.as_ref().map(|state| state.value), Some(7));
+            drop(detached);
+            assert_eq!(drops.get(), 1);
+
+            assert!(WindowState::<Tracked>::detach(&mut table, 1).is_none());
+            assert!(WindowState::<Tracked>::get(&table, 1).is_none());
+        }
+        assert_eq!(drops.get(), 1);
+    }
+
+    #[test]
+    fn attaching_again_drops_the_previous_state() {
+        let mut table = FakeHandleTable::default();
+        let (first, first_drops) = tracked(1);
+        let (second, second_drops) = tracked(2);
+
+        unsafe {
+            WindowState::<Tracked>::attach(
+                &mut table,
+                1,
+                WindowState::create_param(first),
+            );
+            WindowState::<Tracked>::attach(
+                &mut table,
+                1,
+                WindowState::create_param(second),
+            );
+            assert_eq!(first_drops.get(), 1);
+            assert_eq!(
+                WindowState::<Tracked>::get(&table, 1).unwrap().value,
+                2
+            );
+
+            drop(WindowState::<Tracked>::detach(&mut table, 1));
+        }
+        assert_eq!(second_drops.get(), 1);
+    }
+}
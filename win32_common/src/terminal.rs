@@ -0,0 +1,564 @@
+//! File dialogs and message boxes in a terminal, for platforms without
+//! the common item dialog.
+//!
+//! A [`TerminalDialogs`] lists the folder it is in, folders first and files
+//! of the selected type after them, numbered, and reads one command per
+//! line; [`HELP`] lists them. A message box prints its text and reads the
+//! button to press. Reading and writing go through any `BufRead` and
+//! `Write`, so tests can type their commands and read the listings.
+
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    file_dialog::{
+        DialogError, DialogFlags, DialogOptions, Dialogs, Filter, SaveChoice,
+    },
+    message_box::{DialogResult, MessageBoxOptions},
+};
+
+/// What the commands of a [`TerminalDialogs`] do, printed for `?`.
+pub const HELP: &str = "\
+Commands:
+  N        open folder N, or pick file N
+  +N       add file or folder N to the selection, or take it out again
+  ..       go to the parent folder
+  cd DIR   go to the folder DIR
+  type N   show the files of type N
+  name F   save as F in this folder
+  (empty)  done: pick the selection, the folder or the suggested name
+  q        cancel
+  ?        show this help";
+
+/// Shows file dialogs as listings in a terminal and reads commands from it.
+pub struct TerminalDialogs<R, W> {
+    input: R,
+    output: W,
+}
+
+impl TerminalDialogs<io::StdinLock<'static>, io::Stdout> {
+    pub fn stdio() -> Self {
+        TerminalDialogs::new(io::stdin().lock(), io::stdout())
+    }
+}
+
+impl<R: BufRead, W: Write> TerminalDialogs<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        TerminalDialogs { input, output }
+    }
+
+    /// What was written to the terminal.
+    pub fn output(&self) -> &W {
+        &self.output
+    }
+
+    // The next command, or `None` at the end of the input.
+    fn command(&mut self) -> io::Result<Option<Command>> {
+        loop {
+            write!(self.output, "> ")?;
+            self.output.flush()?;
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            match parse(&line) {
+                Some(command) => return Ok(Some(command)),
+                None => writeln!(
+                    self.output,
+                    "Unknown command {:?}, type ? for help.",
+                    line.trim()
+                )?,
+            }
+        }
+    }
+
+    // Asks a yes or no question, answered with no at the end of the input.
+    fn confirm(&mut self, question: &str) -> io::Result<bool> {
+        write!(self.output, "{} [y/N] ", question)?;
+        self.output.flush()?;
+        let mut line = String::new();
+        self.input.read_line(&mut line)?;
+        let answer = line.trim().to_lowercase();
+        Ok(answer == "y" || answer == "yes")
+    }
+
+    fn show(&mut self, title: &str, browser: &Browser) -> io::Result<()> {
+        let output = &mut self.output;
+        writeln!(output)?;
+        writeln!(output, "{}", title)?;
+        writeln!(output, "Folder: {}", browser.folder.display())?;
+        if let (Some(index), Some(filter)) =
+            (browser.file_type, browser.filter())
+        {
+            writeln!(
+                output,
+                "Type {} of {}: {} ({})",
+                index + 1,
+                browser.options.filters.len(),
+                filter.name(),
+                filter.patterns().join(", ")
+            )?;
+        }
+        for (index, entry) in browser.entries.iter().enumerate() {
+            let mark = if browser.selection.contains(&entry.path) {
+                '*'
+            } else {
+                ' '
+            };
+            let slash = if entry.is_folder { "/" } else { "" };
+            writeln!(
+                output,
+                "{} {:>3}  {}{}",
+                mark,
+                index + 1,
+                entry.name,
+                slash
+            )?;
+        }
+        if browser.entries.is_empty() {
+            writeln!(output, "        (nothing here)")?;
+        }
+        Ok(())
+    }
+
+    // Runs the commands every dialog understands, returning whether
+    // `command` was one of them.
+    fn navigate(
+        &mut self,
+        browser: &mut Browser,
+        command: &Command,
+    ) -> io::Result<bool> {
+        match command {
+            Command::Up => match browser.folder.parent() {
+                Some(parent) => {
+                    let parent = parent.to_owned();
+                    self.go(browser, parent)?;
+                }
+                None => writeln!(self.output, "There is no parent folder.")?,
+            },
+            Command::Go(folder) => {
+                let folder = normalize(&browser.folder.join(folder));
+                self.go(browser, folder)?;
+            }
+            Command::FileType(index)
+                if *index < browser.options.filters.len() =>
+            {
+                browser.file_type = Some(*index);
+                let folder = browser.folder.clone();
+                self.go(browser, folder)?;
+            }
+            Command::FileType(index) => {
+                writeln!(self.output, "There is no type {}.", index + 1)?
+            }
+            Command::Help => writeln!(self.output, "{}", HELP)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    // Goes to `folder`, or stays where it is if that can't be listed.
+    fn go(&mut self, browser: &mut Browser, folder: PathBuf) -> io::Result<()> {
+        match browser.read(&folder) {
+            Ok(entries) => {
+                browser.folder = folder;
+                browser.entries = entries;
+            }
+            Err(error) => writeln!(
+                self.output,
+                "Can't open {}: {}",
+                folder.display(),
+                error
+            )?,
+        }
+        Ok(())
+    }
+
+    fn no_entry(&mut self, index: usize) -> io::Result<()> {
+        writeln!(self.output, "There is no entry {}.", index + 1)
+    }
+}
+
+impl<R: BufRead, W: Write> Dialogs for TerminalDialogs<R, W> {
+    fn open(
+        &mut self,
+        options: &DialogOptions,
+    ) -> Result<Vec<PathBuf>, DialogError> {
+        let mut browser = Browser::new(options)?;
+        let multi_select =
+            options.flags.contains(DialogFlags::ALLOWMULTISELECT);
+        let picks_folders = browser.picks_folders();
+        let title = options.title.as_deref().unwrap_or(if picks_folders {
+            "Select Folder"
+        } else {
+            "Open"
+        });
+
+        loop {
+            self.show(title, &browser)?;
+            let command = match self.command()? {
+                Some(command) => command,
+                None => return Err(DialogError::Cancelled),
+            };
+            if self.navigate(&mut browser, &command)? {
+                continue;
+            }
+
+            match command {
+                Command::Open(index) => match browser.entry(index) {
+                    Some(entry) if entry.is_folder => {
+                        self.go(&mut browser, entry.path)?
+                    }
+                    Some(entry) if !multi_select => {
+                        return Ok(vec![entry.path]);
+                    }
+                    Some(entry) => browser.toggle(entry.path, multi_select),
+                    None => self.no_entry(index)?,
+                },
+                Command::Toggle(index) => match browser.entry(index) {
+                    Some(entry) => browser.toggle(entry.path, multi_select),
+                    None => self.no_entry(index)?,
+                },
+                Command::Done if !browser.selection.is_empty() => {
+                    return Ok(browser.selection);
+                }
+                Command::Done if picks_folders => {
+                    return Ok(vec![browser.folder]);
+                }
+                Command::Done => {
+                    writeln!(self.output, "Nothing is selected.")?;
+                }
+                Command::Name(_) => {
+                    writeln!(self.output, "Pick a file by its number instead.")?
+                }
+                Command::Cancel => return Err(DialogError::Cancelled),
+                _ => unreachable!("handled by navigate"),
+            }
+        }
+    }
+
+    fn save(
+        &mut self,
+        options: &DialogOptions,
+    ) -> Result<SaveChoice, DialogError> {
+        let mut browser = Browser::new(options)?;
+        let title = options.title.as_deref().unwrap_or("Save As");
+
+        loop {
+            self.show(title, &browser)?;
+            if let Some(file_name) = &options.file_name {
+                writeln!(self.output, "File name: {}", file_name)?;
+            }
+            let command = match self.command()? {
+                Some(command) => command,
+                None => return Err(DialogError::Cancelled),
+            };
+            if self.navigate(&mut browser, &command)? {
+                continue;
+            }
+
+            let path = match command {
+                Command::Open(index) => match browser.entry(index) {
+                    Some(entry) if entry.is_folder => {
+                        self.go(&mut browser, entry.path)?;
+                        None
+                    }
+                    Some(entry) => Some(entry.path),
+                    None => {
+                        self.no_entry(index)?;
+                        None
+                    }
+                },
+                Command::Name(file_name) if is_file_name(&file_name) => {
+                    Some(browser.folder.join(file_name))
+                }
+                Command::Name(file_name) => {
+                    writeln!(
+                        self.output,
+                        "{:?} isn't a file name, use cd to change folders.",
+                        file_name
+                    )?;
+                    None
+                }
+                Command::Done => match &options.file_name {
+                    Some(file_name) => Some(browser.folder.join(file_name)),
+                    None => {
+                        writeln!(self.output, "Type name F to save as F.")?;
+                        None
+                    }
+                },
+                Command::Toggle(_) => {
+                    writeln!(self.output, "Only one file can be saved.")?;
+                    None
+                }
+                Command::Cancel => return Err(DialogError::Cancelled),
+                _ => unreachable!("handled by navigate"),
+            };
+
+            if let Some(path) = path {
+                let choice = SaveChoice::new(options, path, browser.file_type);
+                let prompts =
+                    options.flags.contains(DialogFlags::OVERWRITEPROMPT);
+                if prompts && choice.path.exists() {
+                    let question = format!(
+                        "{} already exists. Replace it?",
+                        choice.path.display()
+                    );
+                    if !self.confirm(&question)? {
+                        continue;
+                    }
+                }
+                return Ok(choice);
+            }
+        }
+    }
+
+    fn message_box(
+        &mut self,
+        options: &MessageBoxOptions,
+    ) -> Result<DialogResult, DialogError> {
+        let results = options.buttons.results();
+        let default = options.default_result();
+        writeln!(self.output)?;
+        if let Some(caption) = &options.caption {
+            writeln!(self.output, "{}", caption)?;
+        }
+        match options.icon.label() {
+            Some(label) => {
+                writeln!(self.output, "{}: {}", label, options.text)?
+            }
+            None => writeln!(self.output, "{}", options.text)?,
+        }
+
+        // The default button is in brackets, like `[Yes]  No  Cancel`.
+        let buttons: Vec<_> = results
+            .iter()
+            .map(|&result| {
+                if result == default {
+                    format!("[{}]", result)
+                } else {
+                    result.to_string()
+                }
+            })
+            .collect();
+        loop {
+            write!(self.output, "{} > ", buttons.join("  "))?;
+            self.output.flush()?;
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                return options
+                    .buttons
+                    .close_result()
+                    .ok_or(DialogError::Cancelled);
+            }
+
+            // A button is pressed by typing the start of its label.
+            let answer = line.trim().to_lowercase();
+            if answer.is_empty() {
+                return Ok(default);
+            }
+            let pressed = results.iter().find(|result| {
+                result.label().to_lowercase().starts_with(&answer)
+            });
+            match pressed {
+                Some(&result) => return Ok(result),
+                None => writeln!(
+                    self.output,
+                    "Type one of the buttons, or nothing for {}.",
+                    default
+                )?,
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    // Entries are numbered from one on screen and from zero here.
+    Open(usize),
+    Toggle(usize),
+    Up,
+    Go(String),
+    FileType(usize),
+    Name(String),
+    Done,
+    Cancel,
+    Help,
+}
+
+// The command typed on `line`, if it is one.
+fn parse(line: &str) -> Option<Command> {
+    let line = line.trim();
+    let (word, argument) = match line.split_once(char::is_whitespace) {
+        Some((word, argument)) => (word, argument.trim()),
+        None => (line, ""),
+    };
+    let number = |text: &str| {
+        text.parse::<usize>().ok().filter(|&n| n > 0).map(|n| n - 1)
+    };
+
+    Some(match (word, argument) {
+        ("", _) => Command::Done,
+        ("q", "") => Command::Cancel,
+        ("?", "") => Command::Help,
+        ("..", "") => Command::Up,
+        ("cd", folder) if !folder.is_empty() => Command::Go(folder.to_owned()),
+        ("type", index) => Command::FileType(number(index)?),
+        ("name", file_name) if !file_name.is_empty() => {
+            Command::Name(file_name.to_owned())
+        }
+        (word, "") => match word.strip_prefix('+') {
+            Some(index) => Command::Toggle(number(index)?),
+            None => Command::Open(number(word)?),
+        },
+        _ => return None,
+    })
+}
+
+// The path without `.` and with `..` taking out the component before it.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn is_file_name(name: &str) -> bool {
+    !name.contains(['/', '\\', '\0']) && name != "." && name != ".."
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    name: String,
+    path: PathBuf,
+    is_folder: bool,
+}
+
+// Where a dialog is and what is selected.
+struct Browser<'a> {
+    options: &'a DialogOptions,
+    folder: PathBuf,
+    file_type: Option<usize>,
+    entries: Vec<Entry>,
+    selection: Vec<PathBuf>,
+}
+
+impl<'a> Browser<'a> {
+    // A browser in the initial folder of the options, or the working
+    // directory.
+    fn new(options: &'a DialogOptions) -> io::Result<Self> {
+        let folder = match &options.folder {
+            Some(folder) => folder.clone(),
+            None => std::env::current_dir()?,
+        };
+        let mut browser = Browser {
+            options,
+            folder,
+            file_type: options.file_type,
+            entries: Vec::new(),
+            selection: Vec::new(),
+        };
+        browser.entries = browser.read(&browser.folder)?;
+        Ok(browser)
+    }
+
+    fn picks_folders(&self) -> bool {
+        self.options.flags.contains(DialogFlags::PICKFOLDERS)
+    }
+
+    fn filter(&self) -> Option<&Filter> {
+        self.options.filters.get(self.file_type?)
+    }
+
+    fn entry(&self, index: usize) -> Option<Entry> {
+        self.entries.get(index).cloned()
+    }
+
+    // The folders in `folder`, then the files of the selected type unless
+    // picking folders, each sorted by name.
+    fn read(&self, folder: &Path) -> io::Result<Vec<Entry>> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(folder)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let path = entry.path();
+            let is_folder = path.is_dir();
+            let is_shown = is_folder
+                || !self.picks_folders()
+                    && self.filter().is_none_or(|filter| filter.matches(&name));
+            if is_shown {
+                entries.push(Entry {
+                    name,
+                    path,
+                    is_folder,
+                });
+            }
+        }
+        entries
+            .sort_by_key(|entry| (!entry.is_folder, entry.name.to_lowercase()));
+        Ok(entries)
+    }
+
+    // Adds `path` to the selection or takes it out again. Without
+    // multi-select it replaces the selection.
+    fn toggle(&mut self, path: PathBuf, multi_select: bool) {
+        if let Some(index) = self.selection.iter().position(|p| *p == path) {
+            self.selection.remove(index);
+        } else if multi_select {
+            self.selection.push(path);
+        } else {
+            self.selection = vec![path];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_parsed() {
+        assert_eq!(parse("3\n"), Some(Command::Open(2)));
+        assert_eq!(parse(" +1 "), Some(Command::Toggle(0)));
+        assert_eq!(parse(".."), Some(Command::Up));
+        assert_eq!(
+            parse("cd My Traces"),
+            Some(Command::Go("My Traces".to_owned()))
+        );
+        assert_eq!(parse("type 2"), Some(Command::FileType(1)));
+        assert_eq!(
+            parse("name clock 2.png"),
+            Some(Command::Name("clock 2.png".to_owned()))
+        );
+        assert_eq!(parse("\n"), Some(Command::Done));
+        assert_eq!(parse("q"), Some(Command::Cancel));
+        assert_eq!(parse("?"), Some(Command::Help));
+    }
+
+    #[test]
+    fn malformed_commands_are_rejected() {
+        for line in
+            ["0", "+0", "-1", "x", "type", "type x", "cd", "name", "q x"]
+        {
+            assert_eq!(parse(line), None, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn paths_are_normalized() {
+        assert_eq!(
+            normalize(Path::new("/traces/./2021/../clock")),
+            Path::new("/traces/clock")
+        );
+        assert_eq!(normalize(Path::new("/..")), Path::new("/"));
+    }
+}
@@ -0,0 +1,747 @@
+//! Structured tracing of the messages a window procedure handles.
+//!
+//! Wrap the window procedure in [`traced`] and install a [`Tracer`] on the
+//! thread, usually with [`install_from_env`]. Every message whose kind
+//! passes the tracer's filter is then written with the time it arrived, the
+//! window it was sent to and how long handling it took, as text, JSON lines
+//! or a compact binary file that [`read_binary`] reads back.
+//!
+//! `WIN32_TRACE` picks the format and destination: `text` for stderr,
+//! `text:<path>`, `json:<path>` or `binary:<path>`. `WIN32_TRACE_FILTER` is
+//! a comma-separated list of [`MessageKind::name`]s to trace, where `all`
+//! adds every kind and a leading `-` removes one again, like
+//! `all,-mouse_move`. Without a filter every message is traced.
+
+use std::{
+    cell::RefCell,
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    time::Instant,
+};
+
+use crate::message::{decode, Message, MessageKind};
+
+/// The first bytes of a binary trace, followed by a version.
+pub const BINARY_MAGIC: [u8; 8] = *b"W32TRACE";
+pub const BINARY_VERSION: u16 = 1;
+const RECORD_LEN: usize = 44;
+
+/// One handled message. Times are in seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TraceEvent {
+    /// When the message arrived, since the tracer was created.
+    pub time: f64,
+    /// The handle of the window the message was sent to.
+    pub window: isize,
+    pub message: u32,
+    pub wparam: usize,
+    pub lparam: isize,
+    /// How long the window procedure took to handle the message.
+    pub latency: f64,
+}
+
+impl TraceEvent {
+    pub fn decoded(&self) -> Message {
+        decode(self.message, self.wparam, self.lparam)
+    }
+}
+
+/// A set of [`MessageKind`]s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KindFilter(u32);
+
+impl KindFilter {
+    pub fn all() -> Self {
+        KindFilter::none().with_all(&MessageKind::ALL)
+    }
+
+    pub fn none() -> Self {
+        KindFilter(0)
+    }
+
+    pub fn with(self, kind: MessageKind) -> Self {
+        KindFilter(self.0 | bit(kind))
+    }
+
+    pub fn without(self, kind: MessageKind) -> Self {
+        KindFilter(self.0 & !bit(kind))
+    }
+
+    pub fn with_all(self, kinds: &[MessageKind]) -> Self {
+        kinds.iter().fold(self, |filter, &kind| filter.with(kind))
+    }
+
+    pub fn contains(self, kind: MessageKind) -> bool {
+        self.0 & bit(kind) != 0
+    }
+
+    /// Parses a filter like `paint,size` or `all,-mouse_move`, see the
+    /// [module documentation](self).
+    pub fn parse(filter: &str) -> Result<Self, TraceConfigError> {
+        let mut kinds = KindFilter::none();
+        for entry in filter.split(',').map(str::trim) {
+            let (remove, name) = match entry.strip_prefix('-') {
+                Some(name) => (true, name),
+                None => (false, entry),
+            };
+            kinds = match (name, remove) {
+                ("", _) => kinds,
+                ("all", false) => KindFilter::all(),
+                ("all", true) => KindFilter::none(),
+                (name, _) => {
+                    let kind =
+                        MessageKind::from_name(name).ok_or_else(|| {
+                            TraceConfigError::UnknownKind(name.to_string())
+                        })?;
+                    if remove {
+                        kinds.without(kind)
+                    } else {
+                        kinds.with(kind)
+                    }
+                }
+            };
+        }
+        Ok(kinds)
+    }
+}
+
+impl Default for KindFilter {
+    fn default() -> Self {
+        KindFilter::all()
+    }
+}
+
+fn bit(kind: MessageKind) -> u32 {
+    let index = MessageKind::ALL.iter().position(|&k| k == kind).unwrap();
+    1 << index
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// One aligned line per message, for reading along.
+    Text,
+    /// One JSON object per line.
+    JsonLines,
+    /// [`BINARY_MAGIC`] and the version, then fixed-size little-endian
+    /// records, see [`read_binary`].
+    Binary,
+}
+
+/// Where `WIN32_TRACE` and `WIN32_TRACE_FILTER` send which messages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceConfig {
+    pub format: TraceFormat,
+    /// The file to write, `None` for stderr.
+    pub path: Option<String>,
+    pub filter: KindFilter,
+}
+
+impl TraceConfig {
+    /// Parses the values of `WIN32_TRACE` and `WIN32_TRACE_FILTER`.
+    pub fn parse(
+        trace: &str,
+        filter: Option<&str>,
+    ) -> Result<Self, TraceConfigError> {
+        let (format, path) = match trace.split_once(':') {
+            Some((format, path)) => (format, Some(path.to_string())),
+            None => (trace, None),
+        };
+        let format = match (format, &path) {
+            ("text", _) => TraceFormat::Text,
+            ("json", Some(_)) => TraceFormat::JsonLines,
+            ("binary", Some(_)) => TraceFormat::Binary,
+            ("json" | "binary", None) => {
+                return Err(TraceConfigError::MissingPath(format.to_string()))
+            }
+            _ => {
+                return Err(TraceConfigError::UnknownFormat(format.to_string()))
+            }
+        };
+        let filter = match filter {
+            Some(filter) => KindFilter::parse(filter)?,
+            None => KindFilter::all(),
+        };
+        Ok(TraceConfig {
+            format,
+            path,
+            filter,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum TraceConfigError {
+    UnknownFormat(String),
+    /// JSON and binary traces are written to a file.
+    MissingPath(String),
+    UnknownKind(String),
+    /// The trace file couldn't be created.
+    Io(io::Error),
+}
+
+impl fmt::Display for TraceConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceConfigError::UnknownFormat(format) => write!(
+                f,
+                "unknown trace format {:?}, expected text, json or binary",
+                format
+            ),
+            TraceConfigError::MissingPath(format) => {
+                write!(
+                    f,
+                    "{} traces need a path, like {}:trace",
+                    format, format
+                )
+            }
+            TraceConfigError::UnknownKind(kind) => {
+                write!(f, "unknown message kind {:?}", kind)
+            }
+            TraceConfigError::Io(error) => {
+                write!(f, "failed to create the trace file: {}", error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TraceConfigError {}
+
+/// Writes [`TraceEvent`]s in one [`TraceFormat`].
+pub struct TraceWriter {
+    format: TraceFormat,
+    writer: Box<dyn Write>,
+}
+
+impl TraceWriter {
+    /// Starts a trace in `writer`, writing the header of binary traces.
+    pub fn new(
+        format: TraceFormat,
+        mut writer: impl Write + 'static,
+    ) -> io::Result<Self> {
+        if format == TraceFormat::Binary {
+            write_binary_header(&mut writer)?;
+        }
+        Ok(TraceWriter {
+            format,
+            writer: Box::new(writer),
+        })
+    }
+
+    pub fn write(&mut self, event: &TraceEvent) -> io::Result<()> {
+        let w = &mut self.writer;
+        match self.format {
+            TraceFormat::Text => writeln!(
+                w,
+                "{:>12.3} ms  window {:#x}  {:?}  took {:.3} ms",
+                event.time * 1000.0,
+                event.window,
+                event.decoded(),
+                event.latency * 1000.0
+            ),
+            TraceFormat::JsonLines => writeln!(
+                w,
+                "{{\"time_ms\":{:.3},\"window\":{},\"kind\":\"{}\",\
+                 \"message\":{},\"wparam\":{},\"lparam\":{},\
+                 \"decoded\":{},\"latency_ms\":{:.3}}}",
+                event.time * 1000.0,
+                event.window,
+                event.decoded().kind().name(),
+                event.message,
+                event.wparam,
+                event.lparam,
+                json_string(&format!("{:?}", event.decoded())),
+                event.latency * 1000.0
+            ),
+            TraceFormat::Binary => write_binary_record(w, event),
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl fmt::Debug for TraceWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraceWriter")
+            .field("format", &self.format)
+            .finish_non_exhaustive()
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                json.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+pub(crate) fn write_binary_header(mut writer: impl Write) -> io::Result<()> {
+    writer.write_all(&BINARY_MAGIC)?;
+    writer.write_all(&BINARY_VERSION.to_le_bytes())
+}
+
+pub(crate) fn write_binary_record(
+    mut writer: impl Write,
+    event: &TraceEvent,
+) -> io::Result<()> {
+    let mut record = Vec::with_capacity(RECORD_LEN);
+    record.extend(event.time.to_le_bytes());
+    record.extend((event.window as i64).to_le_bytes());
+    record.extend(event.message.to_le_bytes());
+    record.extend((event.wparam as u64).to_le_bytes());
+    record.extend((event.lparam as i64).to_le_bytes());
+    record.extend(event.latency.to_le_bytes());
+    writer.write_all(&record)
+}
+
+/// Reads a trace written in [`TraceFormat::Binary`].
+pub fn read_binary(mut reader: impl Read) -> io::Result<Vec<TraceEvent>> {
+    let invalid = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+
+    let mut header = [0; 10];
+    reader.read_exact(&mut header)?;
+    if header[..8] != BINARY_MAGIC {
+        return Err(invalid("not a message trace"));
+    }
+    if header[8..] != BINARY_VERSION.to_le_bytes() {
+        return Err(invalid("unsupported message trace version"));
+    }
+
+    let mut records = Vec::new();
+    reader.read_to_end(&mut records)?;
+    if records.len() % RECORD_LEN != 0 {
+        return Err(invalid("truncated message trace"));
+    }
+
+    Ok(records
+        .chunks_exact(RECORD_LEN)
+        .map(|record| {
+            let bytes = |at: usize| -> [u8; 8] {
+                record[at..at + 8].try_into().unwrap()
+            };
+            TraceEvent {
+                time: f64::from_le_bytes(bytes(0)),
+                window: i64::from_le_bytes(bytes(8)) as isize,
+                message: u32::from_le_bytes(record[16..20].try_into().unwrap()),
+                wparam: u64::from_le_bytes(bytes(20)) as usize,
+                lparam: i64::from_le_bytes(bytes(28)) as isize,
+                latency: f64::from_le_bytes(bytes(36)),
+            }
+        })
+        .collect())
+}
+
+/// A message the window procedure started handling, see [`Tracer::begin`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span {
+    // Where the message is kept until it can be written, see `Tracer::end`.
+    index: usize,
+    start: f64,
+    window: isize,
+    message: u32,
+    wparam: usize,
+    lparam: isize,
+}
+
+/// Writes the messages of the kinds in its filter to a [`TraceWriter`].
+pub struct Tracer {
+    filter: KindFilter,
+    writer: Option<TraceWriter>,
+    clock: Box<dyn Fn() -> f64>,
+    // The messages that began since no message was being handled, in the
+    // order they began, `None` while they are still being handled.
+    handling: Vec<Option<TraceEvent>>,
+}
+
+impl Tracer {
+    /// Traces every message, timed from now.
+    pub fn new(writer: TraceWriter) -> Self {
+        let start = Instant::now();
+        Tracer {
+            filter: KindFilter::all(),
+            writer: Some(writer),
+            clock: Box::new(move || start.elapsed().as_secs_f64()),
+            handling: Vec::new(),
+        }
+    }
+
+    /// The tracer asked for by `WIN32_TRACE` and `WIN32_TRACE_FILTER`,
+    /// `None` if `WIN32_TRACE` isn't set.
+    pub fn from_env() -> Result<Option<Self>, TraceConfigError> {
+        let trace = match std::env::var("WIN32_TRACE") {
+            Ok(trace) if !trace.is_empty() => trace,
+            _ => return Ok(None),
+        };
+        let filter = std::env::var("WIN32_TRACE_FILTER").ok();
+        let config = TraceConfig::parse(&trace, filter.as_deref())?;
+        Tracer::from_config(&config).map(Some)
+    }
+
+    /// Traces to the stderr or file in `config`.
+    pub fn from_config(config: &TraceConfig) -> Result<Self, TraceConfigError> {
+        let writer = match &config.path {
+            Some(path) => TraceWriter::new(
+                config.format,
+                BufWriter::new(
+                    File::create(path).map_err(TraceConfigError::Io)?,
+                ),
+            ),
+            None => TraceWriter::new(config.format, io::stderr()),
+        }
+        .map_err(TraceConfigError::Io)?;
+        Ok(Tracer::new(writer).with_filter(config.filter))
+    }
+
+    pub fn with_filter(mut self, filter: KindFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Takes the time from `clock`, in seconds, instead of from the
+    /// creation of the tracer.
+    pub fn with_clock(mut self, clock: impl Fn() -> f64 + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Starts timing a message, `None` if its kind is filtered out.
+    pub fn begin(
+        &mut self,
+        window: isize,
+        message: u32,
+        wparam: usize,
+        lparam: isize,
+    ) -> Option<Span> {
+        let kind = decode(message, wparam, lparam).kind();
+        if self.writer.is_none() || !self.filter.contains(kind) {
+            return None;
+        }
+        self.handling.push(None);
+        Some(Span {
+            index: self.handling.len() - 1,
+            start: (self.clock)(),
+            window,
+            message,
+            wparam,
+            lparam,
+        })
+    }
+
+    /// Ends timing the message of `span` now that it was handled.
+    ///
+    /// Messages sent while handling another one end before it, so they are
+    /// only written once every message that began is handled, in the order
+    /// they began. Once writing fails, the error is reported on stderr and
+    /// tracing stops.
+    pub fn end(&mut self, span: Span) {
+        let event = TraceEvent {
+            time: span.start,
+            window: span.window,
+            message: span.message,
+            wparam: span.wparam,
+            lparam: span.lparam,
+            latency: ((self.clock)() - span.start).max(0.0),
+        };
+        match self.handling.get_mut(span.index) {
+            Some(handling) => *handling = Some(event),
+            // The span began with a tracer installed before this one.
+            None => return,
+        }
+        if self.handling.iter().any(Option::is_none) {
+            return;
+        }
+
+        for event in self.handling.drain(..).flatten() {
+            if let Some(writer) = &mut self.writer {
+                if let Err(error) = writer.write(&event) {
+                    eprintln!("Failed to write the message trace: {}", error);
+                    self.writer = None;
+                }
+            }
+        }
+    }
+
+    /// Flushes the writer, which is also done when the tracer is dropped.
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.writer {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Tracer {
+    fn drop(&mut self) {
+        if let Err(error) = self.flush() {
+            eprintln!("Failed to write the message trace: {}", error);
+        }
+    }
+}
+
+impl fmt::Debug for Tracer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tracer")
+            .field("filter", &self.filter)
+            .field("writer", &self.writer)
+            .finish_non_exhaustive()
+    }
+}
+
+thread_local! {
+    static TRACER: RefCell<Option<Tracer>> = const { RefCell::new(None) };
+}
+
+/// Makes `tracer` trace the messages that [`traced`] handles on this
+/// thread, until the returned guard is dropped.
+pub fn install(tracer: Tracer) -> TraceGuard {
+    TRACER.with(|current| *current.borrow_mut() = Some(tracer));
+    TraceGuard(())
+}
+
+/// Installs the tracer asked for by `WIN32_TRACE`, if any. Configuration
+/// errors are reported on stderr and leave tracing off.
+pub fn install_from_env() -> Option<TraceGuard> {
+    match Tracer::from_env() {
+        Ok(tracer) => tracer.map(install),
+        Err(error) => {
+            eprintln!("Not tracing messages: {}", error);
+            None
+        }
+    }
+}
+
+/// Removes the installed tracer when dropped, flushing its trace.
+#[derive(Debug)]
+pub struct TraceGuard(());
+
+impl Drop for TraceGuard {
+    fn drop(&mut self) {
+        let tracer = TRACER.with(|current| current.borrow_mut().take());
+        drop(tracer);
+    }
+}
+
+/// Runs `handle` for a message and traces it with the tracer installed on
+/// this thread, if any.
+///
+/// Window procedures are reentered when handling a message sends another
+/// one, so the tracer isn't borrowed while `handle` runs. Nested messages
+/// are written after the message that sent them, see [`Tracer::end`].
+pub fn traced<R>(
+    window: isize,
+    message: u32,
+    wparam: usize,
+    lparam: isize,
+    handle: impl FnOnce() -> R,
+) -> R {
+    let span = TRACER.with(|tracer| {
+        tracer
+            .borrow_mut()
+            .as_mut()
+            .and_then(|tracer| tracer.begin(window, message, wparam, lparam))
+    });
+    let result = handle();
+    if let Some(span) = span {
+        TRACER.with(|tracer| {
+            if let Some(tracer) = tracer.borrow_mut().as_mut() {
+                tracer.end(span);
+            }
+        });
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+
+    const WM_SIZE: u32 = 0x0005;
+    const WM_PAINT: u32 = 0x000F;
+    const WM_MOUSEMOVE: u32 = 0x0200;
+
+    // A writer whose output stays readable after it was moved into a
+    // `TraceWriter`.
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Shared {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
+    }
+
+    fn tracer(format: TraceFormat) -> (Tracer, Shared, Rc<Cell<f64>>) {
+        let output = Shared::default();
+        let now = Rc::new(Cell::new(0.0));
+        let clock = now.clone();
+        let tracer =
+            Tracer::new(TraceWriter::new(format, output.clone()).unwrap())
+                .with_clock(move || clock.get());
+        (tracer, output, now)
+    }
+
+    fn event(message: u32, wparam: usize, lparam: isize) -> TraceEvent {
+        TraceEvent {
+            time: 1.5,
+            window: 0x1234,
+            message,
+            wparam,
+            lparam,
+            latency: 0.00025,
+        }
+    }
+
+    #[test]
+    fn filters_parse_names_and_removals() {
+        let filter = KindFilter::parse("paint, size").unwrap();
+        assert!(filter.contains(MessageKind::Paint));
+        assert!(filter.contains(MessageKind::Size));
+        assert!(!filter.contains(MessageKind::Create));
+
+        let filter = KindFilter::parse("all,-mouse_move").unwrap();
+        assert_eq!(filter, KindFilter::all().without(MessageKind::MouseMove));
+        assert_eq!(KindFilter::parse("").unwrap(), KindFilter::none());
+        assert!(matches!(
+            KindFilter::parse("paint,resize"),
+            Err(TraceConfigError::UnknownKind(kind)) if kind == "resize"
+        ));
+    }
+
+    #[test]
+    fn configs_name_a_format_and_a_path() {
+        let config = TraceConfig::parse("json:trace.jsonl", None).unwrap();
+        assert_eq!(config.format, TraceFormat::JsonLines);
+        assert_eq!(config.path.as_deref(), Some("trace.jsonl"));
+        assert_eq!(config.filter, KindFilter::all());
+
+        let config = TraceConfig::parse("text", Some("destroy")).unwrap();
+        assert_eq!(config.path, None);
+        assert_eq!(
+            config.filter,
+            KindFilter::none().with(MessageKind::Destroy)
+        );
+
+        assert!(matches!(
+            TraceConfig::parse("binary", None),
+            Err(TraceConfigError::MissingPath(_))
+        ));
+        assert!(matches!(
+            TraceConfig::parse("xml:trace.xml", None),
+            Err(TraceConfigError::UnknownFormat(_))
+        ));
+    }
+
+    #[test]
+    fn traces_timestamp_and_latency() {
+        let (mut tracer, output, now) = tracer(TraceFormat::Text);
+        now.set(0.010);
+        let span = tracer.begin(0xAB, WM_PAINT, 0, 0).unwrap();
+        now.set(0.0125);
+        tracer.end(span);
+
+        assert_eq!(
+            output.text(),
+            "      10.000 ms  window 0xab  Paint  took 2.500 ms\n"
+        );
+    }
+
+    #[test]
+    fn filtered_kinds_are_not_traced() {
+        let (tracer, _, _) = tracer(TraceFormat::Text);
+        let mut tracer = tracer
+            .with_filter(KindFilter::all().without(MessageKind::MouseMove));
+        assert!(tracer.begin(1, WM_MOUSEMOVE, 0, 0).is_none());
+        assert!(tracer.begin(1, WM_PAINT, 0, 0).is_some());
+    }
+
+    #[test]
+    fn writes_json_lines() {
+        let output = Shared::default();
+        let mut writer =
+            TraceWriter::new(TraceFormat::JsonLines, output.clone()).unwrap();
+        writer.write(&event(WM_SIZE, 0, 0x0258_0320)).unwrap();
+
+        assert_eq!(
+            output.text(),
+            "{\"time_ms\":1500.000,\"window\":4660,\"kind\":\"size\",\
+             \"message\":5,\"wparam\":0,\"lparam\":39322400,\
+             \"decoded\":\"Size { kind: Restored, width: 800, height: 600 }\",\
+             \"latency_ms\":0.250}\n"
+        );
+        assert_eq!(json_string("a\"b\\c\n"), "\"a\\\"b\\\\c\\u000a\"");
+    }
+
+    #[test]
+    fn binary_traces_round_trip() {
+        let output = Shared::default();
+        let mut writer =
+            TraceWriter::new(TraceFormat::Binary, output.clone()).unwrap();
+        let events = [
+            event(WM_PAINT, 0, 0),
+            // Negative parameters survive the trip, like sign-extended
+            // coordinates.
+            event(WM_MOUSEMOVE, usize::MAX, -1),
+        ];
+        for event in &events {
+            writer.write(event).unwrap();
+        }
+
+        let bytes = output.0.borrow().clone();
+        assert_eq!(bytes.len(), 10 + 2 * RECORD_LEN);
+        assert_eq!(read_binary(&bytes[..]).unwrap(), events);
+
+        assert!(read_binary(&bytes[..bytes.len() - 1]).is_err());
+        assert!(read_binary(&b"NOTATRACE!"[..]).is_err());
+    }
+
+    #[test]
+    fn traced_uses_the_installed_tracer() {
+        let (tracer, output, now) = tracer(TraceFormat::Text);
+        let guard =
+            install(tracer.with_filter(KindFilter::parse("paint").unwrap()));
+
+        let result = traced(7, WM_PAINT, 0, 0, || {
+            // A message sent while handling another one.
+            traced(7, WM_PAINT, 0, 0, || now.set(0.001));
+            now.set(0.003);
+            42
+        });
+        assert_eq!(result, 42);
+        traced(7, WM_SIZE, 0, 0, || ());
+
+        let text = output.text();
+        let lines: Vec<_> = text.lines().collect();
+        // The message that was sent first is written first.
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("took 3.000 ms"));
+        assert!(lines[1].ends_with("took 1.000 ms"));
+
+        drop(guard);
+        traced(7, WM_PAINT, 0, 0, || ());
+        assert_eq!(output.text().lines().count(), 2);
+    }
+}
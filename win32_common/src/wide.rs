@@ -0,0 +1,663 @@
+//! NUL-terminated UTF-16 strings, the text of the `W` family of Win32 APIs.
+//!
+//! Windows text is a sequence of 16 bit units that is usually, but not
+//! always, valid UTF-16: file names in particular can contain unpaired
+//! surrogates. [`WideString`] and [`WideStr`] keep any such sequence as it
+//! is, and [`WideStr::to_os_string`] converts it without losing anything,
+//! like `std::os::windows::ffi::OsStringExt` does. Only the conversion to a
+//! Rust `String` has to pick between failing and replacing them.
+
+use std::{
+    borrow::Borrow,
+    ffi::{OsStr, OsString},
+    fmt,
+    ops::Deref,
+};
+
+#[cfg(windows)]
+use windows::Win32::{
+    Foundation::{PSTR, PWSTR},
+    Globalization::{
+        MultiByteToWideChar, WideCharToMultiByte, CP_ACP,
+        MULTI_BYTE_TO_WIDE_CHAR_FLAGS,
+    },
+};
+
+/// The string contains a NUL character, which would end it early.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NulError {
+    /// The index of the first NUL, in UTF-16 units.
+    pub position: usize,
+}
+
+impl fmt::Display for NulError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NUL character at position {}", self.position)
+    }
+}
+
+impl std::error::Error for NulError {}
+
+/// The string contains half of a surrogate pair without the other half, so
+/// it isn't valid UTF-16 and has no UTF-8 equivalent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoneSurrogate {
+    /// The index of the surrogate, in UTF-16 units.
+    pub position: usize,
+    pub unit: u16,
+}
+
+impl fmt::Display for LoneSurrogate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unpaired surrogate {:#06x} at position {}",
+            self.unit, self.position
+        )
+    }
+}
+
+impl std::error::Error for LoneSurrogate {}
+
+/// A borrowed NUL-terminated UTF-16 string, which is to [`WideString`] what
+/// `CStr` is to `CString`.
+///
+/// It never contains a NUL before the terminating one.
+#[derive(PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct WideStr {
+    // Including the terminating NUL.
+    units: [u16],
+}
+
+impl WideStr {
+    /// The string `units` holds, which must end with its only NUL.
+    pub fn from_units_with_nul(units: &[u16]) -> Result<&WideStr, NulError> {
+        let position = find_nul(units);
+        match position {
+            Some(position) if position + 1 == units.len() => {
+                // `WideStr` is a transparent wrapper of `[u16]`.
+                Ok(unsafe { &*(units as *const [u16] as *const WideStr) })
+            }
+            Some(position) => Err(NulError { position }),
+            None => Err(NulError {
+                position: units.len(),
+            }),
+        }
+    }
+
+    /// The string starting at `pointer`, up to the first NUL.
+    ///
+    /// # Safety
+    ///
+    /// `pointer` has to point to a NUL-terminated string that stays valid
+    /// and unchanged for `'a`.
+    pub unsafe fn from_ptr<'a>(pointer: *const u16) -> &'a WideStr {
+        let mut len = 0;
+        while *pointer.add(len) != 0 {
+            len += 1;
+        }
+        let units = std::slice::from_raw_parts(pointer, len + 1);
+        &*(units as *const [u16] as *const WideStr)
+    }
+
+    /// The units of the string, without the terminating NUL.
+    pub fn as_units(&self) -> &[u16] {
+        &self.units[..self.units.len() - 1]
+    }
+
+    pub fn as_units_with_nul(&self) -> &[u16] {
+        &self.units
+    }
+
+    pub fn as_ptr(&self) -> *const u16 {
+        self.units.as_ptr()
+    }
+
+    /// The length in UTF-16 units, without the terminating NUL.
+    pub fn len(&self) -> usize {
+        self.units.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The string as UTF-8, unless it contains a lone surrogate.
+    pub fn to_utf8(&self) -> Result<String, LoneSurrogate> {
+        let mut string = String::with_capacity(self.len());
+        for (position, decoded) in decode(self.as_units()) {
+            match decoded {
+                Ok(char) => string.push(char),
+                Err(unit) => return Err(LoneSurrogate { position, unit }),
+            }
+        }
+        Ok(string)
+    }
+
+    /// The string as UTF-8, with lone surrogates replaced by U+FFFD.
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf16_lossy(self.as_units())
+    }
+
+    /// The string as an `OsString`, keeping lone surrogates, so it can be
+    /// turned back into the same string with [`WideString::from_os_str`].
+    pub fn to_os_string(&self) -> OsString {
+        os::from_units(self.as_units())
+    }
+
+    /// A pointer for the `W` APIs that take text. They must not write to
+    /// it, even though `PWSTR` is a mutable pointer.
+    #[cfg(windows)]
+    pub fn as_pwstr(&self) -> PWSTR {
+        PWSTR(self.units.as_ptr() as *mut u16)
+    }
+
+    /// The string in the ANSI code page, NUL-terminated, for the `A` APIs.
+    /// Characters the code page doesn't have are replaced by its default
+    /// character.
+    #[cfg(windows)]
+    pub fn to_ansi(&self) -> Vec<u8> {
+        unsafe {
+            let len = WideCharToMultiByte(
+                CP_ACP,
+                0,
+                self.as_pwstr(),
+                -1,
+                PSTR(std::ptr::null_mut()),
+                0,
+                None,
+                std::ptr::null_mut(),
+            );
+            let mut bytes = vec![0; len.max(1) as usize];
+            WideCharToMultiByte(
+                CP_ACP,
+                0,
+                self.as_pwstr(),
+                -1,
+                PSTR(bytes.as_mut_ptr()),
+                len,
+                None,
+                std::ptr::null_mut(),
+            );
+            bytes
+        }
+    }
+}
+
+impl fmt::Debug for WideStr {
+    /// Like the `Debug` of `str`, with lone surrogates as `\u{d800}`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"")?;
+        for (_, decoded) in decode(self.as_units()) {
+            match decoded {
+                Ok(char) => write!(f, "{}", char.escape_debug())?,
+                Err(unit) => write!(f, "\\u{{{:x}}}", unit)?,
+            }
+        }
+        f.write_str("\"")
+    }
+}
+
+impl fmt::Display for WideStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_string_lossy())
+    }
+}
+
+impl AsRef<WideStr> for WideStr {
+    fn as_ref(&self) -> &WideStr {
+        self
+    }
+}
+
+impl ToOwned for WideStr {
+    type Owned = WideString;
+
+    fn to_owned(&self) -> WideString {
+        WideString {
+            units: self.units.to_vec(),
+        }
+    }
+}
+
+/// An owned NUL-terminated UTF-16 string, for passing text to the `W` APIs
+/// and for keeping the text they return.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct WideString {
+    // Always ends with the only NUL.
+    units: Vec<u16>,
+}
+
+impl WideString {
+    /// `string` in UTF-16, unless it contains a NUL.
+    pub fn new(string: &str) -> Result<WideString, NulError> {
+        WideString::from_units(string.encode_utf16().collect())
+    }
+
+    /// The string of `units`, which may be invalid UTF-16 but must not
+    /// contain a NUL. The terminating one is added.
+    pub fn from_units(mut units: Vec<u16>) -> Result<WideString, NulError> {
+        if let Some(position) = find_nul(&units) {
+            return Err(NulError { position });
+        }
+        units.push(0);
+        Ok(WideString { units })
+    }
+
+    /// The inverse of [`WideStr::to_os_string`]. On Windows every `OsStr`
+    /// is UTF-16 with possibly lone surrogates and converts losslessly;
+    /// elsewhere bytes that `to_os_string` can't have produced are replaced
+    /// by U+FFFD.
+    pub fn from_os_str(string: &OsStr) -> Result<WideString, NulError> {
+        WideString::from_units(os::to_units(string))
+    }
+
+    /// Copies the string starting at `pointer`, up to the first NUL.
+    ///
+    /// # Safety
+    ///
+    /// `pointer` has to point to a NUL-terminated string.
+    #[cfg(windows)]
+    pub unsafe fn from_pwstr(pointer: PWSTR) -> WideString {
+        WideStr::from_ptr(pointer.0).to_owned()
+    }
+
+    /// Copies the string in the ANSI code page starting at `pointer`, up
+    /// to the first NUL, as UTF-16.
+    ///
+    /// # Safety
+    ///
+    /// `pointer` has to point to a NUL-terminated string.
+    #[cfg(windows)]
+    pub unsafe fn from_pstr(pointer: PSTR) -> WideString {
+        let flags = MULTI_BYTE_TO_WIDE_CHAR_FLAGS(0);
+        let len = MultiByteToWideChar(
+            CP_ACP,
+            flags,
+            pointer,
+            -1,
+            PWSTR(std::ptr::null_mut()),
+            0,
+        );
+        let mut units = vec![0; len.max(1) as usize];
+        MultiByteToWideChar(
+            CP_ACP,
+            flags,
+            pointer,
+            -1,
+            PWSTR(units.as_mut_ptr()),
+            len,
+        );
+        WideStr::from_ptr(units.as_ptr()).to_owned()
+    }
+
+    pub fn as_wide_str(&self) -> &WideStr {
+        self
+    }
+
+    /// The units of the string, without the terminating NUL.
+    pub fn into_units(mut self) -> Vec<u16> {
+        self.units.pop();
+        self.units
+    }
+}
+
+impl Default for WideString {
+    fn default() -> Self {
+        WideString { units: vec![0] }
+    }
+}
+
+impl Deref for WideString {
+    type Target = WideStr;
+
+    fn deref(&self) -> &WideStr {
+        // Holds the invariant of `WideStr`.
+        unsafe { &*(self.units.as_slice() as *const [u16] as *const WideStr) }
+    }
+}
+
+impl Borrow<WideStr> for WideString {
+    fn borrow(&self) -> &WideStr {
+        self
+    }
+}
+
+impl AsRef<WideStr> for WideString {
+    fn as_ref(&self) -> &WideStr {
+        self
+    }
+}
+
+impl From<&WideStr> for WideString {
+    fn from(string: &WideStr) -> Self {
+        string.to_owned()
+    }
+}
+
+impl TryFrom<&str> for WideString {
+    type Error = NulError;
+
+    fn try_from(string: &str) -> Result<Self, NulError> {
+        WideString::new(string)
+    }
+}
+
+impl fmt::Debug for WideString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_wide_str(), f)
+    }
+}
+
+impl fmt::Display for WideString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_wide_str(), f)
+    }
+}
+
+fn find_nul(units: &[u16]) -> Option<usize> {
+    units.iter().position(|&unit| unit == 0)
+}
+
+// Decodes `units` into characters and lone surrogates, with the position of
+// each.
+fn decode(
+    units: &[u16],
+) -> impl Iterator<Item = (usize, Result<char, u16>)> + '_ {
+    let mut position = 0;
+    char::decode_utf16(units.iter().copied()).map(move |decoded| {
+        let at = position;
+        let decoded = decoded.map_err(|error| error.unpaired_surrogate());
+        position += decoded.map_or(1, char::len_utf16);
+        (at, decoded)
+    })
+}
+
+#[cfg(target_os = "windows")]
+mod os {
+    use std::{
+        ffi::{OsStr, OsString},
+        os::windows::ffi::{OsStrExt, OsStringExt},
+    };
+
+    pub fn from_units(units: &[u16]) -> OsString {
+        OsString::from_wide(units)
+    }
+
+    pub fn to_units(string: &OsStr) -> Vec<u16> {
+        string.encode_wide().collect()
+    }
+}
+
+// Anywhere else an `OsString` is bytes. The units are kept as WTF-8, the
+// superset of UTF-8 that also encodes lone surrogates, which is also how
+// Windows' `OsString` keeps them.
+#[cfg(not(target_os = "windows"))]
+mod os {
+    use std::ffi::{OsStr, OsString};
+
+    use super::decode;
+
+    const REPLACEMENT: u16 = 0xFFFD;
+
+    pub fn from_units(units: &[u16]) -> OsString {
+        let mut bytes = Vec::with_capacity(units.len());
+        for (_, decoded) in decode(units) {
+            let code_point = match decoded {
+                Ok(char) => char as u32,
+                Err(unit) => unit as u32,
+            };
+            encode_wtf8(code_point, &mut bytes);
+        }
+        to_os_string(bytes)
+    }
+
+    pub fn to_units(string: &OsStr) -> Vec<u16> {
+        let bytes = as_bytes(string);
+        let mut units = Vec::with_capacity(bytes.len());
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            let (code_point, len) = decode_wtf8(rest);
+            rest = &rest[len..];
+            match code_point {
+                Some(code_point) => match char::from_u32(code_point) {
+                    Some(char) => {
+                        let mut buffer = [0; 2];
+                        units.extend_from_slice(char.encode_utf16(&mut buffer));
+                    }
+                    // A surrogate encoded on its own.
+                    None => units.push(code_point as u16),
+                },
+                None => units.push(REPLACEMENT),
+            }
+        }
+        units
+    }
+
+    // Like UTF-8, but surrogates are encoded like any other code point.
+    fn encode_wtf8(code_point: u32, bytes: &mut Vec<u8>) {
+        match code_point {
+            0..=0x7F => bytes.push(code_point as u8),
+            0x80..=0x7FF => bytes.extend_from_slice(&[
+                0xC0 | (code_point >> 6) as u8,
+                0x80 | (code_point & 0x3F) as u8,
+            ]),
+            0x800..=0xFFFF => bytes.extend_from_slice(&[
+                0xE0 | (code_point >> 12) as u8,
+                0x80 | ((code_point >> 6) & 0x3F) as u8,
+                0x80 | (code_point & 0x3F) as u8,
+            ]),
+            _ => bytes.extend_from_slice(&[
+                0xF0 | (code_point >> 18) as u8,
+                0x80 | ((code_point >> 12) & 0x3F) as u8,
+                0x80 | ((code_point >> 6) & 0x3F) as u8,
+                0x80 | (code_point & 0x3F) as u8,
+            ]),
+        }
+    }
+
+    // The code point at the start of `bytes` and how many bytes it takes,
+    // or `None` and one byte for anything that isn't WTF-8.
+    fn decode_wtf8(bytes: &[u8]) -> (Option<u32>, usize) {
+        let first = bytes[0] as u32;
+        let (len, initial, min) = match first {
+            0x00..=0x7F => return (Some(first), 1),
+            0xC0..=0xDF => (2, first & 0x1F, 0x80),
+            0xE0..=0xEF => (3, first & 0x0F, 0x800),
+            0xF0..=0xF4 => (4, first & 0x07, 0x10000),
+            _ => return (None, 1),
+        };
+        if bytes.len() < len {
+            return (None, 1);
+        }
+        let mut code_point = initial;
+        for &byte in &bytes[1..len] {
+            if byte & 0xC0 != 0x80 {
+                return (None, 1);
+            }
+            code_point = (code_point << 6) | (byte & 0x3F) as u32;
+        }
+        // Overlong encodings have a shorter one.
+        if code_point < min || code_point > 0x10FFFF {
+            return (None, 1);
+        }
+        (Some(code_point), len)
+    }
+
+    #[cfg(unix)]
+    fn to_os_string(bytes: Vec<u8>) -> OsString {
+        use std::os::unix::ffi::OsStringExt;
+        OsString::from_vec(bytes)
+    }
+
+    #[cfg(unix)]
+    fn as_bytes(string: &OsStr) -> &[u8] {
+        use std::os::unix::ffi::OsStrExt;
+        string.as_bytes()
+    }
+
+    #[cfg(not(unix))]
+    fn to_os_string(bytes: Vec<u8>) -> OsString {
+        String::from_utf8_lossy(&bytes).into_owned().into()
+    }
+
+    #[cfg(not(unix))]
+    fn as_bytes(string: &OsStr) -> &[u8] {
+        string.to_str().map_or(&[], str::as_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A small xorshift generator, so the properties below are checked
+    // against the same strings on every run.
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, end: u64) -> u64 {
+            self.next() % end
+        }
+
+        // Any character but NUL, weighted towards the interesting ranges.
+        fn char(&mut self) -> char {
+            let code_point = match self.below(4) {
+                0 => 1 + self.below(0x7F),
+                1 => 0x80 + self.below(0x800 - 0x80),
+                2 => 0x800 + self.below(0x10000 - 0x800),
+                _ => 0x10000 + self.below(0x110000 - 0x10000),
+            } as u32;
+            char::from_u32(code_point).unwrap_or('\u{FFFD}')
+        }
+
+        fn string(&mut self) -> String {
+            let len = self.below(24);
+            (0..len).map(|_| self.char()).collect()
+        }
+
+        // Any units but NUL, often surrogates whether paired or not.
+        fn units(&mut self) -> Vec<u16> {
+            let len = self.below(24);
+            (0..len)
+                .map(|_| match self.below(3) {
+                    0 => 0xD800 + self.below(0x800) as u16,
+                    _ => 1 + self.below(0xFFFF) as u16,
+                })
+                .collect()
+        }
+    }
+
+    const CASES: usize = 2000;
+
+    #[test]
+    fn strings_round_trip_through_utf16() {
+        let mut random = Random(0x5EED_1234_ABCD_0001);
+        for _ in 0..CASES {
+            let string = random.string();
+            let wide = WideString::new(&string).unwrap();
+
+            let units: Vec<u16> = string.encode_utf16().collect();
+            assert_eq!(wide.as_units(), units.as_slice());
+            assert_eq!(wide.as_units_with_nul().last(), Some(&0));
+            assert_eq!(wide.to_utf8().as_deref(), Ok(string.as_str()));
+            assert_eq!(wide.to_string(), string);
+        }
+    }
+
+    #[test]
+    fn any_units_round_trip_through_os_strings() {
+        let mut random = Random(0x5EED_1234_ABCD_0002);
+        for _ in 0..CASES {
+            let units = random.units();
+            let wide = WideString::from_units(units.clone()).unwrap();
+
+            let os_string = wide.to_os_string();
+            let back = WideString::from_os_str(&os_string).unwrap();
+            assert_eq!(back.as_units(), units.as_slice(), "{:?}", wide);
+            // Valid UTF-16 is UTF-8 as an `OsString` too.
+            if let Ok(string) = String::from_utf16(&units) {
+                assert_eq!(os_string.to_str(), Some(string.as_str()));
+            }
+        }
+    }
+
+    #[test]
+    fn only_lone_surrogates_fail_to_convert() {
+        let mut random = Random(0x5EED_1234_ABCD_0003);
+        for _ in 0..CASES {
+            let units = random.units();
+            let wide = WideString::from_units(units.clone()).unwrap();
+
+            assert_eq!(
+                wide.to_string_lossy(),
+                String::from_utf16_lossy(&units)
+            );
+            match wide.to_utf8() {
+                Ok(string) => {
+                    assert_eq!(String::from_utf16(&units).ok(), Some(string))
+                }
+                Err(LoneSurrogate { position, unit }) => {
+                    assert_eq!(units[position], unit);
+                    assert!(String::from_utf16(&units).is_err());
+                    assert!(
+                        String::from_utf16(&units[..position]).is_ok(),
+                        "{:?}",
+                        wide
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lone_surrogates_are_kept() {
+        let wide = WideString::from_units(vec![0x61, 0xD800, 0x62]).unwrap();
+        assert_eq!(
+            wide.to_utf8(),
+            Err(LoneSurrogate {
+                position: 1,
+                unit: 0xD800
+            })
+        );
+        assert_eq!(wide.to_string_lossy(), "a\u{FFFD}b");
+        assert_eq!(format!("{:?}", wide), "\"a\\u{d800}b\"");
+    }
+
+    #[test]
+    fn nul_characters_are_rejected() {
+        assert_eq!(
+            WideString::new("File\0Path"),
+            Err(NulError { position: 4 })
+        );
+        assert_eq!(
+            WideStr::from_units_with_nul(&[0x61, 0x62]),
+            Err(NulError { position: 2 })
+        );
+        assert_eq!(
+            WideStr::from_units_with_nul(&[0x61, 0, 0x62, 0]),
+            Err(NulError { position: 1 })
+        );
+    }
+
+    #[test]
+    fn borrowed_strings_end_at_the_first_nul() {
+        let units = [0x46, 0x69, 0x6C, 0x65, 0];
+        let borrowed = WideStr::from_units_with_nul(&units).unwrap();
+        assert_eq!(borrowed.len(), 4);
+        assert_eq!(borrowed.to_owned(), WideString::new("File").unwrap());
+
+        let text = [0x68, 0x69, 0, 0x21, 0];
+        let from_pointer = unsafe { WideStr::from_ptr(text.as_ptr()) };
+        assert_eq!(from_pointer.to_utf8().unwrap(), "hi");
+        assert!(WideString::default().is_empty());
+    }
+}
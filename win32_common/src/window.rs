@@ -0,0 +1,435 @@
+use std::{
+    ffi::c_void,
+    fmt,
+    ops::BitOr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+#[cfg(windows)]
+use windows::Win32::{
+    Foundation::{E_INVALIDARG, HWND, PWSTR},
+    System::LibraryLoader::GetModuleHandleW,
+    UI::WindowsAndMessaging::{
+        CreateWindowExW, LoadCursorW, RegisterClassW, CW_USEDEFAULT, IDC_ARROW,
+        IDC_CROSS, IDC_HAND, IDC_IBEAM, IDC_WAIT, WNDCLASSW, WNDCLASS_STYLES,
+        WNDPROC, WS_OVERLAPPEDWINDOW, WS_VISIBLE,
+    },
+};
+
+#[cfg(windows)]
+use crate::wide::WideString;
+
+/// Lets the system pick the size of the window, the same value as Win32's
+/// `CW_USEDEFAULT`.
+pub const USE_DEFAULT: i32 = i32::MIN;
+
+/// The longest class name `RegisterClass` accepts.
+// https://docs.microsoft.com/en-us/windows/win32/api/winuser/ns-winuser-wndclassa
+pub const MAX_CLASS_NAME_LEN: usize = 256;
+
+// Every generated class name gets its own number, so several windows created
+// by the same process never collide in `RegisterClass`.
+static NEXT_CLASS_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// The cursor shown while the mouse hovers over the client area.
+// https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-loadcursorw
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Cursor {
+    #[default]
+    Arrow,
+    Cross,
+    Hand,
+    IBeam,
+    Wait,
+}
+
+/// Window class styles, mirroring the `CS_*` constants.
+// https://docs.microsoft.com/en-us/windows/win32/winmsg/window-class-styles
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClassStyle(u32);
+
+impl ClassStyle {
+    /// Redraws the entire window when the height of the client area changes.
+    pub const VREDRAW: ClassStyle = ClassStyle(0x0001);
+    /// Redraws the entire window when the width of the client area changes.
+    pub const HREDRAW: ClassStyle = ClassStyle(0x0002);
+    /// Sends double-click messages to the window procedure.
+    pub const DBLCLKS: ClassStyle = ClassStyle(0x0008);
+    /// Allocates a unique device context for each window in the class.
+    pub const OWNDC: ClassStyle = ClassStyle(0x0020);
+
+    pub const fn empty() -> Self {
+        ClassStyle(0)
+    }
+
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    pub const fn contains(self, other: ClassStyle) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl Default for ClassStyle {
+    fn default() -> Self {
+        ClassStyle::HREDRAW | ClassStyle::VREDRAW
+    }
+}
+
+impl BitOr for ClassStyle {
+    type Output = ClassStyle;
+
+    fn bitor(self, rhs: ClassStyle) -> ClassStyle {
+        ClassStyle(self.0 | rhs.0)
+    }
+}
+
+/// Reasons a [`WindowBuilder`] can fail to produce a window.
+#[derive(Debug)]
+pub enum BuildError {
+    /// The window title contains a NUL character, which would end it early.
+    TitleContainsNul,
+    /// The class name is empty or contains a NUL character.
+    InvalidClassName,
+    /// The class name is longer than [`MAX_CLASS_NAME_LEN`].
+    ClassNameTooLong(usize),
+    /// A width or height that is neither positive nor [`USE_DEFAULT`].
+    InvalidSize { width: i32, height: i32 },
+    /// `RegisterClassW` failed.
+    #[cfg(windows)]
+    RegisterClass(windows::core::Error),
+    /// `CreateWindowExW` failed.
+    #[cfg(windows)]
+    CreateWindow(windows::core::Error),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::TitleContainsNul => {
+                write!(f, "window title contains a NUL character")
+            }
+            BuildError::InvalidClassName => {
+                write!(f, "class name is empty or contains a NUL character")
+            }
+            BuildError::ClassNameTooLong(len) => write!(
+                f,
+                "class name is {} characters long, the maximum is {}",
+                len, MAX_CLASS_NAME_LEN
+            ),
+            BuildError::InvalidSize { width, height } => {
+                write!(f, "invalid window size {}x{}", width, height)
+            }
+            #[cfg(windows)]
+            BuildError::RegisterClass(error) => {
+                write!(f, "failed to register the window class: {}", error)
+            }
+            #[cfg(windows)]
+            BuildError::CreateWindow(error) => {
+                write!(f, "failed to create the window: {}", error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+#[cfg(windows)]
+impl From<BuildError> for windows::core::Error {
+    fn from(error: BuildError) -> Self {
+        match error {
+            BuildError::RegisterClass(error)
+            | BuildError::CreateWindow(error) => error,
+            _ => E_INVALIDARG.into(),
+        }
+    }
+}
+
+/// The validated options a window is created with, with every default
+/// resolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WindowOptions {
+    pub title: String,
+    pub class_name: String,
+    pub width: i32,
+    pub height: i32,
+    pub cursor: Cursor,
+    pub class_style: ClassStyle,
+}
+
+/// Registers a window class and creates a visible overlapped window of that
+/// class, replacing the `WNDCLASSA` + `RegisterClassA` + `CreateWindowExA`
+/// sequence every module used to spell out. Both use the `W` APIs, so
+/// titles can be any Unicode text.
+pub struct WindowBuilder {
+    title: String,
+    class_name: Option<String>,
+    size: Option<(i32, i32)>,
+    cursor: Cursor,
+    class_style: ClassStyle,
+    user_data: *const c_void,
+}
+
+impl WindowBuilder {
+    pub fn new(title: &str) -> Self {
+        WindowBuilder {
+            title: title.to_owned(),
+            class_name: None,
+            size: None,
+            cursor: Cursor::default(),
+            class_style: ClassStyle::default(),
+            user_data: std::ptr::null(),
+        }
+    }
+
+    /// Uses a fixed class name instead of a generated one.
+    pub fn class_name(mut self, class_name: &str) -> Self {
+        self.class_name = Some(class_name.to_owned());
+        self
+    }
+
+    /// The initial width and height of the window. Either can be
+    /// [`USE_DEFAULT`].
+    pub fn size(mut self, width: i32, height: i32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    pub fn cursor(mut self, cursor: Cursor) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    pub fn class_style(mut self, class_style: ClassStyle) -> Self {
+        self.class_style = class_style;
+        self
+    }
+
+    /// A pointer to arbitrary data, passed to the window procedure as
+    /// `CREATESTRUCT::lpCreateParams` of `WM_NCCREATE` and `WM_CREATE`.
+    pub fn user_data(mut self, user_data: *const c_void) -> Self {
+        self.user_data = user_data;
+        self
+    }
+
+    /// Validates the builder and resolves every option that was left unset.
+    pub fn options(&self) -> Result<WindowOptions, BuildError> {
+        if self.title.contains('\0') {
+            return Err(BuildError::TitleContainsNul);
+        }
+
+        let class_name = match &self.class_name {
+            Some(class_name) => {
+                validate_class_name(class_name)?;
+                class_name.clone()
+            }
+            None => generate_class_name(),
+        };
+
+        let (width, height) = self.size.unwrap_or((USE_DEFAULT, USE_DEFAULT));
+        if !is_valid_extent(width) || !is_valid_extent(height) {
+            return Err(BuildError::InvalidSize { width, height });
+        }
+
+        Ok(WindowOptions {
+            title: self.title.clone(),
+            class_name,
+            width,
+            height,
+            cursor: self.cursor,
+            class_style: self.class_style,
+        })
+    }
+}
+
+#[cfg(windows)]
+impl WindowBuilder {
+    /// Registers the window class with `wndproc` as its window procedure
+    /// and creates the window.
+    pub fn build(self, wndproc: WNDPROC) -> Result<HWND, BuildError> {
+        let options = self.options()?;
+
+        // Both strings were validated by `options`.
+        let class_name = WideString::new(&options.class_name).unwrap();
+        let title = WideString::new(&options.title).unwrap();
+
+        unsafe {
+            // A handle to the file used to create the calling process.
+            // https://docs.microsoft.com/en-us/windows/win32/api/libloaderapi/nf-libloaderapi-getmodulehandlew
+            let instance = GetModuleHandleW(None);
+            debug_assert!(instance.0 != 0);
+
+            // https://docs.microsoft.com/en-us/windows/win32/api/winuser/ns-winuser-wndclassw
+            let wc = WNDCLASSW {
+                hInstance: instance,
+                lpszClassName: class_name.as_pwstr(),
+                lpfnWndProc: Some(wndproc),
+                style: WNDCLASS_STYLES(options.class_style.bits()),
+                hCursor: LoadCursorW(None, cursor_resource(options.cursor)),
+                ..Default::default()
+            };
+
+            // If the function succeeds, the return value is a class atom that
+            // uniquely identifies the class being registered.
+            if RegisterClassW(&wc) == 0 {
+                return Err(BuildError::RegisterClass(
+                    windows::core::Error::from_win32(),
+                ));
+            }
+
+            // https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-createwindowexw
+            let handle = CreateWindowExW(
+                Default::default(),
+                class_name.as_pwstr(),
+                title.as_pwstr(),
+                WS_OVERLAPPEDWINDOW | WS_VISIBLE,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                options.width,
+                options.height,
+                None,
+                None,
+                instance,
+                self.user_data,
+            );
+
+            if handle.0 == 0 {
+                return Err(BuildError::CreateWindow(
+                    windows::core::Error::from_win32(),
+                ));
+            }
+
+            Ok(handle)
+        }
+    }
+}
+
+#[cfg(windows)]
+fn cursor_resource(cursor: Cursor) -> PWSTR {
+    match cursor {
+        Cursor::Arrow => IDC_ARROW,
+        Cursor::Cross => IDC_CROSS,
+        Cursor::Hand => IDC_HAND,
+        Cursor::IBeam => IDC_IBEAM,
+        Cursor::Wait => IDC_WAIT,
+    }
+}
+
+/// The class name for the `id`-th generated window class.
+pub fn class_name_for(id: usize) -> String {
+    format!("win32_common.window.{}", id)
+}
+
+/// A class name that hasn't been handed out before in this process.
+pub fn generate_class_name() -> String {
+    class_name_for(NEXT_CLASS_ID.fetch_add(1, Ordering::Relaxed))
+}
+
+fn validate_class_name(class_name: &str) -> Result<(), BuildError> {
+    if class_name.is_empty() || class_name.contains('\0') {
+        return Err(BuildError::InvalidClassName);
+    }
+    if class_name.len() > MAX_CLASS_NAME_LEN {
+        return Err(BuildError::ClassNameTooLong(class_name.len()));
+    }
+    Ok(())
+}
+
+fn is_valid_extent(extent: i32) -> bool {
+    extent == USE_DEFAULT || extent > 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unset_options_resolve_to_defaults() {
+        let options = WindowBuilder::new("title").options().unwrap();
+
+        assert_eq!(options.title, "title");
+        assert_eq!(options.width, USE_DEFAULT);
+        assert_eq!(options.height, USE_DEFAULT);
+        assert_eq!(options.cursor, Cursor::Arrow);
+        assert_eq!(
+            options.class_style,
+            ClassStyle::HREDRAW | ClassStyle::VREDRAW
+        );
+        assert!(options.class_name.starts_with("win32_common.window."));
+    }
+
+    #[test]
+    fn explicit_options_are_kept() {
+        let options = WindowBuilder::new("clock")
+            .class_name("clock_class")
+            .size(640, USE_DEFAULT)
+            .cursor(Cursor::Cross)
+            .class_style(ClassStyle::OWNDC)
+            .options()
+            .unwrap();
+
+        assert_eq!(options.class_name, "clock_class");
+        assert_eq!((options.width, options.height), (640, USE_DEFAULT));
+        assert_eq!(options.cursor, Cursor::Cross);
+        assert_eq!(options.class_style, ClassStyle::OWNDC);
+    }
+
+    #[test]
+    fn title_with_nul_is_rejected() {
+        let result = WindowBuilder::new("ti\0tle").options();
+        assert!(matches!(result, Err(BuildError::TitleContainsNul)));
+    }
+
+    #[test]
+    fn invalid_class_names_are_rejected() {
+        for name in ["", "a\0b"] {
+            let result = WindowBuilder::new("t").class_name(name).options();
+            assert!(matches!(result, Err(BuildError::InvalidClassName)));
+        }
+
+        let long = "c".repeat(MAX_CLASS_NAME_LEN + 1);
+        let result = WindowBuilder::new("t").class_name(&long).options();
+        assert!(matches!(result, Err(BuildError::ClassNameTooLong(257))));
+
+        let longest = "c".repeat(MAX_CLASS_NAME_LEN);
+        assert!(WindowBuilder::new("t")
+            .class_name(&longest)
+            .options()
+            .is_ok());
+    }
+
+    #[test]
+    fn non_positive_sizes_are_rejected() {
+        for (width, height) in [(0, 100), (100, 0), (-1, 100), (100, -5)] {
+            let result = WindowBuilder::new("t").size(width, height).options();
+            assert!(matches!(
+                result,
+                Err(BuildError::InvalidSize { width: w, height: h })
+                    if (w, h) == (width, height)
+            ));
+        }
+    }
+
+    #[test]
+    fn generated_class_names_are_unique() {
+        let first = WindowBuilder::new("t").options().unwrap().class_name;
+        let second = WindowBuilder::new("t").options().unwrap().class_name;
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn class_name_for_is_stable() {
+        assert_eq!(class_name_for(0), "win32_common.window.0");
+        assert_eq!(class_name_for(42), "win32_common.window.42");
+    }
+
+    #[test]
+    fn class_style_combines_flags() {
+        let style = ClassStyle::HREDRAW | ClassStyle::OWNDC;
+        assert_eq!(style.bits(), 0x0022);
+        assert!(style.contains(ClassStyle::OWNDC));
+        assert!(!style.contains(ClassStyle::VREDRAW));
+        assert_eq!(ClassStyle::empty().bits(), 0);
+    }
+}
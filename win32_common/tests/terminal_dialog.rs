@@ -0,0 +1,262 @@
+//! Picking files in a terminal by typing commands into a
+//! [`TerminalDialogs`] that browses a folder made for the test, and
+//! answering message boxes there.
+
+use std::{fs, io::Cursor, path::PathBuf};
+
+use win32_common::{
+    Buttons, DialogError, DialogResult, FileDialog, Icon, MessageBox,
+    SaveChoice, SaveDialog, TerminalDialogs,
+};
+
+type Terminal = TerminalDialogs<Cursor<Vec<u8>>, Vec<u8>>;
+
+// A fresh folder holding:
+//
+//   exports/
+//     old.png
+//   traces/
+//   clock.png
+//   clock.trace
+//   Notes.txt
+fn folder(name: &str) -> PathBuf {
+    let folder = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+        .join("terminal_dialog")
+        .join(name);
+    if folder.exists() {
+        fs::remove_dir_all(&folder).unwrap();
+    }
+    fs::create_dir_all(folder.join("exports")).unwrap();
+    fs::create_dir_all(folder.join("traces")).unwrap();
+    for file in ["exports/old.png", "clock.png", "clock.trace", "Notes.txt"] {
+        fs::write(folder.join(file), file).unwrap();
+    }
+    folder
+}
+
+fn terminal(input: &str) -> Terminal {
+    TerminalDialogs::new(Cursor::new(input.as_bytes().to_vec()), Vec::new())
+}
+
+fn output(terminal: &Terminal) -> String {
+    String::from_utf8(terminal.output().clone()).unwrap()
+}
+
+fn open(
+    dialog: FileDialog,
+    input: &str,
+) -> (Result<Vec<PathBuf>, DialogError>, String) {
+    let mut terminal = terminal(input);
+    let result = dialog.show_with(&mut terminal);
+    (result, output(&terminal))
+}
+
+fn save(
+    dialog: SaveDialog,
+    input: &str,
+) -> (Result<SaveChoice, DialogError>, String) {
+    let mut terminal = terminal(input);
+    let result = dialog.show_with(&mut terminal);
+    (result, output(&terminal))
+}
+
+#[test]
+fn folders_come_first_then_files_by_name() {
+    let folder = folder("listing");
+    let (result, output) = open(FileDialog::new().folder(&folder), "q\n");
+
+    assert!(result.unwrap_err().is_cancelled());
+    let listing = format!(
+        "\nOpen\nFolder: {}\n\
+         \x20   1  exports/\n\
+         \x20   2  traces/\n\
+         \x20   3  clock.png\n\
+         \x20   4  clock.trace\n\
+         \x20   5  Notes.txt\n\
+         > ",
+        folder.display()
+    );
+    assert_eq!(output, listing);
+}
+
+#[test]
+fn filters_hide_files_of_other_types() {
+    let folder = folder("filters");
+    let dialog = FileDialog::new()
+        .folder(&folder)
+        .filter("Traces", &["*.trace"])
+        .filter("Text", &["*.TXT"]);
+
+    let (result, output) = open(dialog.clone(), "3\n");
+    assert_eq!(result.unwrap(), [folder.join("clock.trace")]);
+    assert!(output.contains("Type 1 of 2: Traces (*.trace)"));
+    assert!(!output.contains("clock.png"));
+
+    let (result, output) = open(dialog, "type 2\n3\n");
+    assert_eq!(result.unwrap(), [folder.join("Notes.txt")]);
+    assert!(output.contains("Type 2 of 2: Text (*.TXT)"));
+}
+
+#[test]
+fn folders_are_entered_and_left() {
+    let folder = folder("navigation");
+    let dialog = FileDialog::new().folder(&folder);
+
+    let (result, _) = open(dialog.clone(), "1\n1\n");
+    assert_eq!(result.unwrap(), [folder.join("exports/old.png")]);
+
+    let (result, _) = open(dialog.clone(), "cd exports\n..\n3\n");
+    assert_eq!(result.unwrap(), [folder.join("clock.png")]);
+
+    let (result, output) = open(dialog, "cd missing\n9\n3\n");
+    assert_eq!(result.unwrap(), [folder.join("clock.png")]);
+    assert!(output.contains("Can't open"));
+    assert!(output.contains("There is no entry 9."));
+}
+
+#[test]
+fn multi_select_picks_every_marked_file() {
+    let folder = folder("multi_select");
+    let dialog = FileDialog::new().folder(&folder).multi_select(true);
+
+    let (result, output) = open(dialog, "3\n+5\n+4\n+5\n\n");
+    assert_eq!(
+        result.unwrap(),
+        [folder.join("clock.png"), folder.join("clock.trace")]
+    );
+    assert!(output.contains("*   3  clock.png"));
+}
+
+#[test]
+fn folder_pickers_only_list_folders() {
+    let folder = folder("folders");
+    let dialog = FileDialog::new().folder(&folder).pick_folders(true);
+
+    let (result, output) = open(dialog.clone(), "+2\n\n");
+    assert_eq!(result.unwrap(), [folder.join("traces")]);
+    assert!(output.starts_with("\nSelect Folder\n"));
+    assert!(!output.contains("clock.png"));
+
+    let (result, _) = open(dialog, "1\n\n");
+    assert_eq!(result.unwrap(), [folder.join("exports")]);
+}
+
+#[test]
+fn the_end_of_the_input_cancels() {
+    let folder = folder("end_of_input");
+    let (result, output) = open(FileDialog::new().folder(&folder), "x\n");
+
+    assert!(result.unwrap_err().is_cancelled());
+    assert!(output.contains("Unknown command \"x\", type ? for help."));
+}
+
+#[test]
+fn saving_appends_the_extension_of_the_type() {
+    let folder = folder("save_as_type");
+    let dialog = SaveDialog::new()
+        .folder(&folder)
+        .filter("PNG images", &["*.png"])
+        .filter("Traces", &["*.trace"]);
+
+    let (result, _) = save(dialog.clone(), "name export\n");
+    assert_eq!(
+        result.unwrap(),
+        SaveChoice {
+            path: folder.join("export.png"),
+            file_type: Some(0),
+        }
+    );
+
+    let (result, _) = save(dialog, "type 2\n1\nname run 2\n");
+    assert_eq!(
+        result.unwrap(),
+        SaveChoice {
+            path: folder.join("exports/run 2.trace"),
+            file_type: Some(1),
+        }
+    );
+}
+
+#[test]
+fn saving_over_a_file_asks_first() {
+    let folder = folder("overwrite");
+    let dialog = SaveDialog::new().folder(&folder).file_name("clock.png");
+
+    let (result, output) = save(dialog.clone(), "\nn\nname new.png\n");
+    assert_eq!(result.unwrap().path, folder.join("new.png"));
+    assert!(output.contains("File name: clock.png"));
+    assert!(output.contains(&format!(
+        "{} already exists. Replace it? [y/N]",
+        folder.join("clock.png").display()
+    )));
+
+    let (result, _) = save(dialog.clone(), "3\ny\n");
+    assert_eq!(result.unwrap().path, folder.join("clock.png"));
+
+    let (result, output) = save(dialog.overwrite_prompt(false), "\n");
+    assert_eq!(result.unwrap().path, folder.join("clock.png"));
+    assert!(!output.contains("Replace it?"));
+}
+
+#[test]
+fn file_names_stay_in_the_folder() {
+    let folder = folder("file_names");
+    let (result, output) = save(
+        SaveDialog::new().folder(&folder),
+        "name ../escape\n+1\n\nname kept\n",
+    );
+
+    assert_eq!(result.unwrap().path, folder.join("kept"));
+    assert!(output.contains("\"../escape\" isn't a file name"));
+    assert!(output.contains("Only one file can be saved."));
+    assert!(output.contains("Type name F to save as F."));
+}
+
+fn message_box(
+    message: MessageBox,
+    input: &str,
+) -> (Result<DialogResult, DialogError>, String) {
+    let mut terminal = terminal(input);
+    let result = message.show_with(&mut terminal);
+    (result, output(&terminal))
+}
+
+#[test]
+fn message_boxes_press_the_typed_button() {
+    let message = MessageBox::new("Save the trace first?")
+        .caption("Clock")
+        .buttons(Buttons::YesNoCancel)
+        .icon(Icon::Question)
+        .default_button(1);
+
+    let (result, output) = message_box(message.clone(), "y\n");
+    assert_eq!(result.unwrap(), DialogResult::Yes);
+    assert_eq!(
+        output,
+        "\nClock\nQuestion: Save the trace first?\nYes  [No]  Cancel > "
+    );
+
+    let (result, _) = message_box(message.clone(), "\n");
+    assert_eq!(result.unwrap(), DialogResult::No);
+
+    let (result, output) = message_box(message, "maybe\nCANCEL\n");
+    assert_eq!(result.unwrap(), DialogResult::Cancel);
+    assert!(output.contains("Type one of the buttons, or nothing for No."));
+}
+
+#[test]
+fn message_boxes_close_like_escape_does() {
+    let (result, _) = message_box(MessageBox::new("Done"), "");
+    assert_eq!(result.unwrap(), DialogResult::Ok);
+
+    let retry = MessageBox::new("The device was lost.")
+        .buttons(Buttons::RetryCancel)
+        .icon(Icon::Error);
+    let (result, output) = message_box(retry, "");
+    assert_eq!(result.unwrap(), DialogResult::Cancel);
+    assert!(output.starts_with("\nError: The device was lost.\n"));
+
+    let (result, _) =
+        message_box(MessageBox::new("Keep?").buttons(Buttons::YesNo), "");
+    assert!(result.unwrap_err().is_cancelled());
+}